@@ -2,8 +2,18 @@
 
 _status=0
 
+# Features needed by examples with `required-features` in Cargo.toml
+_features="ble,dcf77,rtic"
+
+# Examples for the v2 only, built for the nRF52833
+_v2_examples="rtic_monotonic sound_clap touch_logo"
+
 for example in $(ls examples | sed s/\.rs$//); do
-  output=$(cargo build --target=thumbv6m-none-eabi --example=$example --color=always 2>&1)
+  if [[ " $_v2_examples " == *" $example "* ]]; then
+    output=$(cargo build --target=thumbv7em-none-eabihf --example=$example --no-default-features --features=v2,rtic --color=always 2>&1)
+  else
+    output=$(cargo build --target=thumbv6m-none-eabi --example=$example --features=$_features --color=always 2>&1)
+  fi
   result=$?
 
   if [[ $result == 0 ]]; then
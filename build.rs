@@ -3,16 +3,134 @@ use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
+/// The flash and RAM of the micro:bit's chip, in KiB.
+struct Chip {
+    flash: u32,
+    ram: u32,
+}
+
+/// The v1's nRF51822, with 16K of RAM on most boards.
+const NRF51822: Chip = Chip {
+    flash: 256,
+    ram: 16,
+};
+
+/// The v2's nRF52833.
+const NRF52833: Chip = Chip {
+    flash: 512,
+    ram: 128,
+};
+
+/// The flash and RAM used by a SoftDevice, in KiB.
+struct SoftDevice {
+    flash: u32,
+    ram: u32,
+}
+
+/// S110 8.0, a BLE peripheral stack.
+const S110: SoftDevice = SoftDevice { flash: 96, ram: 8 };
+
+/// S130 2.0, a BLE central and peripheral stack.
+const S130: SoftDevice = SoftDevice { flash: 108, ram: 8 };
+
+/// Returns the number of pages set by an environment variable, or
+/// `default`.
+fn pages(name: &str, default: u32) -> u32 {
+    println!("cargo:rerun-if-env-changed={}", name);
+    match env::var(name) {
+        Ok(pages) => pages
+            .parse()
+            .unwrap_or_else(|_| panic!("{} isn't a number of pages", name)),
+        Err(_) => default,
+    }
+}
+
 fn main() {
+    let feature = |name: &str| env::var_os(format!("CARGO_FEATURE_{}", name)).is_some();
+
+    let soft_device = match (feature("S110"), feature("S130")) {
+        (false, false) => None,
+        (true, false) => Some(S110),
+        (false, true) => Some(S130),
+        (true, true) => panic!("the s110 and s130 features can't be used together"),
+    };
+    let chip = if feature("V2") {
+        assert!(
+            soft_device.is_none() && !feature("RAM_32K"),
+            "the s110, s130 and ram-32k features are for the v1's nRF51822"
+        );
+        NRF52833
+    } else if feature("RAM_32K") {
+        Chip {
+            ram: 32,
+            ..NRF51822
+        }
+    } else {
+        NRF51822
+    };
+    // The nRF52833's pages are 4K
+    let page_size = if feature("V2") { 4 } else { 1 };
+
+    // Pages at the end of flash, kept out of the program for
+    // microbit::storage, only when asked for
+    let storage_pages = pages("MICROBIT_STORAGE_PAGES", 0);
+    let fs_pages = pages("MICROBIT_FS_PAGES", 0);
+    assert!(storage_pages != 1, "the key-value store needs two pages");
+    assert!(fs_pages != 1, "the file system needs two pages");
+
+    let (flash_start, ram_start) = soft_device.as_ref().map_or((0, 0), |sd| (sd.flash, sd.ram));
+    let storage_size = (storage_pages + fs_pages) * page_size;
+    let flash_len = chip.flash - storage_size - flash_start;
+
+    let mut reserved = if storage_size == 0 {
+        "no flash is reserved for microbit::storage".to_string()
+    } else {
+        format!(
+            "the last {}K of flash are reserved for microbit::storage",
+            storage_size
+        )
+    };
+    if soft_device.is_some() {
+        reserved += &format!(
+            ",\n     the first {}K of flash and {}K of RAM for a SoftDevice",
+            flash_start, ram_start
+        );
+    }
+
     // Put the linker script somewhere the linker can find it
     let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
-    File::create(out.join("memory.x"))
-        .unwrap()
-        .write_all(include_bytes!("memory.x"))
-        .unwrap();
+    writeln!(
+        File::create(out.join("memory.x")).unwrap(),
+        "MEMORY
+{{
+  /* NOTE K = KiBi = 1024 bytes */
+  /* Generated by build.rs: {} */
+  FLASH : ORIGIN = 0x{:08x}, LENGTH = {}K
+  RAM : ORIGIN = 0x{:08x}, LENGTH = {}K
+}}",
+        reserved,
+        flash_start * 1024,
+        flash_len,
+        0x2000_0000 + ram_start * 1024,
+        chip.ram - ram_start,
+    )
+    .unwrap();
     println!("cargo:rustc-link-search={}", out.display());
 
-    // Only re-run the build script when memory.x is changed,
-    // instead of when any part of the source code changes.
-    println!("cargo:rerun-if-changed=memory.x");
+    // Tell microbit::storage where its pages are
+    writeln!(
+        File::create(out.join("storage.rs")).unwrap(),
+        "/// The number of flash pages reserved for storage by `memory.x`.
+pub const STORAGE_PAGES: usize = {};
+
+/// The number of flash pages reserved for the file system by `memory.x`.
+pub const FS_PAGES: usize = {};",
+        storage_pages,
+        fs_pages,
+    )
+    .unwrap();
+
+    // Only re-run the build script when it is changed, instead of when any
+    // part of the source code changes.
+    println!("cargo:rerun-if-changed=build.rs");
 }
@@ -0,0 +1,131 @@
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use cortex_m_rt::entry;
+
+use microbit::{
+    button::{self, Button, Channels},
+    clock::{self, LfClockSource},
+    display::{
+        self, image::GreyscaleImage, AsyncDisplay, Display, MicrobitDisplayTimer, SharedDisplay,
+    },
+    display_pins, executor,
+    gpio::DisplayPins,
+    hal::gpio::p0::Parts as P0Parts,
+    pac::{self, interrupt, TIMER1},
+    serial::{self, AsyncSerial},
+    time::{self, ExtU32, RateExtU32},
+};
+
+const HEART: GreyscaleImage = GreyscaleImage::new(&[
+    [0, 7, 0, 7, 0],
+    [7, 9, 7, 9, 7],
+    [7, 9, 9, 9, 7],
+    [0, 7, 9, 7, 0],
+    [0, 0, 7, 0, 0],
+]);
+
+const SMILE: GreyscaleImage = GreyscaleImage::new(&[
+    [0, 0, 0, 0, 0],
+    [0, 9, 0, 9, 0],
+    [0, 0, 0, 0, 0],
+    [9, 0, 0, 0, 9],
+    [0, 9, 9, 9, 0],
+]);
+
+// TIMER1 drives the display, RTC1 drives the async timers, and GPIOTE and
+// UART0 wake the button and serial futures.
+
+static LED_PINS: Mutex<RefCell<Option<DisplayPins>>> = Mutex::new(RefCell::new(None));
+static DISPLAY_TIMER: Mutex<RefCell<Option<MicrobitDisplayTimer<TIMER1>>>> =
+    Mutex::new(RefCell::new(None));
+static DISPLAY: SharedDisplay = Mutex::new(RefCell::new(None));
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+    time::init(p.RTC1);
+
+    let gpio = P0Parts::new(p.GPIO);
+    let mut timer = MicrobitDisplayTimer::new(p.TIMER1);
+    let mut pins = display_pins!(gpio);
+    display::initialise_display(&mut timer, &mut pins);
+    cortex_m::interrupt::free(|cs| {
+        *LED_PINS.borrow(cs).borrow_mut() = Some(pins);
+        *DISPLAY_TIMER.borrow(cs).borrow_mut() = Some(timer);
+        *DISPLAY.borrow(cs).borrow_mut() = Some(Display::new());
+    });
+
+    let channels = Channels::new(p.GPIOTE);
+    let mut button_a = Button::new(
+        gpio.p0_17.into_floating_input().degrade(),
+        channels.channel0,
+    );
+    let mut button_b = Button::new(
+        gpio.p0_26.into_floating_input().degrade(),
+        channels.channel1,
+    );
+    let mut serial = AsyncSerial::new(microbit::serial_port!(gpio, p.UART0, 115_200.Hz()));
+    let mut display = AsyncDisplay::new(&DISPLAY);
+
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::TIMER1);
+        pac::NVIC::unmask(pac::Interrupt::RTC1);
+        pac::NVIC::unmask(pac::Interrupt::GPIOTE);
+        pac::NVIC::unmask(pac::Interrupt::UART0);
+    }
+
+    let buttons = async {
+        loop {
+            button_a.wait_for_press().await;
+            display.show_for(&HEART, 1000.millis()).await;
+            button_b.wait_for_press().await;
+            display.show_for(&SMILE, 1000.millis()).await;
+        }
+    };
+
+    let echo = async {
+        let mut buf = [0; 16];
+        loop {
+            let len = serial.read(&mut buf).await;
+            serial.write_all(&buf[..len]).await;
+        }
+    };
+
+    executor::block_on(executor::join(buttons, echo));
+    unreachable!()
+}
+
+#[interrupt]
+fn TIMER1() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(timer) = DISPLAY_TIMER.borrow(cs).borrow_mut().as_mut() {
+            if let Some(pins) = LED_PINS.borrow(cs).borrow_mut().as_mut() {
+                if let Some(d) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+                    display::handle_display_event(d, timer, pins);
+                }
+            }
+        }
+    });
+}
+
+#[interrupt]
+fn RTC1() {
+    time::handle_rtc_interrupt();
+}
+
+#[interrupt]
+fn GPIOTE() {
+    button::handle_gpiote_interrupt();
+}
+
+#[interrupt]
+fn UART0() {
+    serial::handle_uart_interrupt();
+}
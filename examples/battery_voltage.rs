@@ -0,0 +1,49 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    battery::{self, Battery, Threshold},
+    hal::{self, prelude::*, Timer},
+    pac::{self, interrupt},
+    time::RateExtU32,
+};
+
+// Prints the supply voltage on the serial port every second, with a
+// warning once it drops below 2.3V.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+        let mut timer = Timer::new(p.TIMER0);
+        let mut battery = Battery::new(p.ADC);
+
+        battery::set_low_voltage_warning(&p.POWER, Some(Threshold::V2_3));
+        unsafe {
+            pac::NVIC::unmask(pac::Interrupt::POWER_CLOCK);
+        }
+
+        loop {
+            let _ = write!(serial, "{}mV\r\n", battery.millivolts());
+            if battery::low_voltage_warning() {
+                let _ = write!(serial, "low battery\r\n");
+            }
+            timer.delay_ms(1_000_u16);
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
+
+#[interrupt]
+fn POWER_CLOCK() {
+    battery::handle_power_interrupt();
+}
@@ -0,0 +1,63 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+
+use microbit::{
+    ble::{
+        beacon::{Beacon, EddystoneTlm, EddystoneUid, EddystoneUrl},
+        radio::TxPower,
+    },
+    clock::{self, LfClockSource},
+    pac,
+    time::ExtU32,
+};
+
+// Sends Eddystone-UID and Eddystone-URL frames in turn every 500ms, with a
+// TLM frame every tenth advertising event. The CPU sleeps between
+// advertising events, woken up by RTC0.
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    clock::start_hfclk(&p.CLOCK);
+    clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+
+    let mut beacon = Beacon::new(p.RADIO, p.RTC0, &p.FICR);
+    beacon.set_interval(500.millis());
+    beacon.set_tx_power(TxPower::ZerodBm);
+
+    // The signal strength 0m away, roughly the transmit power less 41dBm
+    let tx_power = -41;
+    let uid = EddystoneUid {
+        tx_power,
+        namespace: *b"micro:bit!",
+        instance: *b"\x00\x00\x00\x00\x00\x01",
+    }
+    .advertising_data();
+    let url = EddystoneUrl {
+        tx_power,
+        url: "https://microbit.org/",
+    }
+    .advertising_data()
+    .unwrap();
+
+    loop {
+        let count = beacon.advertising_count();
+        match count % 10 {
+            9 => {
+                let tlm = EddystoneTlm {
+                    battery: 0,
+                    temperature: None,
+                    advertising_count: count,
+                    uptime: beacon.uptime(),
+                };
+                beacon.advertise(&tlm.advertising_data());
+            }
+            0 | 2 | 4 | 6 | 8 => beacon.advertise(&uid),
+            _ => beacon.advertise(&url),
+        }
+    }
+}
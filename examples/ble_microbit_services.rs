@@ -0,0 +1,135 @@
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use cortex_m_rt::entry;
+
+use embedded_hal::digital::v2::InputPin;
+use microbit::{
+    ble::{
+        ad::{AdStructure, AdvertisingData, Flags},
+        att::AttServer,
+        services::{MicrobitServices, ServiceEvent},
+        AdvertisingKind, BlePeripheral,
+    },
+    clock,
+    display::{self, image::GreyscaleImage, Display, Frame, MicrobitDisplayTimer, MicrobitFrame},
+    display_pins,
+    gpio::DisplayPins,
+    hal::gpio::p0::Parts as P0Parts,
+    pac::{self, interrupt, TIMER1},
+};
+
+const NAME: &str = "BBC micro:bit";
+
+// The BLE peripheral uses RADIO and TIMER0, and TIMER1 drives the display.
+// The display interrupt handler is short enough not to upset the radio
+// timing.
+
+static LED_PINS: Mutex<RefCell<Option<DisplayPins>>> = Mutex::new(RefCell::new(None));
+static DISPLAY_TIMER: Mutex<RefCell<Option<MicrobitDisplayTimer<TIMER1>>>> =
+    Mutex::new(RefCell::new(None));
+static DISPLAY: Mutex<RefCell<Option<Display<MicrobitFrame>>>> = Mutex::new(RefCell::new(None));
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    clock::start_hfclk(&p.CLOCK);
+
+    let gpio = P0Parts::new(p.GPIO);
+    let mut timer = MicrobitDisplayTimer::new(p.TIMER1);
+    let mut pins = display_pins!(gpio);
+    display::initialise_display(&mut timer, &mut pins);
+    cortex_m::interrupt::free(|cs| {
+        *LED_PINS.borrow(cs).borrow_mut() = Some(pins);
+        *DISPLAY_TIMER.borrow(cs).borrow_mut() = Some(timer);
+        *DISPLAY.borrow(cs).borrow_mut() = Some(Display::new());
+    });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::TIMER1);
+    }
+
+    let button_a = gpio.p0_17.into_floating_input();
+    let button_b = gpio.p0_26.into_floating_input();
+
+    let mut ble = BlePeripheral::new(p.RADIO, p.TIMER0, &p.PPI, &p.FICR);
+    let mut server = AttServer::new(MicrobitServices::new(NAME));
+
+    let mut data = AdvertisingData::new();
+    data.add(AdStructure::Flags(
+        Flags::LE_GENERAL_DISCOVERABLE | Flags::BR_EDR_NOT_SUPPORTED,
+    ))
+    .unwrap();
+    data.add(AdStructure::CompleteLocalName(NAME)).unwrap();
+
+    let mut frame = MicrobitFrame::const_default();
+    loop {
+        let mut connection =
+            match ble.advertise(AdvertisingKind::Connectable, &data, &AdvertisingData::new()) {
+                Some(connection) => connection,
+                None => continue,
+            };
+        defmt::info!("connected");
+
+        let mut buttons = [false; 2];
+        loop {
+            if ble.connection_event(&mut connection, &mut server).is_err() {
+                break;
+            }
+
+            // The buttons are active low
+            let pressed = [button_a.is_low().unwrap(), button_b.is_low().unwrap()];
+            if pressed[0] != buttons[0] {
+                server.provider_mut().set_button_a(pressed[0].into());
+                let _ = connection.notify(&mut server, MicrobitServices::BUTTON_A_STATE);
+            }
+            if pressed[1] != buttons[1] {
+                server.provider_mut().set_button_b(pressed[1].into());
+                let _ = connection.notify(&mut server, MicrobitServices::BUTTON_B_STATE);
+            }
+            buttons = pressed;
+
+            while let Some(event) = server.provider_mut().poll_event() {
+                match event {
+                    ServiceEvent::LedMatrix => {
+                        let image = GreyscaleImage::new(&server.provider().led_matrix());
+                        frame.set(&image);
+                        cortex_m::interrupt::free(|cs| {
+                            if let Some(d) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+                                d.set_frame(&frame);
+                            }
+                        });
+                    }
+                    ServiceEvent::UartRx => {
+                        // Echo the data back
+                        let mut echo = [0; 20];
+                        let rx = server.provider().uart_rx();
+                        let len = rx.len();
+                        echo[..len].copy_from_slice(rx);
+                        server.provider_mut().set_uart_tx(&echo[..len]).unwrap();
+                        let _ = connection.notify(&mut server, MicrobitServices::UART_TX);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        defmt::info!("disconnected");
+    }
+}
+
+#[interrupt]
+fn TIMER1() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(timer) = DISPLAY_TIMER.borrow(cs).borrow_mut().as_mut() {
+            if let Some(pins) = LED_PINS.borrow(cs).borrow_mut().as_mut() {
+                if let Some(d) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+                    display::handle_display_event(d, timer, pins);
+                }
+            }
+        }
+    });
+}
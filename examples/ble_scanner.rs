@@ -0,0 +1,65 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    ble::{ad::AdStructure, link::AddressKind, scanner::Scanner},
+    clock::{self, LfClockSource},
+    hal,
+    time::RateExtU32,
+};
+
+// Listens for BLE advertising and prints each advertising PDU received on the
+// serial port, with its signal strength and the AD structures it carries.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        clock::start_hfclk(&p.CLOCK);
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+
+        let mut scanner = Scanner::new(p.RADIO, p.RTC0, &p.FICR);
+        loop {
+            let report = match scanner.receive() {
+                Some(report) => report,
+                None => continue,
+            };
+            let advertisement = report.advertisement;
+            let kind = match advertisement.advertiser.kind {
+                AddressKind::Public => "public",
+                AddressKind::Random => "random",
+            };
+            let _ = write!(
+                serial,
+                "ch{} {}dBm {:?} {} ({})",
+                report.channel, report.rssi, advertisement.pdu_type, advertisement.advertiser, kind
+            );
+            for structure in report.structures() {
+                let _ = match structure {
+                    AdStructure::ShortenedLocalName(name)
+                    | AdStructure::CompleteLocalName(name) => {
+                        write!(serial, " name={}", name)
+                    }
+                    AdStructure::ManufacturerSpecificData(company, data) => {
+                        write!(serial, " manufacturer={:04x}:{:02x?}", company, data)
+                    }
+                    AdStructure::TxPowerLevel(power) => write!(serial, " tx_power={}dBm", power),
+                    AdStructure::Flags(_) => Ok(()),
+                    other => write!(serial, " {:02x?}", other),
+                };
+            }
+            let _ = write!(serial, "\r\n");
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+    led,
+    time::ExtU32,
+};
+
+// The same code runs on the v1 and the v2, built with either feature
+#[entry]
+fn main() -> ! {
+    if let Some(board) = Board::take() {
+        let mut timer = Timer::new(board.TIMER0);
+        let mut leds = led::Display::new(board.display_pins);
+        let button_a = board.buttons.button_a;
+
+        let heart = [
+            [0, 1, 0, 1, 0],
+            [1, 0, 1, 0, 1],
+            [1, 0, 0, 0, 1],
+            [0, 1, 0, 1, 0],
+            [0, 0, 1, 0, 0],
+        ];
+
+        let smiley = [
+            [0, 0, 0, 0, 0],
+            [0, 1, 0, 1, 0],
+            [0, 0, 0, 0, 0],
+            [1, 0, 0, 0, 1],
+            [0, 1, 1, 1, 0],
+        ];
+
+        loop {
+            // Show the smiley while button A is pressed
+            let image = if button_a.is_low().unwrap() {
+                smiley
+            } else {
+                heart
+            };
+            leds.display(&mut timer, image, 100.millis());
+        }
+    }
+
+    panic!("End");
+}
@@ -7,63 +7,62 @@ use core::{cell::RefCell, fmt::Write, ops::DerefMut};
 
 use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
-use dcf77::{DCF77Time, SimpleDCF77Decoder};
 use microbit::{
+    clock::{self, LfClockSource, RtcClock},
+    dcf77::{Dcf77Receiver, Event, Polarity},
     hal::{
         self,
-        gpio::{p0::P0_16, Floating, Input},
-        prelude::*,
+        gpio::{Floating, Input, Pin},
+        gpiote::Gpiote,
         uart::{Baudrate, Uart},
     },
     pac::{self, interrupt},
 };
 
-static DCF: Mutex<RefCell<Option<SimpleDCF77Decoder>>> = Mutex::new(RefCell::new(None));
-static DCFPIN: Mutex<RefCell<Option<P0_16<Input<Floating>>>>> = Mutex::new(RefCell::new(None));
-static RTC: Mutex<RefCell<Option<pac::RTC0>>> = Mutex::new(RefCell::new(None));
+static CLOCK: Mutex<RefCell<Option<RtcClock<pac::RTC0>>>> = Mutex::new(RefCell::new(None));
+static GPIOTE: Mutex<RefCell<Option<Gpiote>>> = Mutex::new(RefCell::new(None));
+type DcfPin = Pin<Input<Floating>>;
+
+static DCF: Mutex<RefCell<Option<Dcf77Receiver<DcfPin>>>> = Mutex::new(RefCell::new(None));
 static TX: Mutex<RefCell<Option<Uart<pac::UART0>>>> = Mutex::new(RefCell::new(None));
 
 #[entry]
 fn main() -> ! {
     if let Some(p) = microbit::Peripherals::take() {
         cortex_m::interrupt::free(move |cs| {
-            // TODO: check if there are safe wrappers now
-            p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-
-            while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-
-            /* And then set it back to 0 again, just because ?!? */
-            p.CLOCK.events_lfclkstarted.write(|w| unsafe { w.bits(0) });
+            // Starting the low-frequency clock (needed for RTC to work)
+            clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
             /* Split GPIO pins */
             let gpio = hal::gpio::p0::Parts::new(p.GPIO);
 
-            /* Configure DCF77 receiver GPIO as input */
-            let pin = gpio.p0_16.into_floating_input();
+            /* Configure DCF77 receiver GPIO as input, generating an event on each edge */
+            let pin = gpio.p0_16.into_floating_input().degrade();
+            let gpiote = Gpiote::new(p.GPIOTE);
+            gpiote
+                .channel0()
+                .input_pin(&pin)
+                .toggle()
+                .enable_interrupt();
 
             /* Initialise serial port on the micro:bit */
             let mut serial = microbit::serial_port!(gpio, p.UART0, Baudrate::BAUD115200);
 
             let _ = serial.write_str("\n\rWelcome to the DCF77 decoder demo.\n\r");
             let _ = serial.write_str("If you are within reach of a DCF77 radio clock signal and have a DCF77 receiver connected\n\r");
-            let _ = serial.write_str("you should see a stream of 59 bits appear in a line, followed by thd decoded date and time.\n\r");
+            let _ = serial.write_str("you should see a stream of 59 bits appear in a line, followed by the decoded date and time.\n\r");
             let _ =
                 serial.write_str("If not, please check your hardware, location and reception.\n\r");
-            // TODO: check for safe wrappers
-            p.RTC0.prescaler.write(|w| unsafe { w.bits(327) });
-            p.RTC0.evtenset.write(|w| w.tick().set_bit());
-            p.RTC0.intenset.write(|w| w.tick().set_bit());
-            p.RTC0.tasks_start.write(|w| unsafe { w.bits(1) });
 
-            *RTC.borrow(cs).borrow_mut() = Some(p.RTC0);
+            *CLOCK.borrow(cs).borrow_mut() = Some(RtcClock::new(p.RTC0));
+            *GPIOTE.borrow(cs).borrow_mut() = Some(gpiote);
             *TX.borrow(cs).borrow_mut() = Some(serial);
-            *DCFPIN.borrow(cs).borrow_mut() = Some(pin);
-            *DCF.borrow(cs).borrow_mut() = Some(SimpleDCF77Decoder::new());
+            *DCF.borrow(cs).borrow_mut() = Some(Dcf77Receiver::new(pin, Polarity::ActiveLow));
 
             unsafe {
                 pac::NVIC::unmask(pac::Interrupt::RTC0);
+                pac::NVIC::unmask(pac::Interrupt::GPIOTE);
             }
-            pac::NVIC::unpend(pac::Interrupt::RTC0);
         });
     }
 
@@ -72,59 +71,45 @@ fn main() -> ! {
     }
 }
 
-// Define an interrupt handler, i.e. function to call when interrupt occurs. Here if our timer
-// trips, we'll process data from the DC77 module
+// Count RTC overflows so the clock keeps running
 #[interrupt]
 fn RTC0() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(clock) = CLOCK.borrow(cs).borrow_mut().as_mut() {
+            clock.handle_interrupt();
+        }
+    });
+}
+
+// Define an interrupt handler, i.e. function to call when interrupt occurs. Here if the
+// DCF77 module's output changes, we'll timestamp the edge and decode the signal
+#[interrupt]
+fn GPIOTE() {
     /* Enter critical section */
     cortex_m::interrupt::free(|cs| {
-        if let (Some(rtc), &mut Some(ref mut tx), &mut Some(ref mut pin), &mut Some(ref mut dcf)) = (
-            RTC.borrow(cs).borrow().as_ref(),
+        if let (Some(gpiote), Some(clock), Some(tx), Some(dcf)) = (
+            GPIOTE.borrow(cs).borrow().as_ref(),
+            CLOCK.borrow(cs).borrow_mut().deref_mut(),
             TX.borrow(cs).borrow_mut().deref_mut(),
-            DCFPIN.borrow(cs).borrow_mut().deref_mut(),
             DCF.borrow(cs).borrow_mut().deref_mut(),
         ) {
-            dcf.read_bit(pin.is_low().unwrap());
-            if dcf.bit_faulty() {
-                let _ = tx.write_str("F");
-            } else if dcf.bit_complete() {
-                let bit = dcf.latest_bit();
-                let _ = tx.write_str(if bit { "1" } else { "0" });
-            }
+            gpiote.channel0().reset_events();
 
-            if dcf.end_of_cycle() {
-                let raw_data = dcf.raw_data();
-
-                let dcftime = DCF77Time(raw_data);
-                if let Ok((year, month, day, _)) = dcftime.date() {
-                    let _ = write!(
-                        tx,
-                        "\n\r{:4}-{:02}-{:02} ",
-                        year as usize, month as usize, day as usize
-                    );
-                } else {
-                    let _ = tx.write_str("\n\rXXXX:XX:XX");
+            match dcf.handle_edge(clock) {
+                Some(Event::Bit(bit)) => {
+                    let _ = tx.write_str(if bit { "1" } else { "0" });
                 }
-
-                if let Ok(hours) = dcftime.hours() {
-                    let _ = write!(tx, "{:02}", hours as usize);
-                } else {
-                    let _ = tx.write_str("XX");
+                Some(Event::Error(_)) => {
+                    let _ = tx.write_str("F");
                 }
-
-                let _ = tx.write_str(":");
-
-                if let Ok(minutes) = dcftime.minutes() {
-                    let _ = write!(tx, "{:02}", minutes as usize);
-                } else {
-                    let _ = tx.write_str("XX");
+                Some(Event::Time(time)) => {
+                    let _ = write!(tx, "\n\r{} (unconfirmed)\n\r", time.datetime);
                 }
-
-                let _ = tx.write_str(":");
-
-                let _ = write!(tx, "{:02}\n\r", dcf.seconds() as usize);
+                Some(Event::Synchronised(time)) => {
+                    let _ = write!(tx, "\n\r{} (clock set)\n\r", time.datetime);
+                }
+                None => {}
             }
-            rtc.events_tick.write(|w| unsafe { w.bits(0) });
         }
     });
 }
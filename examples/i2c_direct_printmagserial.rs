@@ -7,7 +7,10 @@ use core::{cell::RefCell, fmt::Write};
 
 use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
-use microbit::pac::{self, interrupt};
+use microbit::{
+    clock::{self, LfClockSource},
+    pac::{self, interrupt},
+};
 
 static RTC: Mutex<RefCell<Option<pac::RTC0>>> = Mutex::new(RefCell::new(None));
 static UART: Mutex<RefCell<Option<pac::UART0>>> = Mutex::new(RefCell::new(None));
@@ -16,12 +19,8 @@ static TWI: Mutex<RefCell<Option<pac::TWI1>>> = Mutex::new(RefCell::new(None));
 #[entry]
 fn main() -> ! {
     if let Some(p) = microbit::Peripherals::take() {
-        p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-
-        while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-
-        /* And then set it back to 0 again, just because ?!? */
-        p.CLOCK.events_lfclkstarted.write(|w| unsafe { w.bits(0) });
+        // Starting the low-frequency clock (needed for RTC to work)
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
         p.GPIO.pin_cnf[24].write(|w| w.pull().pullup().dir().output());
         p.GPIO.pin_cnf[25].write(|w| w.pull().disabled().dir().input());
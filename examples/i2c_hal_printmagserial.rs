@@ -8,6 +8,7 @@ use core::{cell::RefCell, fmt::Write, ops::DerefMut};
 use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
 use microbit::{
+    clock::{self, LfClockSource},
     hal::{
         self, twi,
         uart::{Baudrate, Uart},
@@ -22,10 +23,8 @@ static I2C: Mutex<RefCell<Option<twi::Twi<pac::TWI1>>>> = Mutex::new(RefCell::ne
 #[entry]
 fn main() -> ! {
     if let Some(p) = microbit::Peripherals::take() {
-        // TODO: check if there are safe wrappers for this now
-        p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-        while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-        p.CLOCK.events_lfclkstarted.write(|w| unsafe { w.bits(0) });
+        // Starting the low-frequency clock (needed for RTC to work)
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
         p.RTC0.prescaler.write(|w| unsafe { w.bits(4095) });
         p.RTC0.evtenset.write(|w| w.tick().set_bit());
@@ -9,6 +9,7 @@ use cortex_m_rt::entry;
 use microbit::{
     display_pins,
     hal::{gpio::p0::Parts as P0Parts, prelude::*, Timer},
+    time::ExtU32,
 };
 
 use microbit::led;
@@ -76,12 +77,12 @@ fn main() -> ! {
             [0, 0, 1, 0, 0],
         ];
         loop {
-            leds.display(&mut timer, letter_I, 1000);
-            leds.display(&mut timer, heart, 1000);
-            leds.display(&mut timer, letter_R, 1000);
-            leds.display(&mut timer, letter_u, 1000);
-            leds.display(&mut timer, letter_s, 1000);
-            leds.display(&mut timer, letter_t, 1000);
+            leds.display(&mut timer, letter_I, 1000.millis());
+            leds.display(&mut timer, heart, 1000.millis());
+            leds.display(&mut timer, letter_R, 1000.millis());
+            leds.display(&mut timer, letter_u, 1000.millis());
+            leds.display(&mut timer, letter_s, 1000.millis());
+            leds.display(&mut timer, letter_t, 1000.millis());
             leds.clear();
             timer.delay_ms(250_u32);
         }
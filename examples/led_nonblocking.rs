@@ -10,6 +10,7 @@ use cortex_m::peripheral::Peripherals;
 use cortex_m_rt::entry;
 
 use microbit::{
+    clock::{self, LfClockSource},
     display::{self, image::GreyscaleImage, Display, Frame, MicrobitDisplayTimer, MicrobitFrame},
     display_pins,
     gpio::DisplayPins,
@@ -44,9 +45,7 @@ static DISPLAY: Mutex<RefCell<Option<Display<MicrobitFrame>>>> = Mutex::new(RefC
 fn main() -> ! {
     if let Some(p) = pac::Peripherals::take() {
         // Starting the low-frequency clock (needed for RTC to work)
-        p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-        while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-        p.CLOCK.events_lfclkstarted.reset();
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
         cortex_m::interrupt::free(move |cs| {
             // RTC at 16Hz (32_768 / (2047 + 1))
@@ -10,6 +10,7 @@
 use panic_halt as _;
 
 use microbit::{
+    clock::{self, LfClockSource},
     display::{self, image::GreyscaleImage, Display, Frame, MicrobitDisplayTimer, MicrobitFrame},
     display_pins,
     gpio::DisplayPins,
@@ -46,9 +47,7 @@ const APP: () = {
         let p: pac::Peripherals = cx.device;
 
         // Starting the low-frequency clock (needed for RTC to work)
-        p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-        while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-        p.CLOCK.events_lfclkstarted.reset();
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
         // RTC at 16Hz (32_768 / (2047 + 1))
         // 16Hz; 62.5ms period
@@ -0,0 +1,57 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    button::{Button, Channels},
+    hal, power,
+    time::RateExtU32,
+};
+
+// Prints why the micro:bit started and how many times it has been woken
+// up on the serial port, then enters System OFF until button A or B is
+// pressed.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        let reason = power::reset_reason(&p.POWER);
+        let wakes = if reason.gpio {
+            power::retained(&p.POWER).wrapping_add(1)
+        } else {
+            0
+        };
+        power::set_retained(&p.POWER, wakes);
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let channels = Channels::new(p.GPIOTE);
+        let button_a = Button::new(
+            gpio.p0_17.into_floating_input().degrade(),
+            channels.channel0,
+        );
+        let button_b = Button::new(
+            gpio.p0_26.into_floating_input().degrade(),
+            channels.channel1,
+        );
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+
+        let _ = write!(serial, "{:?}, woken {} times\r\n", reason, wakes);
+
+        // A button still pressed would wake the micro:bit at once
+        while button_a.is_pressed() || button_b.is_pressed() {}
+        // Let the UART finish sending
+        cortex_m::asm::delay(100_000);
+
+        power::wake_on_press(&button_a);
+        power::wake_on_press(&button_b);
+        power::system_off(&p.POWER);
+    }
+
+    loop {
+        power::idle();
+    }
+}
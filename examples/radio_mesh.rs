@@ -0,0 +1,84 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::{fmt::Write, str};
+
+use cortex_m_rt::entry;
+use microbit::{
+    clock,
+    hal::{self, prelude::*, rng::Rng},
+    pac::{self, interrupt},
+    radio::{
+        self,
+        mesh::{Router, BROADCAST, MAX_PACKET_LEN, MAX_PAYLOAD_LEN},
+        Config, Radio,
+    },
+    time::RateExtU32,
+};
+
+// A chat over a mesh of micro:bits: each line typed on the serial port is
+// sent to all the other boards, relayed by those in between, and printed on
+// their serial ports.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        clock::start_hfclk(&p.CLOCK);
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+        let mut rng = Rng::new(p.RNG);
+
+        let mut radio = Radio::new(p.RADIO, Config::default());
+        unsafe {
+            pac::NVIC::unmask(pac::Interrupt::RADIO);
+        }
+        let mut router = Router::new(p.FICR.deviceid[0].read().bits() as u16, rng.random_u16());
+        let _ = write!(serial, "node {:04x}\r\n", router.address());
+
+        let mut line = [0; MAX_PAYLOAD_LEN];
+        let mut line_len = 0;
+        let mut packet = [0; MAX_PACKET_LEN];
+        loop {
+            if let Ok(byte) = serial.read() {
+                let _ = serial.write(byte);
+                if byte == b'\r' {
+                    let _ = serial.write(b'\n');
+                    let len = router
+                        .send(BROADCAST, &line[..line_len], &mut packet)
+                        .unwrap();
+                    radio.send_bytes(&packet[..len]);
+                    line_len = 0;
+                } else if line_len < line.len() {
+                    line[line_len] = byte;
+                    line_len += 1;
+                }
+            }
+
+            if let Some(received) = radio.receive() {
+                let result = router.receive(received.data(), &mut packet);
+                if let Some(message) = result.message {
+                    let text = str::from_utf8(message.payload).unwrap_or("?");
+                    let _ = write!(serial, "{:04x}: {}\r\n", message.source, text);
+                }
+                if let Some(len) = result.relay {
+                    // Wait up to 4ms, so that neighbours relaying the same
+                    // message don't all send at once
+                    cortex_m::asm::delay(u32::from(rng.random_u8()) * 256);
+                    radio.send_bytes(&packet[..len]);
+                }
+            }
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
+
+#[interrupt]
+fn RADIO() {
+    radio::handle_radio_interrupt();
+}
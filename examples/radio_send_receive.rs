@@ -0,0 +1,118 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::{fmt::Write, str};
+
+use cortex_m_rt::entry;
+use microbit::{
+    clock,
+    hal::{self, prelude::*},
+    pac::{self, interrupt},
+    radio::{
+        self,
+        frame::{Datagram, MakeCodePacket, MakeCodeValue},
+        Config, Radio,
+    },
+    time::RateExtU32,
+};
+
+// Talks to micro:bits running MicroPython's `radio` module or MakeCode's
+// `radio` blocks on group 1. Button A sends a message like MicroPython's
+// `radio.send()`, and button B sends a number like MakeCode's
+// `radio.sendNumber()`. Messages received are printed on the serial port.
+
+const GROUP: u8 = 1;
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        clock::start_hfclk(&p.CLOCK);
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+        let button_a = gpio.p0_17.into_floating_input();
+        let button_b = gpio.p0_26.into_floating_input();
+
+        let mut radio = Radio::new(
+            p.RADIO,
+            Config {
+                group: GROUP,
+                ..Config::default()
+            },
+        );
+        unsafe {
+            pac::NVIC::unmask(pac::Interrupt::RADIO);
+        }
+
+        let mut buf = [0; 32];
+        let mut pressed = (false, false);
+        let mut count = 0;
+        loop {
+            // The buttons are active low
+            let a = button_a.is_low().unwrap();
+            let b = button_b.is_low().unwrap();
+            if a && !pressed.0 {
+                let len = Datagram {
+                    group: 0,
+                    payload: b"hello",
+                }
+                .encode(&mut buf)
+                .unwrap();
+                radio.send_bytes(&buf[..len]);
+            }
+            if b && !pressed.1 {
+                count += 1;
+                let mut payload = [0; 32];
+                let payload_len = MakeCodePacket {
+                    time: 0,
+                    serial: 0,
+                    value: MakeCodeValue::Number(count),
+                }
+                .encode(&mut payload)
+                .unwrap();
+                let len = Datagram {
+                    group: GROUP,
+                    payload: &payload[..payload_len],
+                }
+                .encode(&mut buf)
+                .unwrap();
+                radio.send_bytes(&buf[..len]);
+            }
+            pressed = (a, b);
+
+            if let Some(packet) = radio.receive() {
+                let _ = write!(serial, "[{}dBm] ", packet.rssi());
+                let datagram = Datagram::parse(packet.data());
+                let makecode =
+                    datagram.and_then(|datagram| MakeCodePacket::parse(datagram.payload));
+                match (datagram, makecode) {
+                    (_, Some(MakeCodePacket { value, .. })) => {
+                        let _ = write!(serial, "MakeCode: {:?}\r\n", value);
+                    }
+                    (Some(datagram), None) => match str::from_utf8(datagram.payload) {
+                        Ok(message) => {
+                            let _ = write!(serial, "message: {}\r\n", message);
+                        }
+                        Err(_) => {
+                            let _ = write!(serial, "datagram: {:?}\r\n", datagram.payload);
+                        }
+                    },
+                    (None, _) => {
+                        let _ = write!(serial, "bytes: {:?}\r\n", packet.data());
+                    }
+                }
+            }
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
+
+#[interrupt]
+fn RADIO() {
+    radio::handle_radio_interrupt();
+}
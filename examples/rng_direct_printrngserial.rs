@@ -3,7 +3,10 @@
 
 use panic_halt as _;
 
-use microbit::pac::{self, interrupt};
+use microbit::{
+    clock::{self, LfClockSource},
+    pac::{self, interrupt},
+};
 
 use cortex_m::interrupt::Mutex;
 
@@ -18,12 +21,8 @@ static UART: Mutex<RefCell<Option<pac::UART0>>> = Mutex::new(RefCell::new(None))
 #[entry]
 fn main() -> ! {
     if let Some(p) = microbit::Peripherals::take() {
-        p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-
-        while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-
-        /* And then set it back to 0 again, just because ?!? */
-        p.CLOCK.events_lfclkstarted.write(|w| unsafe { w.bits(0) });
+        // Starting the low-frequency clock (needed for RTC to work)
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
         p.GPIO.pin_cnf[24].write(|w| w.pull().pullup().dir().output());
         p.GPIO.pin_cnf[25].write(|w| w.pull().disabled().dir().input());
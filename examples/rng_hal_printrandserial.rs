@@ -9,6 +9,7 @@ use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
 
 use microbit::{
+    clock::{self, LfClockSource},
     hal::{
         self, rng,
         uart::{Baudrate, Uart},
@@ -27,12 +28,8 @@ static RNG: Mutex<RefCell<Option<ChaChaRng>>> = Mutex::new(RefCell::new(None));
 fn main() -> ! {
     if let Some(p) = microbit::Peripherals::take() {
         cortex_m::interrupt::free(move |cs| {
-            p.CLOCK.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
-
-            while p.CLOCK.events_lfclkstarted.read().bits() == 0 {}
-
-            /* And then set it back to 0 again, just because ?!? */
-            p.CLOCK.events_lfclkstarted.write(|w| unsafe { w.bits(0) });
+            // Starting the low-frequency clock (needed for RTC to work)
+            clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
 
             let gpio = hal::gpio::p0::Parts::new(p.GPIO);
             let mut serial = microbit::serial_port!(gpio, p.UART0, Baudrate::BAUD115200);
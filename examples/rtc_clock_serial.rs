@@ -0,0 +1,103 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::{cell::RefCell, fmt::Write, str};
+
+use cortex_m::interrupt::Mutex;
+use cortex_m_rt::entry;
+use microbit::{
+    clock::{self, DateTime, LfClockSource, RtcClock},
+    hal::{self, prelude::*},
+    pac::{self, interrupt},
+    time::RateExtU32,
+};
+
+static CLOCK: Mutex<RefCell<Option<RtcClock<pac::RTC0>>>> = Mutex::new(RefCell::new(None));
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+
+        let rtc_clock = RtcClock::new(p.RTC0);
+        cortex_m::interrupt::free(move |cs| {
+            *CLOCK.borrow(cs).borrow_mut() = Some(rtc_clock);
+        });
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+        unsafe {
+            pac::NVIC::unmask(pac::Interrupt::RTC0);
+        }
+
+        let _ = write!(
+            serial,
+            "\n\rType the time as YYYY-MM-DD HH:MM:SS and press enter to set the clock.\n\r"
+        );
+
+        let mut line = [0u8; 32];
+        let mut len = 0;
+        let mut last_second = None;
+        loop {
+            if let Ok(byte) = serial.read() {
+                match byte {
+                    b'\r' | b'\n' => {
+                        let parsed = str::from_utf8(&line[..len])
+                            .ok()
+                            .and_then(|s| s.parse::<DateTime>().ok());
+                        match parsed {
+                            Some(datetime) => cortex_m::interrupt::free(|cs| {
+                                if let Some(clock) = CLOCK.borrow(cs).borrow_mut().as_mut() {
+                                    clock.set_datetime(datetime);
+                                }
+                            }),
+                            None => {
+                                let _ = write!(serial, "\n\rCan't parse that time\n\r");
+                            }
+                        }
+                        len = 0;
+                    }
+                    _ if len < line.len() => {
+                        line[len] = byte;
+                        len += 1;
+                    }
+                    _ => {}
+                }
+            }
+
+            let (uptime, datetime) = cortex_m::interrupt::free(|cs| {
+                CLOCK
+                    .borrow(cs)
+                    .borrow()
+                    .as_ref()
+                    .map_or((0, None), |clock| (clock.uptime_secs(), clock.datetime()))
+            });
+            if last_second != Some(uptime) {
+                last_second = Some(uptime);
+                match datetime {
+                    Some(datetime) => {
+                        let _ = write!(serial, "{}\n\r", datetime);
+                    }
+                    None => {
+                        let _ = write!(serial, "up {}s, time not set\n\r", uptime);
+                    }
+                }
+            }
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
+
+#[interrupt]
+fn RTC0() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(clock) = CLOCK.borrow(cs).borrow_mut().as_mut() {
+            clock.handle_interrupt();
+        }
+    });
+}
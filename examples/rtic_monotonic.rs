@@ -0,0 +1,84 @@
+//! A complete working example of RTIC's `schedule` API.
+//!
+//! This requires `cortex-m-rtic` v0.5 and the `rtic` feature, and runs on the
+//! v2 only, as RTIC needs SysTick to schedule tasks.
+//!
+//! It uses `TIMER0` as the monotonic timer and `TIMER1` to drive the
+//! display. A scheduled task polls the buttons: button A shows a heart and
+//! button B clears the display. Each press is reported on the serial port.
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use microbit::{
+    board::Board,
+    button::{Button, ButtonEvent, Channels},
+    display::{image::GreyscaleImage, DisplayDriver},
+    hal::uarte::Uarte,
+    monotonic::{Duration, MonoTimer},
+    pac,
+};
+use rtic::app;
+
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+const HEART: GreyscaleImage = GreyscaleImage::new(&[
+    [0, 7, 0, 7, 0],
+    [7, 9, 7, 9, 7],
+    [7, 9, 9, 9, 7],
+    [0, 7, 9, 7, 0],
+    [0, 0, 7, 0, 0],
+]);
+
+#[app(device = microbit::pac, peripherals = true, monotonic = microbit::monotonic::MonoTimer)]
+const APP: () = {
+    struct Resources {
+        display: DisplayDriver<pac::TIMER1>,
+        button_a: Button,
+        button_b: Button,
+        serial: Uarte<pac::UARTE0>,
+    }
+
+    #[init(schedule = [poll_buttons])]
+    fn init(cx: init::Context) -> init::LateResources {
+        let board = Board::new(cx.device);
+
+        MonoTimer::new(board.TIMER0);
+        let channels = Channels::new(board.GPIOTE);
+        cx.schedule.poll_buttons(cx.start).unwrap();
+
+        init::LateResources {
+            display: DisplayDriver::new(board.TIMER1, board.display_pins),
+            button_a: Button::new(board.buttons.button_a.degrade(), channels.channel0),
+            button_b: Button::new(board.buttons.button_b.degrade(), channels.channel1),
+            serial: board.uart,
+        }
+    }
+
+    #[task(binds = TIMER1, priority = 2, resources = [display])]
+    fn timer1(cx: timer1::Context) {
+        cx.resources.display.handle_display_event();
+    }
+
+    #[task(schedule = [poll_buttons], resources = [display, button_a, button_b, serial])]
+    fn poll_buttons(mut cx: poll_buttons::Context) {
+        if let Some(ButtonEvent::Pressed) = cx.resources.button_a.poll_event() {
+            cx.resources.display.lock(|display| display.show(&HEART));
+            write!(cx.resources.serial, "A\r\n").ok();
+        }
+        if let Some(ButtonEvent::Pressed) = cx.resources.button_b.poll_event() {
+            cx.resources.display.lock(|display| display.clear());
+            write!(cx.resources.serial, "B\r\n").ok();
+        }
+        cx.schedule
+            .poll_buttons(cx.scheduled + POLL_PERIOD)
+            .unwrap();
+    }
+
+    extern "C" {
+        fn SWI0_EGU0();
+    }
+};
@@ -0,0 +1,47 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    board::Board,
+    hal::gpio::Level,
+    sound::{Microphone, SoundDetector, SoundEvent, Speaker},
+    time::RateExtU32,
+};
+
+#[entry]
+fn main() -> ! {
+    let board = Board::take().unwrap();
+    let mut serial = board.uart;
+    let mut microphone = Microphone::new(
+        board.SAADC,
+        board.pins.p0_05,
+        board.pins.p0_20.into_push_pull_output(Level::Low),
+    );
+    let mut speaker = Speaker::new(
+        board.PWM0,
+        board.pins.p0_00.into_push_pull_output(Level::Low).degrade(),
+    );
+    let mut detector = SoundDetector::new();
+
+    // A chirp falling from 1kHz to about 270Hz, as a quiet square wave in
+    // 8-bit PCM at 7812.5Hz
+    let mut chirp = [0; 2048];
+    let mut phase: u32 = 0;
+    for (i, sample) in chirp.iter_mut().enumerate() {
+        phase = phase.wrapping_add(8_389 - 3 * i as u32);
+        *sample = if phase & 0x8000 != 0 { 160 } else { 96 };
+    }
+
+    loop {
+        let level = microphone.sound_level();
+        if detector.update(level) == Some(SoundEvent::Loud) {
+            write!(serial, "clap at {}dB\r\n", level).ok();
+            speaker.play(&chirp, 7_812.Hz());
+        }
+    }
+}
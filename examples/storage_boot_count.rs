@@ -0,0 +1,42 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    hal,
+    storage::{KeyValueStore, Nvmc, STORAGE_PAGES, STORAGE_START},
+    time::RateExtU32,
+};
+
+// Counts how many times the micro:bit has started, keeping the count in
+// flash, and prints it on the serial port.
+//
+// Build with `MICROBIT_STORAGE_PAGES=4` or more, to keep pages for the store.
+
+const BOOT_COUNT: u16 = 1;
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+
+        let mut store = KeyValueStore::new(Nvmc::new(&p.NVMC, STORAGE_START, STORAGE_PAGES));
+        let mut buf = [0; 4];
+        let count = match store.get(BOOT_COUNT, &mut buf) {
+            Some(4) => u32::from_le_bytes(buf) + 1,
+            _ => 1,
+        };
+        store.set(BOOT_COUNT, &count.to_le_bytes()).unwrap();
+
+        let _ = write!(serial, "started {} times\r\n", count);
+    }
+
+    loop {
+        continue;
+    }
+}
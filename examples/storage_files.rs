@@ -0,0 +1,58 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::{fmt::Write, str};
+
+use cortex_m_rt::entry;
+use microbit::{
+    hal::{self, prelude::*},
+    storage::{fs::FileSystem, Nvmc, FS_PAGES, FS_START},
+    time::RateExtU32,
+};
+
+// Lists the files in flash on the serial port, prints `note.txt`, then
+// saves each line typed on the serial port as the new `note.txt`.
+//
+// Build with `MICROBIT_FS_PAGES=8` or more, to keep pages for the files.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+
+        let mut fs = FileSystem::new(Nvmc::new(&p.NVMC, FS_START, FS_PAGES));
+        for file in fs.list() {
+            let _ = write!(serial, "{} ({} bytes)\r\n", file.name(), file.size());
+        }
+
+        let mut line = [0; 128];
+        if let Ok(mut file) = fs.open("note.txt") {
+            let len = file.read(&mut line);
+            let text = str::from_utf8(&line[..len]).unwrap_or("?");
+            let _ = write!(serial, "note.txt: {}\r\n", text);
+        }
+
+        let mut line_len = 0;
+        loop {
+            if let Ok(byte) = serial.read() {
+                let _ = serial.write(byte);
+                if byte == b'\r' {
+                    let _ = serial.write(b'\n');
+                    let mut file = fs.create("note.txt").unwrap();
+                    file.write(&line[..line_len]).unwrap();
+                    line_len = 0;
+                } else if line_len < line.len() {
+                    line[line_len] = byte;
+                    line_len += 1;
+                }
+            }
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
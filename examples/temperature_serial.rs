@@ -0,0 +1,58 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    clock::{self, LfClockSource},
+    executor, hal,
+    pac::{self, interrupt},
+    storage::{KeyValueStore, Nvmc, STORAGE_PAGES, STORAGE_START},
+    temperature::{self, Thermometer},
+    time::{self, ExtU32, RateExtU32, Timer},
+};
+
+// Prints the temperature on the serial port every second, corrected by the
+// calibration offset kept in flash, if any.
+
+#[entry]
+fn main() -> ! {
+    let p = pac::Peripherals::take().unwrap();
+    clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+    time::init(p.RTC1);
+
+    let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+    let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+
+    let mut thermometer = Thermometer::new(p.TEMP);
+    let store = KeyValueStore::new(Nvmc::new(&p.NVMC, STORAGE_START, STORAGE_PAGES));
+    if thermometer.load_offset(&store) {
+        let _ = write!(serial, "offset {}°C\r\n", thermometer.offset());
+    }
+
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::RTC1);
+        pac::NVIC::unmask(pac::Interrupt::TEMP);
+    }
+
+    executor::block_on(async {
+        loop {
+            let temperature = thermometer.measure().await;
+            let _ = write!(serial, "{}°C\r\n", temperature);
+            Timer::after(1_000.millis()).await;
+        }
+    })
+}
+
+#[interrupt]
+fn RTC1() {
+    time::handle_rtc_interrupt();
+}
+
+#[interrupt]
+fn TEMP() {
+    temperature::handle_temp_interrupt();
+}
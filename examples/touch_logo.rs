@@ -0,0 +1,34 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    board::Board,
+    button::ButtonEvent,
+    touch::{TouchMode, TouchPad},
+};
+
+#[entry]
+fn main() -> ! {
+    let board = Board::take().unwrap();
+    let mut serial = board.uart;
+
+    // Don't touch the logo or the pad while they are calibrated
+    let mut logo = TouchPad::logo(board.pins.p1_04);
+    let mut pad = TouchPad::new(board.pins.pad1.degrade(), TouchMode::Capacitive);
+
+    loop {
+        match logo.poll_event() {
+            Some(ButtonEvent::Pressed) => write!(serial, "logo touched\r\n").ok(),
+            Some(ButtonEvent::Released) => write!(serial, "logo released\r\n").ok(),
+            None => None,
+        };
+        if pad.poll_event() == Some(ButtonEvent::Pressed) {
+            write!(serial, "pad 1 touched\r\n").ok();
+        }
+    }
+}
@@ -0,0 +1,51 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+    interface::{self, UsbEvent, UsbMonitor},
+};
+
+// Logs a reading every second: streamed over the serial port while USB is
+// connected, and buffered while running from the battery, then flushed when
+// USB comes back. A real logger would buffer to flash, with `storage`.
+
+#[entry]
+fn main() -> ! {
+    let board = Board::take().unwrap();
+    let mut serial = board.uart;
+    let mut timer = Timer::new(board.TIMER0);
+    let mut usb = UsbMonitor::new();
+
+    let mut buffer = [0_u32; 256];
+    let mut buffered = 0;
+    let mut reading = 0_u32;
+
+    loop {
+        if usb.update(interface::usb_connected(&mut timer)) == Some(UsbEvent::Connected) {
+            write!(serial, "usb connected, {} buffered\r\n", buffered).ok();
+            for value in &buffer[..buffered] {
+                write!(serial, "{}\r\n", value).ok();
+            }
+            buffered = 0;
+        }
+        if interface::take_uart_break() {
+            write!(serial, "break\r\n").ok();
+        }
+
+        reading += 1;
+        if usb.is_connected() {
+            write!(serial, "{}\r\n", reading).ok();
+        } else if buffered < buffer.len() {
+            buffer[buffered] = reading;
+            buffered += 1;
+        }
+        timer.delay_ms(1_000_u16);
+    }
+}
@@ -0,0 +1,54 @@
+#![no_main]
+#![no_std]
+
+use panic_halt as _;
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use microbit::{
+    button::{Button, Channels},
+    clock::{self, LfClockSource},
+    hal, power,
+    time::{ExtU32, RateExtU32},
+    watchdog::{Config, Watchdog},
+};
+
+// Feeds a watchdog with a 2 second timeout while button A isn't pressed.
+// Holding button A stops feeding it, and the micro:bit is reset: the reset
+// reason is printed on the serial port when it starts.
+
+#[entry]
+fn main() -> ! {
+    if let Some(p) = microbit::Peripherals::take() {
+        let reason = power::reset_reason(&p.POWER);
+
+        let gpio = hal::gpio::p0::Parts::new(p.GPIO);
+        let channels = Channels::new(p.GPIOTE);
+        let button_a = Button::new(
+            gpio.p0_17.into_floating_input().degrade(),
+            channels.channel0,
+        );
+        let mut serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+        let _ = write!(serial, "{:?}\r\n", reason);
+
+        // The watchdog counts with the LFCLK
+        clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+        let config = Config {
+            timeout: 2_000.millis(),
+            ..Config::default()
+        };
+        let mut watchdog = Watchdog::start(p.WDT, config, 1).ok().unwrap();
+        let mut feeder = watchdog.feeder(0).unwrap();
+
+        loop {
+            if !button_a.is_pressed() {
+                feeder.feed();
+            }
+        }
+    }
+
+    loop {
+        continue;
+    }
+}
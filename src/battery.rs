@@ -0,0 +1,146 @@
+//! Supply voltage measurement and low-voltage warning.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - a [`Battery`] driver, which measures the supply voltage with the `ADC`
+//! - a low-voltage warning from the `POWER` peripheral's power-fail
+//!   comparator, set with [`set_low_voltage_warning()`].
+//!
+//! The micro:bit runs from the battery through a diode, which drops a few
+//! hundred millivolts, or from USB through the interface chip's 3.3V
+//! regulator. The nRF51822 works down to 1.8V.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::battery::{self, Battery, Threshold};
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let mut battery = Battery::new(p.ADC);
+//! let millivolts = battery.millivolts();
+//!
+//! battery::set_low_voltage_warning(&p.POWER, Some(Threshold::V2_3));
+//! if battery::low_voltage_warning() {
+//!     // the supply has dropped below 2.3V
+//! }
+//! ```
+//!
+//! See a working example at `examples/battery_voltage.rs`
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::pac::{ADC, POWER};
+
+/// The bandgap reference voltage, in millivolts.
+const REFERENCE_MILLIVOLTS: u32 = 1200;
+
+/// Set by [`handle_power_interrupt()`] when the supply drops below the
+/// threshold.
+static LOW_VOLTAGE: AtomicBool = AtomicBool::new(false);
+
+/// Measures the supply voltage with the `ADC`.
+pub struct Battery {
+    adc: ADC,
+}
+
+impl Battery {
+    /// Returns a new `Battery` using the `ADC`.
+    pub fn new(adc: ADC) -> Self {
+        Battery { adc }
+    }
+
+    /// Releases the `ADC`.
+    pub fn free(self) -> ADC {
+        self.adc
+    }
+
+    /// Measures the supply voltage, in millivolts.
+    ///
+    /// The `ADC` is only enabled during the measurement, which takes about
+    /// 70µs.
+    pub fn millivolts(&mut self) -> u16 {
+        // A third of the supply, against the 1.2V bandgap reference
+        self.adc.config.write(|w| {
+            w.res()
+                ._10bit()
+                .inpsel()
+                .supply_one_third_prescaling()
+                .refsel()
+                .vbg()
+                .psel()
+                .disabled()
+        });
+        self.adc.enable.write(|w| w.enable().enabled());
+        self.adc.events_end.reset();
+        self.adc.tasks_start.write(|w| unsafe { w.bits(1) });
+        while self.adc.events_end.read().bits() == 0 {}
+        self.adc.events_end.reset();
+        let result = u32::from(self.adc.result.read().result().bits());
+        self.adc.enable.write(|w| w.enable().disabled());
+        (result * 3 * REFERENCE_MILLIVOLTS / 1023) as u16
+    }
+}
+
+/// A threshold for the low-voltage warning.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Threshold {
+    /// 2.1V
+    V2_1,
+    /// 2.3V
+    V2_3,
+    /// 2.5V
+    V2_5,
+    /// 2.7V
+    V2_7,
+}
+
+/// Sets the supply voltage below which a low-voltage warning is raised, or
+/// `None` to stop watching it.
+///
+/// The warning can be polled with [`low_voltage_warning()`], or raise the
+/// `POWER_CLOCK` interrupt, which must be unmasked in the NVIC with
+/// [`handle_power_interrupt()`] called from its handler.
+pub fn set_low_voltage_warning(power: &POWER, threshold: Option<Threshold>) {
+    match threshold {
+        Some(threshold) => {
+            power.pofcon.write(|w| {
+                let w = w.pof().enabled();
+                match threshold {
+                    Threshold::V2_1 => w.threshold().v21(),
+                    Threshold::V2_3 => w.threshold().v23(),
+                    Threshold::V2_5 => w.threshold().v25(),
+                    Threshold::V2_7 => w.threshold().v27(),
+                }
+            });
+            power.intenset.write(|w| w.pofwarn().set());
+        }
+        None => {
+            power.intenclr.write(|w| w.pofwarn().clear());
+            power.pofcon.write(|w| w.pof().disabled());
+        }
+    }
+}
+
+/// Returns whether the supply has dropped below the threshold since the
+/// last call.
+pub fn low_voltage_warning() -> bool {
+    handle_power_interrupt();
+    cortex_m::interrupt::free(|_| {
+        let low = LOW_VOLTAGE.load(Ordering::Relaxed);
+        LOW_VOLTAGE.store(false, Ordering::Relaxed);
+        low
+    })
+}
+
+/// Records a low-voltage warning.
+///
+/// Call this in the interrupt handler for `POWER_CLOCK`.
+pub fn handle_power_interrupt() {
+    // Only the POFWARN event is used
+    let power = unsafe { &*POWER::ptr() };
+    if power.events_pofwarn.read().bits() != 0 {
+        power.events_pofwarn.reset();
+        LOW_VOLTAGE.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,261 @@
+//! Advertising data.
+//!
+//! Advertising and scan response PDUs carry up to 31 bytes of data, made of
+//! length-prefixed AD structures. [`AdvertisingData`] builds this data:
+//!
+//! ```
+//! use microbit::ble::ad::{AdStructure, AdvertisingData, Flags};
+//!
+//! let mut data = AdvertisingData::new();
+//! data.add(AdStructure::Flags(Flags::LE_GENERAL_DISCOVERABLE | Flags::BR_EDR_NOT_SUPPORTED))
+//!     .unwrap();
+//! data.add(AdStructure::CompleteLocalName("BBC micro:bit")).unwrap();
+//! assert_eq!(data.len(), 3 + 15);
+//! ```
+//!
+//! [`AdStructures`] parses received advertising data:
+//!
+//! ```
+//! use microbit::ble::ad::{AdStructure, AdStructures, Flags};
+//!
+//! let data = b"\x02\x01\x06\x06\x09micro\x05\xff\x59\x00\x01\x02";
+//! let mut structures = AdStructures::new(data);
+//! assert_eq!(structures.next(), Some(AdStructure::Flags(Flags(0x06))));
+//! assert_eq!(structures.next(), Some(AdStructure::CompleteLocalName("micro")));
+//! assert_eq!(
+//!     structures.next(),
+//!     Some(AdStructure::ManufacturerSpecificData(0x0059, &[0x01, 0x02]))
+//! );
+//! assert_eq!(structures.next(), None);
+//! ```
+
+use core::{ops::BitOr, str};
+
+use super::link::MAX_ADVERTISING_DATA;
+
+/// The `Flags` AD structure's bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Flags(pub u8);
+
+impl Flags {
+    /// LE Limited Discoverable Mode.
+    pub const LE_LIMITED_DISCOVERABLE: Flags = Flags(1 << 0);
+    /// LE General Discoverable Mode.
+    pub const LE_GENERAL_DISCOVERABLE: Flags = Flags(1 << 1);
+    /// BR/EDR Not Supported.
+    pub const BR_EDR_NOT_SUPPORTED: Flags = Flags(1 << 2);
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Flags) -> Flags {
+        Flags(self.0 | rhs.0)
+    }
+}
+
+/// An AD structure.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdStructure<'a> {
+    /// Discoverability flags.
+    Flags(Flags),
+    /// The complete list of the 16-bit UUIDs of the device's services.
+    CompleteUuids16(&'a [u16]),
+    /// The complete list of the 128-bit UUIDs of the device's services.
+    CompleteUuids128(&'a [u128]),
+    /// The shortened device name.
+    ShortenedLocalName(&'a str),
+    /// The complete device name.
+    CompleteLocalName(&'a str),
+    /// The transmit power level, in dBm.
+    TxPowerLevel(i8),
+    /// Data for a service with a 16-bit UUID.
+    ServiceData16(u16, &'a [u8]),
+    /// Data defined by a company, identified by its company identifier.
+    ManufacturerSpecificData(u16, &'a [u8]),
+    /// Any other AD structure, as its AD type and data.
+    Other(u8, &'a [u8]),
+}
+
+impl<'a> AdStructure<'a> {
+    fn ad_type(&self) -> u8 {
+        match self {
+            AdStructure::Flags(_) => 0x01,
+            AdStructure::CompleteUuids16(_) => 0x03,
+            AdStructure::CompleteUuids128(_) => 0x07,
+            AdStructure::ShortenedLocalName(_) => 0x08,
+            AdStructure::CompleteLocalName(_) => 0x09,
+            AdStructure::TxPowerLevel(_) => 0x0a,
+            AdStructure::ServiceData16(..) => 0x16,
+            AdStructure::ManufacturerSpecificData(..) => 0xff,
+            AdStructure::Other(ad_type, _) => *ad_type,
+        }
+    }
+
+    /// Parses an AD structure from its type and data.
+    fn parse(ad_type: u8, data: &'a [u8]) -> Self {
+        match (ad_type, data) {
+            (0x01, &[flags]) => AdStructure::Flags(Flags(flags)),
+            (0x08, _) | (0x09, _) => match str::from_utf8(data) {
+                Ok(name) if ad_type == 0x08 => AdStructure::ShortenedLocalName(name),
+                Ok(name) => AdStructure::CompleteLocalName(name),
+                Err(_) => AdStructure::Other(ad_type, data),
+            },
+            (0x0a, &[power]) => AdStructure::TxPowerLevel(power as i8),
+            (0x16, &[low, high, ref data @ ..]) => {
+                AdStructure::ServiceData16(u16::from_le_bytes([low, high]), data)
+            }
+            (0xff, &[low, high, ref data @ ..]) => {
+                AdStructure::ManufacturerSpecificData(u16::from_le_bytes([low, high]), data)
+            }
+            _ => AdStructure::Other(ad_type, data),
+        }
+    }
+
+    fn data_len(&self) -> usize {
+        match self {
+            AdStructure::Flags(_) | AdStructure::TxPowerLevel(_) => 1,
+            AdStructure::CompleteUuids16(uuids) => 2 * uuids.len(),
+            AdStructure::CompleteUuids128(uuids) => 16 * uuids.len(),
+            AdStructure::ShortenedLocalName(name) | AdStructure::CompleteLocalName(name) => {
+                name.len()
+            }
+            AdStructure::ServiceData16(_, data)
+            | AdStructure::ManufacturerSpecificData(_, data) => 2 + data.len(),
+            AdStructure::Other(_, data) => data.len(),
+        }
+    }
+
+    fn write_data(&self, buf: &mut [u8]) {
+        match self {
+            AdStructure::Flags(flags) => buf[0] = flags.0,
+            AdStructure::TxPowerLevel(power) => buf[0] = *power as u8,
+            AdStructure::CompleteUuids16(uuids) => {
+                for (chunk, uuid) in buf.chunks_mut(2).zip(uuids.iter()) {
+                    chunk.copy_from_slice(&uuid.to_le_bytes());
+                }
+            }
+            AdStructure::CompleteUuids128(uuids) => {
+                for (chunk, uuid) in buf.chunks_mut(16).zip(uuids.iter()) {
+                    chunk.copy_from_slice(&uuid.to_le_bytes());
+                }
+            }
+            AdStructure::ShortenedLocalName(name) | AdStructure::CompleteLocalName(name) => {
+                buf.copy_from_slice(name.as_bytes())
+            }
+            AdStructure::ServiceData16(id, data)
+            | AdStructure::ManufacturerSpecificData(id, data) => {
+                buf[..2].copy_from_slice(&id.to_le_bytes());
+                buf[2..].copy_from_slice(data);
+            }
+            AdStructure::Other(_, data) => buf.copy_from_slice(data),
+        }
+    }
+}
+
+/// The error returned when an AD structure doesn't fit in the remaining
+/// space.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AdvertisingDataFull;
+
+/// Up to 31 bytes of advertising data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AdvertisingData {
+    buf: [u8; MAX_ADVERTISING_DATA],
+    len: u8,
+}
+
+impl AdvertisingData {
+    /// Returns empty advertising data.
+    pub const fn new() -> Self {
+        AdvertisingData {
+            buf: [0; MAX_ADVERTISING_DATA],
+            len: 0,
+        }
+    }
+
+    /// Appends an AD structure.
+    pub fn add(&mut self, structure: AdStructure) -> Result<(), AdvertisingDataFull> {
+        let start = usize::from(self.len);
+        let data_len = structure.data_len();
+        let end = start + 2 + data_len;
+        if end > MAX_ADVERTISING_DATA {
+            return Err(AdvertisingDataFull);
+        }
+        self.buf[start] = 1 + data_len as u8;
+        self.buf[start + 1] = structure.ad_type();
+        structure.write_data(&mut self.buf[start + 2..end]);
+        self.len = end as u8;
+        Ok(())
+    }
+
+    /// Returns the encoded data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+
+    /// Returns the length of the encoded data.
+    pub fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// Returns whether there is no data.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for AdvertisingData {
+    fn default() -> Self {
+        AdvertisingData::new()
+    }
+}
+
+/// An iterator over the AD structures in received advertising data.
+///
+/// UUID lists can't be borrowed as `u16`s or `u128`s, so they are returned as
+/// [`AdStructure::Other`], as are structures whose data isn't valid for
+/// their type.
+#[derive(Clone, Debug)]
+pub struct AdStructures<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AdStructures<'a> {
+    /// Returns an iterator over the AD structures in `data`.
+    ///
+    /// Iteration stops at a zero length, which starts the padding after the
+    /// significant part of the data, or at a structure which overruns the
+    /// data:
+    ///
+    /// ```
+    /// use microbit::ble::ad::{AdStructure, AdStructures};
+    ///
+    /// let padded = b"\x02\x0a\xf4\x00\x00\x00";
+    /// let structures = AdStructures::new(padded);
+    /// assert!(structures.eq([AdStructure::TxPowerLevel(-12)].iter().copied()));
+    ///
+    /// let truncated = b"\x03\x03\xaa\xfe\x09\x16\xaa\xfe";
+    /// let structures = AdStructures::new(truncated);
+    /// assert!(structures.eq([AdStructure::Other(0x03, b"\xaa\xfe")].iter().copied()));
+    /// ```
+    pub fn new(data: &'a [u8]) -> Self {
+        AdStructures { data }
+    }
+}
+
+impl<'a> Iterator for AdStructures<'a> {
+    type Item = AdStructure<'a>;
+
+    fn next(&mut self) -> Option<AdStructure<'a>> {
+        let (&len, rest) = self.data.split_first()?;
+        let len = usize::from(len);
+        if len == 0 || len > rest.len() {
+            self.data = &[];
+            return None;
+        }
+        let (structure, rest) = rest.split_at(len);
+        self.data = rest;
+        Some(AdStructure::parse(structure[0], &structure[1..]))
+    }
+}
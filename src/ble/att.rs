@@ -0,0 +1,568 @@
+//! The Attribute Protocol server.
+//!
+//! [`AttServer`] answers ATT requests from a GATT client using an
+//! [`AttributeProvider`]'s attribute table. It only deals in ATT PDUs, so it
+//! can be driven by any transport.
+//!
+//! The ATT MTU is fixed at the minimum of 23 bytes, which fits in a single
+//! link-layer data PDU with its L2CAP header.
+
+use super::gatt::{AttError, Attribute, AttributeProvider, Properties, Uuid};
+
+/// The ATT MTU, in bytes.
+pub const MTU: usize = 23;
+
+mod opcode {
+    pub const ERROR_RSP: u8 = 0x01;
+    pub const EXCHANGE_MTU_REQ: u8 = 0x02;
+    pub const EXCHANGE_MTU_RSP: u8 = 0x03;
+    pub const FIND_INFORMATION_REQ: u8 = 0x04;
+    pub const FIND_INFORMATION_RSP: u8 = 0x05;
+    pub const FIND_BY_TYPE_VALUE_REQ: u8 = 0x06;
+    pub const FIND_BY_TYPE_VALUE_RSP: u8 = 0x07;
+    pub const READ_BY_TYPE_REQ: u8 = 0x08;
+    pub const READ_BY_TYPE_RSP: u8 = 0x09;
+    pub const READ_REQ: u8 = 0x0a;
+    pub const READ_RSP: u8 = 0x0b;
+    pub const READ_BLOB_REQ: u8 = 0x0c;
+    pub const READ_BLOB_RSP: u8 = 0x0d;
+    pub const READ_BY_GROUP_TYPE_REQ: u8 = 0x10;
+    pub const READ_BY_GROUP_TYPE_RSP: u8 = 0x11;
+    pub const WRITE_REQ: u8 = 0x12;
+    pub const WRITE_RSP: u8 = 0x13;
+    pub const HANDLE_VALUE_NTF: u8 = 0x1b;
+    pub const HANDLE_VALUE_IND: u8 = 0x1d;
+    pub const HANDLE_VALUE_CFM: u8 = 0x1e;
+    pub const WRITE_CMD: u8 = 0x52;
+}
+
+/// Client Characteristic Configuration bit enabling notifications.
+const CCCD_NOTIFY: u16 = 1;
+/// Client Characteristic Configuration bit enabling indications.
+const CCCD_INDICATE: u16 = 2;
+
+/// The reason a notification couldn't be sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NotifyError {
+    /// There is no connection.
+    NotConnected,
+    /// The client hasn't enabled notifications or indications for the
+    /// characteristic.
+    NotEnabled,
+    /// An indication is waiting to be confirmed, or the transmit queue is
+    /// full.
+    Busy,
+    /// The handle isn't a characteristic value which can be notified.
+    InvalidHandle,
+}
+
+/// An ATT server for one connection.
+pub struct AttServer<P> {
+    provider: P,
+    /// Two bits per Client Characteristic Configuration descriptor, in
+    /// table order.
+    client_config: u32,
+    indication_pending: bool,
+}
+
+impl<P: AttributeProvider> AttServer<P> {
+    /// Returns a new `AttServer` serving the provider's attributes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table has more than 16 Client Characteristic
+    /// Configuration descriptors.
+    pub fn new(provider: P) -> Self {
+        let descriptors = provider
+            .attributes()
+            .iter()
+            .filter(|attribute| **attribute == Attribute::ClientConfig)
+            .count();
+        assert!(descriptors <= 16);
+        AttServer {
+            provider,
+            client_config: 0,
+            indication_pending: false,
+        }
+    }
+
+    /// Returns a reference to the attribute provider.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Returns a mutable reference to the attribute provider.
+    pub fn provider_mut(&mut self) -> &mut P {
+        &mut self.provider
+    }
+
+    /// Clears the connection state: the client configurations and any
+    /// pending indication.
+    pub fn reset(&mut self) {
+        self.client_config = 0;
+        self.indication_pending = false;
+    }
+
+    /// Handles an ATT PDU from the client, writing any response to
+    /// `response`.
+    ///
+    /// Returns the length of the response, or 0 if there is none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `response` is shorter than [`MTU`].
+    pub fn handle(&mut self, request: &[u8], response: &mut [u8]) -> usize {
+        let response = &mut response[..MTU];
+        let (&code, params) = match request.split_first() {
+            Some(split) => split,
+            None => return 0,
+        };
+        let result = match code {
+            opcode::EXCHANGE_MTU_REQ => {
+                if params.len() != 2 {
+                    Err((AttError::InvalidPdu, 0))
+                } else {
+                    response[0] = opcode::EXCHANGE_MTU_RSP;
+                    response[1..3].copy_from_slice(&(MTU as u16).to_le_bytes());
+                    Ok(3)
+                }
+            }
+            opcode::FIND_INFORMATION_REQ => self.find_information(params, response),
+            opcode::FIND_BY_TYPE_VALUE_REQ => self.find_by_type_value(params, response),
+            opcode::READ_BY_TYPE_REQ => self.read_by_type(params, response),
+            opcode::READ_REQ => {
+                if params.len() != 2 {
+                    Err((AttError::InvalidPdu, 0))
+                } else {
+                    self.read(u16_at(params, 0), 0, opcode::READ_RSP, response)
+                }
+            }
+            opcode::READ_BLOB_REQ => {
+                if params.len() != 4 {
+                    Err((AttError::InvalidPdu, 0))
+                } else {
+                    let offset = usize::from(u16_at(params, 2));
+                    self.read(u16_at(params, 0), offset, opcode::READ_BLOB_RSP, response)
+                }
+            }
+            opcode::READ_BY_GROUP_TYPE_REQ => self.read_by_group_type(params, response),
+            opcode::WRITE_REQ | opcode::WRITE_CMD => {
+                let result = if params.len() < 2 {
+                    Err((AttError::InvalidPdu, 0))
+                } else {
+                    let handle = u16_at(params, 0);
+                    self.write(handle, &params[2..], code == opcode::WRITE_REQ)
+                        .map_err(|error| (error, handle))
+                };
+                if code == opcode::WRITE_CMD {
+                    // Commands never get a response
+                    return 0;
+                }
+                result.map(|()| {
+                    response[0] = opcode::WRITE_RSP;
+                    1
+                })
+            }
+            opcode::HANDLE_VALUE_CFM => {
+                self.indication_pending = false;
+                return 0;
+            }
+            // Unknown commands are ignored
+            _ if code & 0x40 != 0 => return 0,
+            _ => Err((AttError::RequestNotSupported, 0)),
+        };
+        match result {
+            Ok(len) => len,
+            Err((error, handle)) => {
+                response[0] = opcode::ERROR_RSP;
+                response[1] = code;
+                response[2..4].copy_from_slice(&handle.to_le_bytes());
+                response[4] = error as u8;
+                5
+            }
+        }
+    }
+
+    /// Builds a notification or indication of the current value of the
+    /// characteristic value at `handle`, depending on which the client has
+    /// enabled, writing it to `pdu`.
+    ///
+    /// Returns the length of the PDU.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pdu` is shorter than [`MTU`].
+    pub fn notification(&mut self, handle: u16, pdu: &mut [u8]) -> Result<usize, NotifyError> {
+        let pdu = &mut pdu[..MTU];
+        let index = self
+            .client_config_index(handle)
+            .ok_or(NotifyError::InvalidHandle)?;
+        let config = self.client_config(index);
+        let code = if config & CCCD_INDICATE != 0 {
+            if self.indication_pending {
+                return Err(NotifyError::Busy);
+            }
+            opcode::HANDLE_VALUE_IND
+        } else if config & CCCD_NOTIFY != 0 {
+            opcode::HANDLE_VALUE_NTF
+        } else {
+            return Err(NotifyError::NotEnabled);
+        };
+        let len = self
+            .provider
+            .read(handle, &mut pdu[3..])
+            .map_err(|_| NotifyError::InvalidHandle)?;
+        pdu[0] = code;
+        pdu[1..3].copy_from_slice(&handle.to_le_bytes());
+        if code == opcode::HANDLE_VALUE_IND {
+            self.indication_pending = true;
+        }
+        Ok(3 + len)
+    }
+
+    /// Returns whether the client has enabled notifications or indications
+    /// for the characteristic value at `handle`.
+    pub fn is_notifying(&self, handle: u16) -> bool {
+        self.client_config_index(handle)
+            .is_some_and(|index| self.client_config(index) != 0)
+    }
+
+    fn attributes(&self) -> &'static [Attribute] {
+        self.provider.attributes()
+    }
+
+    fn attribute(&self, handle: u16) -> Option<&'static Attribute> {
+        self.attributes().get(usize::from(handle).checked_sub(1)?)
+    }
+
+    /// Returns the properties of the characteristic whose value is at
+    /// `handle`.
+    fn value_properties(&self, handle: u16) -> Option<Properties> {
+        match self.attribute(handle.checked_sub(1)?)? {
+            Attribute::Characteristic(properties, _) => Some(*properties),
+            _ => None,
+        }
+    }
+
+    /// Returns the index among the Client Characteristic Configuration
+    /// descriptors of the one for the characteristic value at `handle`.
+    fn client_config_index(&self, handle: u16) -> Option<usize> {
+        self.value_properties(handle)?;
+        let attributes = self.attributes();
+        let start = usize::from(handle);
+        let offset = attributes[start..]
+            .iter()
+            .take_while(|attribute| {
+                !matches!(
+                    attribute,
+                    Attribute::Characteristic(..) | Attribute::PrimaryService(_)
+                )
+            })
+            .position(|attribute| *attribute == Attribute::ClientConfig)?;
+        Some(self.descriptor_index(start + offset))
+    }
+
+    /// Returns the number of Client Characteristic Configuration
+    /// descriptors before `index` in the table.
+    fn descriptor_index(&self, index: usize) -> usize {
+        self.attributes()[..index]
+            .iter()
+            .filter(|attribute| **attribute == Attribute::ClientConfig)
+            .count()
+    }
+
+    fn client_config(&self, index: usize) -> u16 {
+        (self.client_config >> (2 * index) & 3) as u16
+    }
+
+    /// Returns the handle of the last attribute in the service starting at
+    /// `handle`.
+    fn group_end(&self, handle: u16) -> u16 {
+        let attributes = self.attributes();
+        let next = attributes[usize::from(handle)..]
+            .iter()
+            .position(|attribute| matches!(attribute, Attribute::PrimaryService(_)));
+        match next {
+            Some(offset) => handle + offset as u16,
+            None => attributes.len() as u16,
+        }
+    }
+
+    /// Returns the handles in a request's range which exist, or an error.
+    fn range(&self, params: &[u8]) -> Result<core::ops::RangeInclusive<u16>, (AttError, u16)> {
+        let start = u16_at(params, 0);
+        let end = u16_at(params, 2);
+        if start == 0 || start > end {
+            return Err((AttError::InvalidHandle, start));
+        }
+        let last = self.attributes().len() as u16;
+        Ok(start..=end.min(last))
+    }
+
+    /// Reads the value of any attribute into `buf`, returning its length.
+    fn read_value(&mut self, handle: u16, buf: &mut [u8]) -> Result<usize, AttError> {
+        let attribute = self.attribute(handle).ok_or(AttError::InvalidHandle)?;
+        match attribute {
+            Attribute::PrimaryService(uuid) => {
+                let mut value = [0; 16];
+                let len = uuid.write_le_bytes(&mut value);
+                Ok(copy_truncated(&value[..len], buf))
+            }
+            Attribute::Characteristic(properties, uuid) => {
+                let mut value = [0; 19];
+                value[0] = properties.0;
+                value[1..3].copy_from_slice(&(handle + 1).to_le_bytes());
+                let len = 3 + uuid.write_le_bytes(&mut value[3..]);
+                Ok(copy_truncated(&value[..len], buf))
+            }
+            Attribute::Value(_) => {
+                let properties = self.value_properties(handle).unwrap_or(Properties(0));
+                if !properties.contains(Properties::READ) {
+                    return Err(AttError::ReadNotPermitted);
+                }
+                self.provider.read(handle, buf)
+            }
+            Attribute::ClientConfig => {
+                let index = self.descriptor_index(usize::from(handle) - 1);
+                Ok(copy_truncated(
+                    &self.client_config(index).to_le_bytes(),
+                    buf,
+                ))
+            }
+            Attribute::Constant(_, value) => Ok(copy_truncated(value, buf)),
+        }
+    }
+
+    fn read(
+        &mut self,
+        handle: u16,
+        offset: usize,
+        code: u8,
+        response: &mut [u8],
+    ) -> Result<usize, (AttError, u16)> {
+        // Read the whole value, as far as it fits, then skip to the offset
+        let mut value = [0; 64];
+        let len = self
+            .read_value(handle, &mut value)
+            .map_err(|error| (error, handle))?;
+        if offset > len {
+            return Err((AttError::InvalidOffset, handle));
+        }
+        response[0] = code;
+        Ok(1 + copy_truncated(&value[offset..len], &mut response[1..]))
+    }
+
+    fn write(&mut self, handle: u16, value: &[u8], with_response: bool) -> Result<(), AttError> {
+        let attribute = self.attribute(handle).ok_or(AttError::InvalidHandle)?;
+        match attribute {
+            Attribute::Value(_) => {
+                let properties = self.value_properties(handle).unwrap_or(Properties(0));
+                let required = if with_response {
+                    Properties::WRITE
+                } else {
+                    Properties::WRITE_WITHOUT_RESPONSE
+                };
+                if !properties.contains(required) {
+                    return Err(AttError::WriteNotPermitted);
+                }
+                self.provider.write(handle, value)
+            }
+            Attribute::ClientConfig => {
+                if value.len() != 2 {
+                    return Err(AttError::InvalidAttributeValueLength);
+                }
+                let config = u16_at(value, 0);
+                // The descriptor belongs to the nearest preceding characteristic
+                let index = usize::from(handle) - 1;
+                let characteristic = self.attributes()[..index]
+                    .iter()
+                    .rposition(|attribute| matches!(attribute, Attribute::Characteristic(..)));
+                let properties = match characteristic.map(|i| &self.attributes()[i]) {
+                    Some(Attribute::Characteristic(properties, _)) => *properties,
+                    _ => Properties(0),
+                };
+                let mut allowed = 0;
+                if properties.contains(Properties::NOTIFY) {
+                    allowed |= CCCD_NOTIFY;
+                }
+                if properties.contains(Properties::INDICATE) {
+                    allowed |= CCCD_INDICATE;
+                }
+                if config & !allowed != 0 {
+                    return Err(AttError::WriteNotPermitted);
+                }
+                let shift = 2 * self.descriptor_index(index);
+                self.client_config =
+                    self.client_config & !(3 << shift) | u32::from(config) << shift;
+                Ok(())
+            }
+            _ => Err(AttError::WriteNotPermitted),
+        }
+    }
+
+    fn find_information(
+        &mut self,
+        params: &[u8],
+        response: &mut [u8],
+    ) -> Result<usize, (AttError, u16)> {
+        if params.len() != 4 {
+            return Err((AttError::InvalidPdu, 0));
+        }
+        let range = self.range(params)?;
+        let start = *range.start();
+        let mut len = 2;
+        let mut format_len = 0;
+        for handle in range {
+            let uuid = self.attribute(handle).unwrap().attribute_type();
+            let entry_len = 2 + uuid.encoded_len();
+            if format_len == 0 {
+                format_len = entry_len;
+            }
+            if entry_len != format_len || len + entry_len > MTU {
+                break;
+            }
+            response[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            uuid.write_le_bytes(&mut response[len + 2..]);
+            len += entry_len;
+        }
+        if len == 2 {
+            return Err((AttError::AttributeNotFound, start));
+        }
+        response[0] = opcode::FIND_INFORMATION_RSP;
+        response[1] = if format_len == 4 { 1 } else { 2 };
+        Ok(len)
+    }
+
+    fn find_by_type_value(
+        &mut self,
+        params: &[u8],
+        response: &mut [u8],
+    ) -> Result<usize, (AttError, u16)> {
+        if params.len() < 6 {
+            return Err((AttError::InvalidPdu, 0));
+        }
+        let range = self.range(params)?;
+        let start = *range.start();
+        let attribute_type = Uuid::Uuid16(u16_at(params, 4));
+        let value = &params[6..];
+        let mut len = 1;
+        // Only service discovery by UUID is supported
+        if attribute_type == Uuid::PRIMARY_SERVICE {
+            for handle in range {
+                if let Attribute::PrimaryService(uuid) = self.attribute(handle).unwrap() {
+                    let matches =
+                        Uuid::from_le_bytes(value).is_some_and(|value| value.matches(uuid));
+                    if matches {
+                        if len + 4 > MTU {
+                            break;
+                        }
+                        response[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+                        response[len + 2..len + 4]
+                            .copy_from_slice(&self.group_end(handle).to_le_bytes());
+                        len += 4;
+                    }
+                }
+            }
+        }
+        if len == 1 {
+            return Err((AttError::AttributeNotFound, start));
+        }
+        response[0] = opcode::FIND_BY_TYPE_VALUE_RSP;
+        Ok(len)
+    }
+
+    fn read_by_type(
+        &mut self,
+        params: &[u8],
+        response: &mut [u8],
+    ) -> Result<usize, (AttError, u16)> {
+        let attribute_type =
+            Uuid::from_le_bytes(params.get(4..).unwrap_or(&[])).ok_or((AttError::InvalidPdu, 0))?;
+        let range = self.range(params)?;
+        let start = *range.start();
+        let mut len = 2;
+        let mut entry_len = 0;
+        for handle in range {
+            if !self
+                .attribute(handle)
+                .unwrap()
+                .attribute_type()
+                .matches(&attribute_type)
+            {
+                continue;
+            }
+            let mut value = [0; MTU];
+            let value_len = match self.read_value(handle, &mut value[..MTU - 4]) {
+                Ok(value_len) => value_len,
+                // Only report an error for the first attribute found
+                Err(error) if entry_len == 0 => return Err((error, handle)),
+                Err(_) => break,
+            };
+            if entry_len == 0 {
+                entry_len = 2 + value_len;
+            }
+            if 2 + value_len != entry_len || len + entry_len > MTU {
+                break;
+            }
+            response[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            response[len + 2..len + entry_len].copy_from_slice(&value[..value_len]);
+            len += entry_len;
+        }
+        if len == 2 {
+            return Err((AttError::AttributeNotFound, start));
+        }
+        response[0] = opcode::READ_BY_TYPE_RSP;
+        response[1] = entry_len as u8;
+        Ok(len)
+    }
+
+    fn read_by_group_type(
+        &mut self,
+        params: &[u8],
+        response: &mut [u8],
+    ) -> Result<usize, (AttError, u16)> {
+        let group_type =
+            Uuid::from_le_bytes(params.get(4..).unwrap_or(&[])).ok_or((AttError::InvalidPdu, 0))?;
+        let range = self.range(params)?;
+        let start = *range.start();
+        if !group_type.matches(&Uuid::PRIMARY_SERVICE) {
+            return Err((AttError::UnsupportedGroupType, start));
+        }
+        let mut len = 2;
+        let mut entry_len = 0;
+        for handle in range {
+            let uuid = match self.attribute(handle).unwrap() {
+                Attribute::PrimaryService(uuid) => uuid,
+                _ => continue,
+            };
+            if entry_len == 0 {
+                entry_len = 4 + uuid.encoded_len();
+            }
+            if 4 + uuid.encoded_len() != entry_len || len + entry_len > MTU {
+                break;
+            }
+            response[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            response[len + 2..len + 4].copy_from_slice(&self.group_end(handle).to_le_bytes());
+            uuid.write_le_bytes(&mut response[len + 4..]);
+            len += entry_len;
+        }
+        if len == 2 {
+            return Err((AttError::AttributeNotFound, start));
+        }
+        response[0] = opcode::READ_BY_GROUP_TYPE_RSP;
+        response[1] = entry_len as u8;
+        Ok(len)
+    }
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+}
+
+/// Copies as much of `value` as fits into `buf`, returning the length
+/// copied.
+fn copy_truncated(value: &[u8], buf: &mut [u8]) -> usize {
+    let len = value.len().min(buf.len());
+    buf[..len].copy_from_slice(&value[..len]);
+    len
+}
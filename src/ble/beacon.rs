@@ -0,0 +1,401 @@
+//! iBeacon and Eddystone beacons.
+//!
+//! A [`Beacon`] sends non-connectable advertising at a fixed interval,
+//! sleeping between advertising events until an `RTC` compare event wakes it
+//! up. It only needs the `RADIO` and an `RTC`, not the rest of the BLE
+//! peripheral support.
+//!
+//! The frame types build the [`AdvertisingData`] for each beacon format:
+//! - [`IBeacon`]
+//! - [`EddystoneUid`], [`EddystoneUrl`] and [`EddystoneTlm`].
+//!
+//! ```
+//! use microbit::ble::beacon::{EddystoneUrl, IBeacon};
+//!
+//! let data = IBeacon {
+//!     uuid: 0xe2c56db5_dffb_48d2_b060_d0f5a71096e0,
+//!     major: 1,
+//!     minor: 2,
+//!     measured_power: -59,
+//! }
+//! .advertising_data();
+//! assert_eq!(data.len(), 30);
+//! assert_eq!(&data.as_bytes()[5..9], &[0x4c, 0x00, 0x02, 0x15]);
+//!
+//! let data = EddystoneUrl {
+//!     tx_power: -20,
+//!     url: "https://microbit.org/",
+//! }
+//! .advertising_data()
+//! .unwrap();
+//! // "https://" and ".org/" are each encoded as a single byte
+//! assert_eq!(&data.as_bytes()[11..], b"\x10\xec\x03microbit\x01");
+//! ```
+
+use crate::{
+    clock::{COUNTER_MASK, MIN_COMPARE_TICKS},
+    hal::rtc::{Instance, Rtc, RtcCompareReg, RtcInterrupt},
+    pac::{FICR, NVIC, RADIO, SCB},
+    time::{MillisDurationU32, RtcDuration},
+};
+
+use super::{
+    ad::{AdStructure, AdvertisingData, Flags},
+    link::{
+        encode_advertising_pdu, AdvertisingPduType, DeviceAddress, ADVERTISING_CHANNELS,
+        PACKET_BUFFER_LEN,
+    },
+    radio::{BleRadio, TxPower},
+    random::Xorshift,
+};
+
+/// Apple's company identifier.
+const APPLE: u16 = 0x004c;
+/// The iBeacon type and length, at the start of the manufacturer data.
+const IBEACON_PREFIX: [u8; 2] = [0x02, 0x15];
+
+/// The 16-bit UUID of the Eddystone service.
+const EDDYSTONE: u16 = 0xfeaa;
+const EDDYSTONE_UID: u8 = 0x00;
+const EDDYSTONE_URL: u8 = 0x10;
+const EDDYSTONE_TLM: u8 = 0x20;
+
+/// The longest encoded URL in an Eddystone-URL frame, scheme excluded.
+pub const MAX_URL_LEN: usize = 17;
+
+/// The URL schemes of Eddystone-URL frames, by their code.
+const URL_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+
+/// The text expansions of Eddystone-URL frames, by their code.
+const URL_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+/// The maximum random delay added to each advertising interval.
+const MAX_DELAY: RtcDuration = RtcDuration::millis(10);
+
+/// The `SEVONPEND` bit of the `SCR` register.
+const SCR_SEVONPEND: u32 = 1 << 4;
+
+/// The advertising data of both beacon formats starts with these flags.
+fn beacon_flags(data: &mut AdvertisingData) {
+    data.add(AdStructure::Flags(
+        Flags::LE_GENERAL_DISCOVERABLE | Flags::BR_EDR_NOT_SUPPORTED,
+    ))
+    .unwrap();
+}
+
+/// Returns Eddystone advertising data, carrying `frame`.
+fn eddystone_data(frame: &[u8]) -> AdvertisingData {
+    let mut data = AdvertisingData::new();
+    beacon_flags(&mut data);
+    data.add(AdStructure::CompleteUuids16(&[EDDYSTONE]))
+        .unwrap();
+    data.add(AdStructure::ServiceData16(EDDYSTONE, frame))
+        .unwrap();
+    data
+}
+
+/// An Apple iBeacon frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IBeacon {
+    /// The proximity UUID, identifying the beacons of a deployment.
+    pub uuid: u128,
+    /// The major number, identifying a group of beacons.
+    pub major: u16,
+    /// The minor number, identifying a beacon in its group.
+    pub minor: u16,
+    /// The signal strength received 1m away, in dBm.
+    pub measured_power: i8,
+}
+
+impl IBeacon {
+    /// Returns the advertising data of the frame.
+    pub fn advertising_data(&self) -> AdvertisingData {
+        let mut frame = [0; 23];
+        frame[..2].copy_from_slice(&IBEACON_PREFIX);
+        frame[2..18].copy_from_slice(&self.uuid.to_be_bytes());
+        frame[18..20].copy_from_slice(&self.major.to_be_bytes());
+        frame[20..22].copy_from_slice(&self.minor.to_be_bytes());
+        frame[22] = self.measured_power as u8;
+        let mut data = AdvertisingData::new();
+        beacon_flags(&mut data);
+        data.add(AdStructure::ManufacturerSpecificData(APPLE, &frame))
+            .unwrap();
+        data
+    }
+}
+
+/// An Eddystone-UID frame, identifying the beacon.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EddystoneUid {
+    /// The signal strength received 0m away, in dBm.
+    pub tx_power: i8,
+    /// The namespace, identifying the beacons of a deployment.
+    pub namespace: [u8; 10],
+    /// The instance, identifying a beacon in its namespace.
+    pub instance: [u8; 6],
+}
+
+impl EddystoneUid {
+    /// Returns the advertising data of the frame.
+    pub fn advertising_data(&self) -> AdvertisingData {
+        // The last two bytes are reserved
+        let mut frame = [0; 20];
+        frame[0] = EDDYSTONE_UID;
+        frame[1] = self.tx_power as u8;
+        frame[2..12].copy_from_slice(&self.namespace);
+        frame[12..18].copy_from_slice(&self.instance);
+        eddystone_data(&frame)
+    }
+}
+
+/// The error returned when a URL can't be sent in an Eddystone-URL frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidUrl;
+
+/// An Eddystone-URL frame, broadcasting a URL.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EddystoneUrl<'a> {
+    /// The signal strength received 0m away, in dBm.
+    pub tx_power: i8,
+    /// The URL, starting with `http://` or `https://`.
+    pub url: &'a str,
+}
+
+impl EddystoneUrl<'_> {
+    /// Returns the advertising data of the frame.
+    ///
+    /// Common prefixes and suffixes such as `https://www.` and `.com/` are
+    /// encoded as single bytes. Returns an error if the URL doesn't start
+    /// with `http://` or `https://`, contains non-ASCII characters, or is
+    /// longer than [`MAX_URL_LEN`] once encoded.
+    pub fn advertising_data(&self) -> Result<AdvertisingData, InvalidUrl> {
+        let mut frame = [0; 3 + MAX_URL_LEN];
+        frame[0] = EDDYSTONE_URL;
+        frame[1] = self.tx_power as u8;
+        let (scheme, prefix) = URL_SCHEMES
+            .iter()
+            .enumerate()
+            .find(|(_, prefix)| self.url.starts_with(*prefix))
+            .ok_or(InvalidUrl)?;
+        frame[2] = scheme as u8;
+        let mut len = 3;
+        let mut rest = &self.url[prefix.len()..];
+        while !rest.is_empty() {
+            let byte = match URL_EXPANSIONS
+                .iter()
+                .position(|expansion| rest.starts_with(expansion))
+            {
+                Some(code) => {
+                    rest = &rest[URL_EXPANSIONS[code].len()..];
+                    code as u8
+                }
+                None => {
+                    let byte = rest.as_bytes()[0];
+                    // Codes up to 0x20 are expansions or reserved
+                    if !(0x21..0x7f).contains(&byte) {
+                        return Err(InvalidUrl);
+                    }
+                    rest = &rest[1..];
+                    byte
+                }
+            };
+            *frame.get_mut(len).ok_or(InvalidUrl)? = byte;
+            len += 1;
+        }
+        Ok(eddystone_data(&frame[..len]))
+    }
+}
+
+/// An Eddystone-TLM frame, reporting the beacon's telemetry.
+///
+/// TLM frames are meant to be interleaved with UID or URL frames.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EddystoneTlm {
+    /// The battery voltage in mV, or 0 if it isn't known.
+    pub battery: u16,
+    /// The temperature in 1/256 °C, or `None` if it isn't known.
+    pub temperature: Option<i16>,
+    /// The number of advertising events since the beacon started (see
+    /// [`Beacon::advertising_count()`]).
+    pub advertising_count: u32,
+    /// The time since the beacon started (see [`Beacon::uptime()`]).
+    pub uptime: RtcDuration,
+}
+
+impl EddystoneTlm {
+    /// Returns the advertising data of the frame.
+    pub fn advertising_data(&self) -> AdvertisingData {
+        // The temperature is sent as -128 °C when it isn't known
+        let temperature = self.temperature.unwrap_or(i16::MIN);
+        // The uptime is sent in units of 0.1s
+        let uptime = (self.uptime.to_millis() / 100) as u32;
+        let mut frame = [0; 14];
+        frame[0] = EDDYSTONE_TLM;
+        // frame[1] is the TLM version, 0
+        frame[2..4].copy_from_slice(&self.battery.to_be_bytes());
+        frame[4..6].copy_from_slice(&temperature.to_be_bytes());
+        frame[6..10].copy_from_slice(&self.advertising_count.to_be_bytes());
+        frame[10..14].copy_from_slice(&uptime.to_be_bytes());
+        eddystone_data(&frame)
+    }
+}
+
+/// A beacon sending non-connectable advertising, using the `RADIO` and an
+/// `RTC`.
+///
+/// The `RTC` times the advertising events. Between them, the CPU sleeps
+/// until the `RTC` compare event wakes it up, so the `RTC` interrupt must
+/// stay masked in the NVIC.
+///
+/// The HFCLK crystal oscillator and the LFCLK must be running (see
+/// [`start_hfclk()`](crate::clock::start_hfclk) and
+/// [`start_lfclk()`](crate::clock::start_lfclk)).
+pub struct Beacon<T: Instance> {
+    radio: BleRadio,
+    rtc: Rtc<T>,
+    address: DeviceAddress,
+    /// The advertising interval.
+    interval: RtcDuration,
+    /// The `RTC` counter at the next advertising event.
+    next: u32,
+    /// The `RTC` counter at the last advertising event.
+    last: u32,
+    /// The time from the start to the last advertising event.
+    uptime: RtcDuration,
+    count: u32,
+    /// The generator for the random advertising delay.
+    random: Xorshift,
+    tx: [u8; PACKET_BUFFER_LEN],
+}
+
+impl<T: Instance> Beacon<T> {
+    /// Returns a new `Beacon`, using the factory-programmed random static
+    /// address (see [`DeviceAddress::from_ficr()`]).
+    ///
+    /// Takes ownership of the `RADIO` and `RTC` peripherals, and starts the
+    /// `RTC`.
+    pub fn new(radio: RADIO, rtc: T, ficr: &FICR) -> Self {
+        rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+        // A prescaler of 0 is always in range
+        let rtc = Rtc::new(rtc, 0).unwrap();
+        rtc.enable_counter();
+        Beacon {
+            radio: BleRadio::new(radio, ficr),
+            rtc,
+            address: DeviceAddress::from_ficr(ficr),
+            interval: RtcDuration::millis(100),
+            next: 0,
+            last: 0,
+            uptime: RtcDuration::from_ticks(0),
+            count: 0,
+            random: Xorshift::from_ficr(ficr),
+            tx: [0; PACKET_BUFFER_LEN],
+        }
+    }
+
+    /// Disables the radio, stops the `RTC` and gives the `RADIO` and `RTC`
+    /// peripherals back.
+    pub fn free(self) -> (RADIO, T) {
+        self.rtc.disable_counter();
+        (self.radio.free(), self.rtc.release())
+    }
+
+    /// Returns the device address.
+    pub fn address(&self) -> DeviceAddress {
+        self.address
+    }
+
+    /// Sets the device address.
+    pub fn set_address(&mut self, address: DeviceAddress) {
+        self.address = address;
+    }
+
+    /// Sets the transmit power.
+    pub fn set_tx_power(&mut self, power: TxPower) {
+        self.radio.set_tx_power(power);
+    }
+
+    /// Sets the interval between advertising events (100ms by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval isn't between 100ms and 10.24s.
+    pub fn set_interval(&mut self, interval: MillisDurationU32) {
+        assert!((100..=10_240).contains(&interval.ticks()));
+        self.interval = RtcDuration::millis(u64::from(interval.to_millis()));
+    }
+
+    /// Returns the number of advertising events so far.
+    pub fn advertising_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns the time from the start to the last advertising event.
+    pub fn uptime(&self) -> RtcDuration {
+        self.uptime
+    }
+
+    /// Sleeps until the next advertising event is due, then sends the
+    /// advertising data on each advertising channel in turn.
+    ///
+    /// The first advertising event is sent straight away. If this is called
+    /// late, the advertising event is sent straight away and the following
+    /// one is timed from it.
+    pub fn advertise(&mut self, data: &AdvertisingData) {
+        self.sleep_until_next();
+        let now = self.rtc.get_counter();
+        self.uptime +=
+            RtcDuration::from_ticks(u64::from(now.wrapping_sub(self.last) & COUNTER_MASK));
+        self.last = now;
+        self.count = self.count.wrapping_add(1);
+
+        encode_advertising_pdu(
+            AdvertisingPduType::AdvNonconnInd,
+            &self.address,
+            data.as_bytes(),
+            &mut self.tx,
+        );
+        for &channel in ADVERTISING_CHANNELS.iter() {
+            self.radio.set_channel(channel);
+            self.radio.transmit(&self.tx);
+        }
+
+        // Advertising events are spread out by a random delay of up to 10ms
+        let delay = u64::from(self.random.next_u32()) % (MAX_DELAY.ticks() + 1);
+        let until_next = self.interval + RtcDuration::from_ticks(delay);
+        self.next = (now + until_next.ticks() as u32) & COUNTER_MASK;
+    }
+
+    /// Sleeps until the `RTC` counter reaches `self.next`, unless it is
+    /// already past it.
+    fn sleep_until_next(&mut self) {
+        let remaining = self.next.wrapping_sub(self.rtc.get_counter()) & COUNTER_MASK;
+        // More than half the counter range away means the time has passed
+        if !(MIN_COMPARE_TICKS..=COUNTER_MASK / 2).contains(&remaining) {
+            return;
+        }
+        // Compare values are 24 bits, so this can't fail
+        self.rtc
+            .set_compare(RtcCompareReg::Compare0, self.next)
+            .unwrap();
+        self.rtc.reset_event(RtcInterrupt::Compare0);
+        self.rtc.enable_event(RtcInterrupt::Compare0);
+        // With SEVONPEND, the interrupt becoming pending wakes the CPU from
+        // WFE even though it is masked in the NVIC.
+        self.rtc.enable_interrupt(RtcInterrupt::Compare0, None);
+        unsafe {
+            (*SCB::ptr()).scr.modify(|scr| scr | SCR_SEVONPEND);
+        }
+        while !self.rtc.is_event_triggered(RtcInterrupt::Compare0) {
+            cortex_m::asm::wfe();
+        }
+        self.rtc.reset_event(RtcInterrupt::Compare0);
+        self.rtc.disable_interrupt(RtcInterrupt::Compare0, None);
+        self.rtc.disable_event(RtcInterrupt::Compare0);
+        NVIC::unpend(T::INTERRUPT);
+    }
+}
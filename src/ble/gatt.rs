@@ -0,0 +1,188 @@
+//! GATT attribute tables.
+//!
+//! A GATT server is described by a static table of [`Attribute`]s. The
+//! handle of each attribute is its index in the table plus one. Services
+//! are laid out as a [`PrimaryService`](Attribute::PrimaryService) followed
+//! by its characteristics, each of which is a
+//! [`Characteristic`](Attribute::Characteristic) declaration immediately
+//! followed by its [`Value`](Attribute::Value), and then optionally a
+//! [`ClientConfig`](Attribute::ClientConfig) descriptor:
+//!
+//! ```
+//! use microbit::ble::gatt::{Attribute, Properties, Uuid};
+//!
+//! const BATTERY_LEVEL: Uuid = Uuid::Uuid16(0x2a19);
+//!
+//! static ATTRIBUTES: [Attribute; 4] = [
+//!     Attribute::PrimaryService(Uuid::Uuid16(0x180f)),
+//!     Attribute::Characteristic(Properties::READ.union(Properties::NOTIFY), BATTERY_LEVEL),
+//!     Attribute::Value(BATTERY_LEVEL), // handle 3
+//!     Attribute::ClientConfig,
+//! ];
+//! ```
+//!
+//! The values of characteristics are read and written through an
+//! [`AttributeProvider`]. The client configuration descriptors are managed
+//! by the [`AttServer`](super::att::AttServer).
+
+/// A Bluetooth UUID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Uuid {
+    /// A 16-bit UUID assigned by the Bluetooth SIG.
+    Uuid16(u16),
+    /// A full 128-bit UUID.
+    Uuid128(u128),
+}
+
+impl Uuid {
+    /// The Primary Service declaration type.
+    pub const PRIMARY_SERVICE: Uuid = Uuid::Uuid16(0x2800);
+    /// The Characteristic declaration type.
+    pub const CHARACTERISTIC: Uuid = Uuid::Uuid16(0x2803);
+    /// The Client Characteristic Configuration descriptor type.
+    pub const CLIENT_CONFIG: Uuid = Uuid::Uuid16(0x2902);
+
+    /// The Bluetooth base UUID, from which 16-bit UUIDs are expanded.
+    const BASE: u128 = 0x0000_0000_0000_1000_8000_0080_5f9b_34fb;
+
+    /// Parses a UUID from its little-endian encoding (2 or 16 bytes).
+    pub fn from_le_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes.len() {
+            2 => Some(Uuid::Uuid16(u16::from_le_bytes([bytes[0], bytes[1]]))),
+            16 => {
+                let mut uuid = [0; 16];
+                uuid.copy_from_slice(bytes);
+                Some(Uuid::Uuid128(u128::from_le_bytes(uuid)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the length of the UUID's encoding, 2 or 16 bytes.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Uuid::Uuid16(_) => 2,
+            Uuid::Uuid128(_) => 16,
+        }
+    }
+
+    /// Writes the UUID's little-endian encoding to the start of `buf`,
+    /// returning its length.
+    pub fn write_le_bytes(&self, buf: &mut [u8]) -> usize {
+        match self {
+            Uuid::Uuid16(uuid) => buf[..2].copy_from_slice(&uuid.to_le_bytes()),
+            Uuid::Uuid128(uuid) => buf[..16].copy_from_slice(&uuid.to_le_bytes()),
+        }
+        self.encoded_len()
+    }
+
+    /// Returns the full 128-bit form of the UUID.
+    pub fn to_u128(&self) -> u128 {
+        match self {
+            Uuid::Uuid16(uuid) => Uuid::BASE | u128::from(*uuid) << 96,
+            Uuid::Uuid128(uuid) => *uuid,
+        }
+    }
+
+    /// Returns whether two UUIDs are equal, comparing 16-bit UUIDs in their
+    /// expanded form.
+    pub fn matches(&self, other: &Uuid) -> bool {
+        self.to_u128() == other.to_u128()
+    }
+}
+
+/// Characteristic properties.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Properties(pub u8);
+
+impl Properties {
+    /// The value can be read.
+    pub const READ: Properties = Properties(0x02);
+    /// The value can be written without a response.
+    pub const WRITE_WITHOUT_RESPONSE: Properties = Properties(0x04);
+    /// The value can be written.
+    pub const WRITE: Properties = Properties(0x08);
+    /// The value can be notified.
+    pub const NOTIFY: Properties = Properties(0x10);
+    /// The value can be indicated.
+    pub const INDICATE: Properties = Properties(0x20);
+
+    /// Returns the union of two sets of properties.
+    pub const fn union(self, other: Properties) -> Properties {
+        Properties(self.0 | other.0)
+    }
+
+    /// Returns whether all of `other` is set.
+    pub const fn contains(self, other: Properties) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// An entry in an attribute table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Attribute {
+    /// A primary service declaration, with the service's UUID.
+    PrimaryService(Uuid),
+    /// A characteristic declaration, with the characteristic's properties
+    /// and UUID. Its value attribute must follow it.
+    Characteristic(Properties, Uuid),
+    /// A characteristic value, provided by the [`AttributeProvider`].
+    Value(Uuid),
+    /// A Client Characteristic Configuration descriptor for the preceding
+    /// characteristic, managed by the server.
+    ClientConfig,
+    /// A read-only attribute with a constant value.
+    Constant(Uuid, &'static [u8]),
+}
+
+impl Attribute {
+    /// Returns the attribute's type.
+    pub fn attribute_type(&self) -> Uuid {
+        match self {
+            Attribute::PrimaryService(_) => Uuid::PRIMARY_SERVICE,
+            Attribute::Characteristic(..) => Uuid::CHARACTERISTIC,
+            Attribute::Value(uuid) | Attribute::Constant(uuid, _) => *uuid,
+            Attribute::ClientConfig => Uuid::CLIENT_CONFIG,
+        }
+    }
+}
+
+/// An ATT error code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AttError {
+    /// The attribute handle is invalid.
+    InvalidHandle = 0x01,
+    /// The attribute can't be read.
+    ReadNotPermitted = 0x02,
+    /// The attribute can't be written.
+    WriteNotPermitted = 0x03,
+    /// The request was malformed.
+    InvalidPdu = 0x04,
+    /// The request isn't supported.
+    RequestNotSupported = 0x06,
+    /// The offset is past the end of the value.
+    InvalidOffset = 0x07,
+    /// No attribute was found in the handle range.
+    AttributeNotFound = 0x0a,
+    /// The value has the wrong length.
+    InvalidAttributeValueLength = 0x0d,
+    /// The request couldn't be completed for another reason.
+    UnlikelyError = 0x0e,
+    /// The grouping attribute type isn't supported.
+    UnsupportedGroupType = 0x10,
+}
+
+/// The source of a GATT server's attributes and characteristic values.
+pub trait AttributeProvider {
+    /// Returns the attribute table.
+    fn attributes(&self) -> &'static [Attribute];
+
+    /// Reads the [`Value`](Attribute::Value) attribute at `handle` into
+    /// `buf`, returning its length.
+    ///
+    /// Values longer than `buf` should be truncated.
+    fn read(&mut self, handle: u16, buf: &mut [u8]) -> Result<usize, AttError>;
+
+    /// Writes the [`Value`](Attribute::Value) attribute at `handle`.
+    fn write(&mut self, handle: u16, value: &[u8]) -> Result<(), AttError>;
+}
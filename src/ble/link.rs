@@ -0,0 +1,604 @@
+//! Link-layer packet formats.
+//!
+//! Everything here is pure logic, independent of the `RADIO` peripheral:
+//! advertising and data channel PDUs, channel selection, and software
+//! implementations of the CRC and whitening which the radio applies in
+//! hardware.
+//!
+//! Packets are handled in the layout used by the radio's `PACKETPTR`: a
+//! 2-byte header (the `S0` and `LENGTH` fields) followed by the payload.
+
+use core::fmt;
+
+/// The access address used on the advertising channels.
+pub const ADVERTISING_ACCESS_ADDRESS: u32 = 0x8E89_BED6;
+
+/// The CRC initialisation value used on the advertising channels.
+pub const ADVERTISING_CRC_INIT: u32 = 0x55_5555;
+
+/// The CRC polynomial, x²⁴ + x¹⁰ + x⁹ + x⁶ + x⁴ + x³ + x + 1.
+pub const CRC_POLY: u32 = 0x00_065B;
+
+/// The maximum payload length of an advertising channel PDU.
+pub const MAX_ADVERTISING_PAYLOAD: usize = 37;
+
+/// The maximum payload length of a data channel PDU (without the Data
+/// Length Extension).
+pub const MAX_DATA_PAYLOAD: usize = 27;
+
+/// The maximum length of the advertising data in an advertising PDU.
+pub const MAX_ADVERTISING_DATA: usize = 31;
+
+/// The length of a packet buffer: header plus the longest payload.
+pub const PACKET_BUFFER_LEN: usize = 2 + MAX_ADVERTISING_PAYLOAD;
+
+/// The three advertising channel indices.
+pub const ADVERTISING_CHANNELS: [u8; 3] = [37, 38, 39];
+
+/// Returns the frequency of a link-layer channel, as an offset in MHz from
+/// 2400MHz, or `None` if the channel index is out of range.
+pub fn channel_frequency(channel: u8) -> Option<u8> {
+    Some(match channel {
+        0..=10 => 4 + 2 * channel,
+        11..=36 => 6 + 2 * channel,
+        37 => 2,
+        38 => 26,
+        39 => 80,
+        _ => return None,
+    })
+}
+
+/// Calculates the CRC of a PDU (header and payload).
+///
+/// `crc_init` is the value programmed into the radio's `CRCINIT` register.
+/// The result is the three CRC bytes in the order they're transmitted.
+///
+/// ```
+/// use microbit::ble::link::{crc24, ADVERTISING_CRC_INIT};
+///
+/// // ADV_NONCONN_IND from random address E6:D5:C4:B3:A2:C1, with flags
+/// let pdu = [0x42, 0x09, 0xc1, 0xa2, 0xb3, 0xc4, 0xd5, 0xe6, 0x02, 0x01, 0x06];
+/// assert_eq!(crc24(ADVERTISING_CRC_INIT, &pdu), [0x92, 0x2d, 0xf4]);
+/// ```
+pub fn crc24(crc_init: u32, pdu: &[u8]) -> [u8; 3] {
+    let mut crc = crc_init & 0xff_ffff;
+    for &byte in pdu {
+        for bit in 0..8 {
+            let feedback = (crc >> 23) & 1 != u32::from(byte >> bit) & 1;
+            crc = (crc << 1) & 0xff_ffff;
+            if feedback {
+                crc ^= CRC_POLY;
+            }
+        }
+    }
+    [
+        ((crc >> 16) as u8).reverse_bits(),
+        ((crc >> 8) as u8).reverse_bits(),
+        (crc as u8).reverse_bits(),
+    ]
+}
+
+/// Whitens or de-whitens data sent on a channel, in place.
+///
+/// Whitening is its own inverse. It applies to the PDU and CRC.
+///
+/// ```
+/// use microbit::ble::link::whiten;
+///
+/// // The whitening sequence of channel 37
+/// let mut data = [0; 8];
+/// whiten(37, &mut data);
+/// assert_eq!(data, [0x8d, 0xd2, 0x57, 0xa1, 0x3d, 0xa7, 0x66, 0xb0]);
+///
+/// // The ADV_NONCONN_IND of `crc24()`, with its CRC, as sent on channel 37
+/// let packet = [
+///     0x42, 0x09, 0xc1, 0xa2, 0xb3, 0xc4, 0xd5, 0xe6, 0x02, 0x01, 0x06, 0x92, 0x2d, 0xf4,
+/// ];
+/// let mut data = packet;
+/// whiten(37, &mut data);
+/// assert_eq!(
+///     data,
+///     [0xcf, 0xdb, 0x96, 0x03, 0x8e, 0x63, 0xb3, 0x56, 0x77, 0x30, 0x17, 0xda, 0xbb, 0x83]
+/// );
+/// whiten(37, &mut data);
+/// assert_eq!(data, packet);
+/// ```
+pub fn whiten(channel: u8, data: &mut [u8]) {
+    // The 7-bit LFSR, shifted left by one bit.
+    let mut lfsr = channel.reverse_bits() | 2;
+    for byte in data {
+        let mut mask = 1;
+        while mask != 0 {
+            if lfsr & 0x80 != 0 {
+                lfsr ^= 0x11;
+                *byte ^= mask;
+            }
+            lfsr <<= 1;
+            mask <<= 1;
+        }
+    }
+}
+
+/// The type of a device address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressKind {
+    /// A public (IEEE-assigned) address.
+    Public,
+    /// A random address.
+    Random,
+}
+
+/// A 48-bit Bluetooth device address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceAddress {
+    /// The address bytes, least significant first (the order they're sent).
+    pub bytes: [u8; 6],
+    /// The type of the address.
+    pub kind: AddressKind,
+}
+
+impl DeviceAddress {
+    /// Returns a new `DeviceAddress`.
+    pub const fn new(bytes: [u8; 6], kind: AddressKind) -> Self {
+        DeviceAddress { bytes, kind }
+    }
+
+    /// Returns the nRF51's factory-programmed random static address.
+    pub fn from_ficr(ficr: &crate::pac::FICR) -> Self {
+        let low = ficr.deviceaddr[0].read().bits().to_le_bytes();
+        let high = ficr.deviceaddr[1].read().bits().to_le_bytes();
+        DeviceAddress {
+            // The two most significant bits of a random static address are 1
+            bytes: [low[0], low[1], low[2], low[3], high[0], high[1] | 0xc0],
+            kind: AddressKind::Random,
+        }
+    }
+
+    fn tx_add(&self) -> u8 {
+        match self.kind {
+            AddressKind::Public => 0,
+            AddressKind::Random => 1 << 6,
+        }
+    }
+
+    fn parse(bytes: &[u8], random: bool) -> Self {
+        let mut address = [0; 6];
+        address.copy_from_slice(&bytes[..6]);
+        DeviceAddress {
+            bytes: address,
+            kind: if random {
+                AddressKind::Random
+            } else {
+                AddressKind::Public
+            },
+        }
+    }
+}
+
+impl fmt::Display for DeviceAddress {
+    /// Formats the address most significant byte first, as
+    /// `c6:05:04:03:02:01`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let b = &self.bytes;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            b[5], b[4], b[3], b[2], b[1], b[0]
+        )
+    }
+}
+
+/// The PDU types used on the advertising channels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdvertisingPduType {
+    /// Connectable and scannable undirected advertising.
+    AdvInd = 0,
+    /// Connectable directed advertising.
+    AdvDirectInd = 1,
+    /// Non-connectable and non-scannable undirected advertising.
+    AdvNonconnInd = 2,
+    /// A scan request.
+    ScanReq = 3,
+    /// A scan response.
+    ScanRsp = 4,
+    /// A connection request.
+    ConnectReq = 5,
+    /// Scannable undirected advertising.
+    AdvScanInd = 6,
+}
+
+impl AdvertisingPduType {
+    fn from_bits(bits: u8) -> Option<Self> {
+        use AdvertisingPduType::*;
+        Some(match bits {
+            0 => AdvInd,
+            1 => AdvDirectInd,
+            2 => AdvNonconnInd,
+            3 => ScanReq,
+            4 => ScanRsp,
+            5 => ConnectReq,
+            6 => AdvScanInd,
+            _ => return None,
+        })
+    }
+}
+
+/// Encodes an advertising PDU carrying an address and data: `ADV_IND`,
+/// `ADV_NONCONN_IND`, `ADV_SCAN_IND` or `SCAN_RSP`.
+///
+/// Returns the packet length (header plus payload).
+///
+/// # Panics
+///
+/// Panics if `data` is longer than [`MAX_ADVERTISING_DATA`], if `buf` is
+/// too short or if `pdu_type` doesn't carry advertising data.
+pub fn encode_advertising_pdu(
+    pdu_type: AdvertisingPduType,
+    address: &DeviceAddress,
+    data: &[u8],
+    buf: &mut [u8],
+) -> usize {
+    use AdvertisingPduType::*;
+    assert!(matches!(
+        pdu_type,
+        AdvInd | AdvNonconnInd | AdvScanInd | ScanRsp
+    ));
+    assert!(data.len() <= MAX_ADVERTISING_DATA);
+    let payload_len = 6 + data.len();
+    buf[0] = pdu_type as u8 | address.tx_add();
+    buf[1] = payload_len as u8;
+    buf[2..8].copy_from_slice(&address.bytes);
+    buf[8..8 + data.len()].copy_from_slice(data);
+    2 + payload_len
+}
+
+/// The parameters of a new connection, from a `CONNECT_REQ` PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectRequest {
+    /// The initiator's address.
+    pub initiator: DeviceAddress,
+    /// The advertiser's address.
+    pub advertiser: DeviceAddress,
+    /// The access address of the connection.
+    pub access_address: u32,
+    /// The CRC initialisation value of the connection.
+    pub crc_init: u32,
+    /// The transmit window size, in units of 1.25ms.
+    pub window_size: u8,
+    /// The transmit window offset, in units of 1.25ms.
+    pub window_offset: u16,
+    /// The connection interval, in units of 1.25ms.
+    pub interval: u16,
+    /// The slave latency, in connection events.
+    pub latency: u16,
+    /// The supervision timeout, in units of 10ms.
+    pub timeout: u16,
+    /// The data channels in use.
+    pub channel_map: ChannelMap,
+    /// The hop increment of the channel selection algorithm.
+    pub hop: u8,
+    /// The master's sleep clock accuracy, in ppm.
+    pub master_sca_ppm: u16,
+}
+
+/// A parsed advertising channel PDU received from a scanner or initiator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdvertisingRequest {
+    /// A scan request.
+    ScanReq {
+        /// The scanner's address.
+        scanner: DeviceAddress,
+        /// The address of the advertiser being scanned.
+        advertiser: DeviceAddress,
+    },
+    /// A connection request.
+    ConnectReq(ConnectRequest),
+}
+
+impl AdvertisingRequest {
+    /// Parses a packet (header plus payload) received on an advertising
+    /// channel, returning `None` if it isn't a valid `SCAN_REQ` or
+    /// `CONNECT_REQ`.
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+        if packet.len() < 2 {
+            return None;
+        }
+        let header = packet[0];
+        let len = usize::from(packet[1] & 0x3f);
+        let payload = packet.get(2..2 + len)?;
+        let tx_add = header & (1 << 6) != 0;
+        let rx_add = header & (1 << 7) != 0;
+        match AdvertisingPduType::from_bits(header & 0x0f)? {
+            AdvertisingPduType::ScanReq if len == 12 => Some(AdvertisingRequest::ScanReq {
+                scanner: DeviceAddress::parse(&payload[0..6], tx_add),
+                advertiser: DeviceAddress::parse(&payload[6..12], rx_add),
+            }),
+            AdvertisingPduType::ConnectReq if len == 34 => {
+                let u16_at = |i: usize| u16::from_le_bytes([payload[i], payload[i + 1]]);
+                let mut channel_map = [0; 5];
+                channel_map.copy_from_slice(&payload[28..33]);
+                let channel_map = ChannelMap::new(channel_map)?;
+                let hop = payload[33] & 0x1f;
+                if !(5..=16).contains(&hop) {
+                    return None;
+                }
+                Some(AdvertisingRequest::ConnectReq(ConnectRequest {
+                    initiator: DeviceAddress::parse(&payload[0..6], tx_add),
+                    advertiser: DeviceAddress::parse(&payload[6..12], rx_add),
+                    access_address: u32::from_le_bytes([
+                        payload[12],
+                        payload[13],
+                        payload[14],
+                        payload[15],
+                    ]),
+                    crc_init: u32::from_le_bytes([payload[16], payload[17], payload[18], 0]),
+                    window_size: payload[19],
+                    window_offset: u16_at(20),
+                    interval: u16_at(22),
+                    latency: u16_at(24),
+                    timeout: u16_at(26),
+                    channel_map,
+                    hop,
+                    master_sca_ppm: sca_ppm(payload[33] >> 5),
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A parsed advertising channel PDU sent by an advertiser: `ADV_IND`,
+/// `ADV_DIRECT_IND`, `ADV_NONCONN_IND`, `ADV_SCAN_IND` or `SCAN_RSP`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Advertisement<'a> {
+    /// The type of the PDU.
+    pub pdu_type: AdvertisingPduType,
+    /// The advertiser's address.
+    pub advertiser: DeviceAddress,
+    /// The address of the initiator, for `ADV_DIRECT_IND`.
+    pub target: Option<DeviceAddress>,
+    /// The advertising data (see [`AdStructures`](super::ad::AdStructures)),
+    /// which is empty for `ADV_DIRECT_IND`.
+    pub data: &'a [u8],
+}
+
+impl<'a> Advertisement<'a> {
+    /// Parses a packet (header plus payload) received on an advertising
+    /// channel, returning `None` if it isn't a valid PDU sent by an
+    /// advertiser.
+    ///
+    /// ```
+    /// use microbit::ble::link::{
+    ///     encode_advertising_pdu, AddressKind, Advertisement, AdvertisingPduType, DeviceAddress,
+    ///     PACKET_BUFFER_LEN,
+    /// };
+    ///
+    /// let address = DeviceAddress::new([1, 2, 3, 4, 5, 0xc6], AddressKind::Random);
+    /// let mut packet = [0; PACKET_BUFFER_LEN];
+    /// encode_advertising_pdu(AdvertisingPduType::AdvInd, &address, b"\x02\x01\x06", &mut packet);
+    /// let advertisement = Advertisement::parse(&packet).unwrap();
+    /// assert_eq!(advertisement.pdu_type, AdvertisingPduType::AdvInd);
+    /// assert_eq!(advertisement.advertiser, address);
+    /// assert_eq!(advertisement.data, b"\x02\x01\x06");
+    /// ```
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+        if packet.len() < 2 {
+            return None;
+        }
+        let header = packet[0];
+        let len = usize::from(packet[1] & 0x3f);
+        let payload = packet.get(2..2 + len)?;
+        let tx_add = header & (1 << 6) != 0;
+        let rx_add = header & (1 << 7) != 0;
+        let pdu_type = AdvertisingPduType::from_bits(header & 0x0f)?;
+        let (target, data) = match pdu_type {
+            AdvertisingPduType::AdvDirectInd if len == 12 => (
+                Some(DeviceAddress::parse(&payload[6..12], rx_add)),
+                &payload[12..],
+            ),
+            AdvertisingPduType::AdvInd
+            | AdvertisingPduType::AdvNonconnInd
+            | AdvertisingPduType::AdvScanInd
+            | AdvertisingPduType::ScanRsp
+                if (6..=6 + MAX_ADVERTISING_DATA).contains(&len) =>
+            {
+                (None, &payload[6..])
+            }
+            _ => return None,
+        };
+        Some(Advertisement {
+            pdu_type,
+            advertiser: DeviceAddress::parse(&payload[0..6], tx_add),
+            target,
+            data,
+        })
+    }
+}
+
+/// Returns the worst-case sleep clock accuracy for an `SCA` field value.
+fn sca_ppm(sca: u8) -> u16 {
+    [500, 250, 150, 100, 75, 50, 30, 20][usize::from(sca & 7)]
+}
+
+/// The set of data channels used by a connection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChannelMap {
+    map: [u8; 5],
+    used: u8,
+}
+
+impl ChannelMap {
+    /// Returns a channel map from its 5-byte encoding, or `None` if fewer
+    /// than two channels are used.
+    pub fn new(mut map: [u8; 5]) -> Option<Self> {
+        // Only channels 0 to 36 exist
+        map[4] &= 0x1f;
+        let used = map.iter().map(|byte| byte.count_ones() as u8).sum();
+        if used < 2 {
+            return None;
+        }
+        Some(ChannelMap { map, used })
+    }
+
+    /// Returns whether a data channel is used.
+    pub fn is_used(&self, channel: u8) -> bool {
+        channel < 37 && self.map[usize::from(channel / 8)] & (1 << (channel % 8)) != 0
+    }
+
+    /// Returns the number of channels used.
+    pub fn num_used(&self) -> u8 {
+        self.used
+    }
+
+    /// Returns the `index`th used channel.
+    fn used_channel(&self, index: u8) -> u8 {
+        (0..37)
+            .filter(|&channel| self.is_used(channel))
+            .nth(usize::from(index))
+            .unwrap()
+    }
+}
+
+/// Channel selection algorithm #1.
+#[derive(Copy, Clone, Debug)]
+pub struct ChannelSelector {
+    hop: u8,
+    last_unmapped: u8,
+}
+
+impl ChannelSelector {
+    /// Returns a new channel selector with the hop increment from the
+    /// `CONNECT_REQ`.
+    pub fn new(hop: u8) -> Self {
+        ChannelSelector {
+            hop,
+            last_unmapped: 0,
+        }
+    }
+
+    /// Returns the data channel of the next connection event.
+    pub fn next_channel(&mut self, map: &ChannelMap) -> u8 {
+        let unmapped = (self.last_unmapped + self.hop) % 37;
+        self.last_unmapped = unmapped;
+        if map.is_used(unmapped) {
+            unmapped
+        } else {
+            map.used_channel(unmapped % map.num_used())
+        }
+    }
+}
+
+/// The `LLID` field of a data channel PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Llid {
+    /// A continuation fragment of an L2CAP message, or an empty PDU.
+    Continuation = 1,
+    /// The start of an L2CAP message, or a complete message.
+    Start = 2,
+    /// An LL control PDU.
+    Control = 3,
+}
+
+/// The header of a data channel PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DataHeader {
+    /// The type of the payload, or `None` for the reserved value.
+    pub llid: Option<Llid>,
+    /// The next expected sequence number.
+    pub nesn: bool,
+    /// The sequence number.
+    pub sn: bool,
+    /// Whether the sender has more data.
+    pub md: bool,
+    /// The payload length.
+    pub len: u8,
+}
+
+impl DataHeader {
+    /// Parses the 2-byte header.
+    pub fn parse(header: [u8; 2]) -> Self {
+        DataHeader {
+            llid: match header[0] & 3 {
+                1 => Some(Llid::Continuation),
+                2 => Some(Llid::Start),
+                3 => Some(Llid::Control),
+                _ => None,
+            },
+            nesn: header[0] & (1 << 2) != 0,
+            sn: header[0] & (1 << 3) != 0,
+            md: header[0] & (1 << 4) != 0,
+            len: header[1],
+        }
+    }
+
+    /// Returns the 2-byte encoding of the header.
+    pub fn encode(&self) -> [u8; 2] {
+        let llid = self.llid.map_or(0, |llid| llid as u8);
+        [
+            llid | (self.nesn as u8) << 2 | (self.sn as u8) << 3 | (self.md as u8) << 4,
+            self.len,
+        ]
+    }
+}
+
+/// LL control PDU opcodes.
+pub mod control {
+    /// `LL_CONNECTION_UPDATE_IND`
+    pub const CONNECTION_UPDATE_IND: u8 = 0x00;
+    /// `LL_CHANNEL_MAP_IND`
+    pub const CHANNEL_MAP_IND: u8 = 0x01;
+    /// `LL_TERMINATE_IND`
+    pub const TERMINATE_IND: u8 = 0x02;
+    /// `LL_ENC_REQ`
+    pub const ENC_REQ: u8 = 0x03;
+    /// `LL_UNKNOWN_RSP`
+    pub const UNKNOWN_RSP: u8 = 0x07;
+    /// `LL_FEATURE_REQ`
+    pub const FEATURE_REQ: u8 = 0x08;
+    /// `LL_FEATURE_RSP`
+    pub const FEATURE_RSP: u8 = 0x09;
+    /// `LL_VERSION_IND`
+    pub const VERSION_IND: u8 = 0x0c;
+    /// `LL_REJECT_IND`
+    pub const REJECT_IND: u8 = 0x0d;
+    /// `LL_PING_REQ`
+    pub const PING_REQ: u8 = 0x12;
+    /// `LL_PING_RSP`
+    pub const PING_RSP: u8 = 0x13;
+}
+
+/// A connection parameter update, from an `LL_CONNECTION_UPDATE_IND`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionUpdate {
+    /// The transmit window size, in units of 1.25ms.
+    pub window_size: u8,
+    /// The transmit window offset, in units of 1.25ms.
+    pub window_offset: u16,
+    /// The new connection interval, in units of 1.25ms.
+    pub interval: u16,
+    /// The new slave latency.
+    pub latency: u16,
+    /// The new supervision timeout, in units of 10ms.
+    pub timeout: u16,
+    /// The connection event count at which the update takes effect.
+    pub instant: u16,
+}
+
+impl ConnectionUpdate {
+    /// Parses the control data (after the opcode) of an
+    /// `LL_CONNECTION_UPDATE_IND`.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != 11 {
+            return None;
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        Some(ConnectionUpdate {
+            window_size: data[0],
+            window_offset: u16_at(1),
+            interval: u16_at(3),
+            latency: u16_at(5),
+            timeout: u16_at(7),
+            instant: u16_at(9),
+        })
+    }
+}
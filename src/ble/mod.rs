@@ -0,0 +1,72 @@
+//! Bluetooth Low Energy peripheral support, using the nRF51822's `RADIO`.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - a [`BlePeripheral`], which sends connectable, scannable or
+//!   non-connectable advertising and runs connections in the slave role
+//! - [advertising data](ad) encoding and parsing
+//! - a minimal [GATT server](att::AttServer) over a static
+//!   [attribute table](gatt)
+//! - the standard [micro:bit services](services): LED, button, accelerometer
+//!   and UART
+//! - [iBeacon and Eddystone beacons](beacon) and a passive
+//!   [scanner](scanner), which only need the `RADIO` and an `RTC`.
+//!
+//! The [link-layer packet formats](link), including the CRC and whitening
+//! which the radio applies in hardware, are pure logic, independent of the
+//! `RADIO` peripheral.
+//!
+//! Only the minimum ATT MTU is supported, without pairing or encryption.
+//!
+//! This module is only available with the `ble` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     ble::{
+//!         ad::{AdStructure, AdvertisingData, Flags},
+//!         att::AttServer,
+//!         services::MicrobitServices,
+//!         AdvertisingKind, BlePeripheral,
+//!     },
+//!     clock,
+//! };
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! clock::start_hfclk(&p.CLOCK);
+//!
+//! let mut ble = BlePeripheral::new(p.RADIO, p.TIMER0, &p.PPI, &p.FICR);
+//! let mut server = AttServer::new(MicrobitServices::new("BBC micro:bit"));
+//!
+//! let mut data = AdvertisingData::new();
+//! data.add(AdStructure::Flags(Flags::LE_GENERAL_DISCOVERABLE | Flags::BR_EDR_NOT_SUPPORTED))
+//!     .unwrap();
+//! data.add(AdStructure::CompleteLocalName("BBC micro:bit")).unwrap();
+//!
+//! loop {
+//!     if let Some(mut connection) =
+//!         ble.advertise(AdvertisingKind::Connectable, &data, &AdvertisingData::new())
+//!     {
+//!         while ble.connection_event(&mut connection, &mut server).is_ok() {
+//!             // update the services and handle their events
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! See a working example at `examples/ble_microbit_services.rs`
+
+pub mod ad;
+pub mod att;
+pub mod beacon;
+pub mod gatt;
+pub mod link;
+mod peripheral;
+pub mod radio;
+mod random;
+pub mod scanner;
+pub mod services;
+
+pub use peripheral::{AdvertisingKind, BlePeripheral, Connection, Disconnected};
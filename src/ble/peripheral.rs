@@ -0,0 +1,803 @@
+//! Advertising and the slave role of a connection.
+
+use crate::{
+    pac::{FICR, PPI, RADIO, TIMER0},
+    time::MillisDurationU32,
+};
+
+use super::{
+    ad::AdvertisingData,
+    att::{AttServer, NotifyError, MTU},
+    gatt::AttributeProvider,
+    link::{
+        control, encode_advertising_pdu, AdvertisingPduType, AdvertisingRequest, ChannelMap,
+        ChannelSelector, ConnectRequest, ConnectionUpdate, DataHeader, DeviceAddress, Llid,
+        ADVERTISING_ACCESS_ADDRESS, ADVERTISING_CHANNELS, ADVERTISING_CRC_INIT, MAX_DATA_PAYLOAD,
+        PACKET_BUFFER_LEN,
+    },
+    radio::{BleRadio, TxPower, DISABLED_RXEN, DISABLED_TXEN, END_DISABLE, READY_START},
+    random::Xorshift,
+};
+
+/// The `TIMER` prescaler giving 1MHz from the 16MHz base clock.
+const PRESCALER: u32 = 4;
+
+/// The `TIMER0` capture register holding the time of the last `RADIO`
+/// `ADDRESS` event, set by pre-programmed PPI channel 26.
+const CC_ADDRESS: usize = 1;
+/// The `TIMER0` capture register holding the time of the last `RADIO` `END`
+/// event, set by pre-programmed PPI channel 27.
+const CC_END: usize = 2;
+/// The `TIMER0` capture register used to read the counter.
+const CC_NOW: usize = 3;
+
+/// The pre-programmed PPI channels 26 and 27.
+const PPI_CHANNELS: u32 = 1 << 26 | 1 << 27;
+
+/// The inter-frame space, in µs.
+const T_IFS: u32 = 150;
+/// The time from the start of a packet to its `ADDRESS` event (the preamble
+/// and access address), in µs.
+const ADDRESS_DELAY: u32 = 40;
+/// The radio's receiver ramp-up time, with some margin, in µs.
+const RX_RAMP_UP: u32 = 150;
+/// Extra time allowed for a packet to arrive, in µs.
+const RX_MARGIN: u32 = 50;
+/// The accuracy of the `TIMER`, running from the HFCLK crystal, in ppm.
+const LOCAL_SCA_PPM: u32 = 50;
+/// The time between the end of a `CONNECT_REQ` and the transmit window
+/// offset, in µs.
+const TRANSMIT_WINDOW_DELAY: u32 = 1250;
+/// The time before the next connection event at which a connection event is
+/// closed, in µs.
+const EVENT_END_MARGIN: u32 = 1250;
+/// The number of connection events without a packet after which a new
+/// connection is considered to have failed.
+const ESTABLISH_EVENTS: u16 = 6;
+
+/// The L2CAP channel of the Attribute Protocol.
+const ATT_CHANNEL: u16 = 0x0004;
+/// The L2CAP LE signaling channel.
+const SIGNALING_CHANNEL: u16 = 0x0005;
+/// The L2CAP channel of the Security Manager Protocol.
+const SECURITY_CHANNEL: u16 = 0x0006;
+
+/// The L2CAP `Command Reject` signaling code.
+const COMMAND_REJECT: u8 = 0x01;
+/// The L2CAP `Connection Parameter Update Response` signaling code.
+const CONNECTION_PARAMETER_UPDATE_RSP: u8 = 0x13;
+/// The SMP `Pairing Request` code.
+const PAIRING_REQUEST: u8 = 0x01;
+/// The SMP `Pairing Failed` code.
+const PAIRING_FAILED: u8 = 0x05;
+/// The SMP `Pairing Not Supported` reason.
+const PAIRING_NOT_SUPPORTED: u8 = 0x05;
+
+/// The `VersNr` of Bluetooth 4.0, sent in `LL_VERSION_IND`.
+const BLUETOOTH_4_0: u8 = 6;
+/// The `Unsupported Remote Feature` error code.
+const UNSUPPORTED_REMOTE_FEATURE: u8 = 0x1a;
+
+/// The number of data PDUs which can be queued in each direction.
+const QUEUE_LEN: usize = 4;
+
+/// The length of a data PDU, header included.
+const DATA_PDU_LEN: usize = 2 + MAX_DATA_PAYLOAD;
+
+/// The kind of advertising PDU to send.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdvertisingKind {
+    /// Connectable and scannable advertising (`ADV_IND`).
+    Connectable,
+    /// Scannable, non-connectable advertising (`ADV_SCAN_IND`).
+    Scannable,
+    /// Non-connectable, non-scannable advertising (`ADV_NONCONN_IND`).
+    NonConnectable,
+}
+
+impl AdvertisingKind {
+    fn pdu_type(self) -> AdvertisingPduType {
+        match self {
+            AdvertisingKind::Connectable => AdvertisingPduType::AdvInd,
+            AdvertisingKind::Scannable => AdvertisingPduType::AdvScanInd,
+            AdvertisingKind::NonConnectable => AdvertisingPduType::AdvNonconnInd,
+        }
+    }
+}
+
+/// The reason a connection ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Disconnected {
+    /// Nothing was received from the central within the supervision
+    /// timeout, or the connection was never established.
+    Timeout,
+    /// The central terminated the connection, with this error code.
+    Terminated(u8),
+}
+
+/// A BLE peripheral, using the `RADIO` and `TIMER0` peripherals.
+///
+/// `TIMER0` counts microseconds to time the radio, with pre-programmed PPI
+/// channels 26 and 27 capturing the time of each packet, so it can't be
+/// shared with the RTIC monotonic timer.
+///
+/// All the methods block, busy-waiting until the radio is done.
+pub struct BlePeripheral {
+    radio: BleRadio,
+    timer: TIMER0,
+    address: DeviceAddress,
+    /// The advertising interval, in µs.
+    interval: u32,
+    /// The generator for the random advertising delay.
+    random: Xorshift,
+    tx: [u8; PACKET_BUFFER_LEN],
+    rx: [u8; PACKET_BUFFER_LEN],
+    scan_response: [u8; PACKET_BUFFER_LEN],
+}
+
+impl BlePeripheral {
+    /// Returns a new `BlePeripheral`, using the factory-programmed random
+    /// static address (see [`DeviceAddress::from_ficr()`]).
+    ///
+    /// Takes ownership of the `RADIO` and `TIMER0` peripherals, and enables
+    /// PPI channels 26 and 27.
+    ///
+    /// The HFCLK crystal oscillator must be running (see
+    /// [`start_hfclk()`](crate::clock::start_hfclk)).
+    pub fn new(radio: RADIO, timer: TIMER0, ppi: &PPI, ficr: &FICR) -> Self {
+        timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.bits(PRESCALER) });
+        timer.shorts.reset();
+        timer.intenclr.write(|w| unsafe { w.bits(0xffff_ffff) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        ppi.chenset.write(|w| unsafe { w.bits(PPI_CHANNELS) });
+        BlePeripheral {
+            radio: BleRadio::new(radio, ficr),
+            timer,
+            address: DeviceAddress::from_ficr(ficr),
+            interval: 100_000,
+            random: Xorshift::from_ficr(ficr),
+            tx: [0; PACKET_BUFFER_LEN],
+            rx: [0; PACKET_BUFFER_LEN],
+            scan_response: [0; PACKET_BUFFER_LEN],
+        }
+    }
+
+    /// Disables the radio, stops the timer and gives the `RADIO` and
+    /// `TIMER0` peripherals back.
+    pub fn free(self) -> (RADIO, TIMER0) {
+        self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        (self.radio.free(), self.timer)
+    }
+
+    /// Returns the device address.
+    pub fn address(&self) -> DeviceAddress {
+        self.address
+    }
+
+    /// Sets the device address.
+    pub fn set_address(&mut self, address: DeviceAddress) {
+        self.address = address;
+    }
+
+    /// Sets the transmit power.
+    pub fn set_tx_power(&mut self, power: TxPower) {
+        self.radio.set_tx_power(power);
+    }
+
+    /// Sets the interval between advertising events (100ms by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval isn't between 20ms and 10.24s.
+    pub fn set_advertising_interval(&mut self, interval: MillisDurationU32) {
+        assert!((20..=10_240).contains(&interval.ticks()));
+        self.interval = interval.to_micros();
+    }
+
+    /// Runs one advertising event, sending the advertising data on each
+    /// advertising channel in turn.
+    ///
+    /// `scan_response` is sent in reply to scan requests, unless `kind` is
+    /// [`NonConnectable`](AdvertisingKind::NonConnectable).
+    ///
+    /// If a central connects, returns the new connection straight away, to
+    /// be run with [`connection_event()`](BlePeripheral::connection_event).
+    /// Otherwise returns `None` at the end of the advertising interval.
+    pub fn advertise(
+        &mut self,
+        kind: AdvertisingKind,
+        data: &AdvertisingData,
+        scan_response: &AdvertisingData,
+    ) -> Option<Connection> {
+        let start = self.now();
+        encode_advertising_pdu(
+            kind.pdu_type(),
+            &self.address,
+            data.as_bytes(),
+            &mut self.tx,
+        );
+        encode_advertising_pdu(
+            AdvertisingPduType::ScanRsp,
+            &self.address,
+            scan_response.as_bytes(),
+            &mut self.scan_response,
+        );
+        self.radio
+            .set_access_address(ADVERTISING_ACCESS_ADDRESS, ADVERTISING_CRC_INIT);
+        for &channel in ADVERTISING_CHANNELS.iter() {
+            self.radio.set_channel(channel);
+            if let Some(connection) = self.advertise_on_channel(kind) {
+                return Some(connection);
+            }
+        }
+        // Advertising events are spread out by a random delay of up to 10ms
+        let delay = self.interval + self.random.next_u32() % 10_000;
+        self.wait_until(start.wrapping_add(delay));
+        None
+    }
+
+    /// Sends the advertising PDU on the current channel and answers any
+    /// request which follows.
+    fn advertise_on_channel(&mut self, kind: AdvertisingKind) -> Option<Connection> {
+        if kind == AdvertisingKind::NonConnectable {
+            self.radio.transmit(&self.tx);
+            return None;
+        }
+        self.radio.clear_events();
+        self.radio.set_packet_ptr(&self.tx);
+        self.radio
+            .set_shorts(READY_START | END_DISABLE | DISABLED_RXEN);
+        self.radio.start_tx();
+        // PACKETPTR is double-buffered, so once the advertising PDU has
+        // started this applies to the packet received after it.
+        while !self.radio.take_address() {}
+        self.radio.set_packet_ptr(&self.rx);
+        self.radio.wait_disabled();
+        self.radio.clear_events();
+        // Get ready to send the scan response within T_IFS of a request
+        self.radio
+            .set_shorts(READY_START | END_DISABLE | DISABLED_TXEN);
+        let deadline = self
+            .captured(CC_END)
+            .wrapping_add(T_IFS + ADDRESS_DELAY + RX_MARGIN);
+        if !self.wait_for_address(deadline) {
+            return None;
+        }
+        self.radio.set_packet_ptr(&self.scan_response);
+        self.radio.wait_end();
+        let request = if self.radio.crc_ok() {
+            AdvertisingRequest::parse(&self.rx)
+        } else {
+            None
+        };
+        match request {
+            Some(AdvertisingRequest::ScanReq { advertiser, .. }) if advertiser == self.address => {
+                self.radio.wait_disabled();
+                self.radio.set_shorts(READY_START | END_DISABLE);
+                self.radio.wait_disabled();
+                None
+            }
+            Some(AdvertisingRequest::ConnectReq(request))
+                if kind == AdvertisingKind::Connectable && request.advertiser == self.address =>
+            {
+                let end = self.captured(CC_END);
+                self.radio.disable();
+                Some(Connection::new(request, end))
+            }
+            _ => {
+                // Abort the scan response
+                self.radio.disable();
+                None
+            }
+        }
+    }
+
+    /// Runs the next connection event, serving `server` over the
+    /// connection.
+    ///
+    /// This blocks until the connection event is over, which is about one
+    /// connection interval. ATT requests are answered in the following
+    /// connection event.
+    ///
+    /// Connection events which start before this is called are skipped,
+    /// which is fine as long as the supervision timeout doesn't expire.
+    ///
+    /// When the connection ends, the server is [reset](AttServer::reset)
+    /// and the reason is returned; the `Connection` shouldn't be used
+    /// again.
+    pub fn connection_event<P: AttributeProvider>(
+        &mut self,
+        connection: &mut Connection,
+        server: &mut AttServer<P>,
+    ) -> Result<(), Disconnected> {
+        let result = self.run_connection_event(connection, server);
+        if result.is_err() {
+            server.reset();
+        }
+        result
+    }
+
+    fn run_connection_event<P: AttributeProvider>(
+        &mut self,
+        connection: &mut Connection,
+        server: &mut AttServer<P>,
+    ) -> Result<(), Disconnected> {
+        while before(
+            connection.listen_start(),
+            self.now().wrapping_add(RX_MARGIN),
+        ) {
+            connection.advance();
+            connection.check_timeout(self.now())?;
+        }
+
+        self.radio
+            .set_access_address(connection.access_address, connection.crc_init);
+        self.radio.set_channel(connection.channel);
+        self.radio.clear_events();
+        self.radio.set_packet_ptr(&self.rx);
+        self.radio
+            .set_shorts(READY_START | END_DISABLE | DISABLED_TXEN);
+        self.wait_until(connection.listen_start());
+        self.radio.start_rx();
+        let deadline = connection
+            .anchor
+            .wrapping_add(connection.window + connection.widening() + ADDRESS_DELAY + RX_MARGIN);
+        let received = self.wait_for_address(deadline);
+        if received {
+            connection.anchor = self.captured(CC_ADDRESS).wrapping_sub(ADDRESS_DELAY);
+            self.exchange_packets(connection);
+        }
+
+        connection.process(server)?;
+        connection.end_event(received);
+        connection.check_timeout(self.now())
+    }
+
+    /// Exchanges packets with the central until neither has more data, once
+    /// the first packet of a connection event has been found.
+    fn exchange_packets(&mut self, connection: &mut Connection) {
+        let end_by = connection
+            .anchor
+            .wrapping_add(connection.interval)
+            .wrapping_sub(EVENT_END_MARGIN);
+        let mut crc_errors = 0;
+        loop {
+            self.radio.wait_end();
+            let mut more = false;
+            if self.radio.crc_ok() {
+                crc_errors = 0;
+                more = connection.receive(&self.rx, self.captured(CC_END));
+            } else {
+                crc_errors += 1;
+            }
+            // The response is sent T_IFS after the packet ends
+            more |= connection.prepare_tx(&mut self.tx);
+            self.radio.set_packet_ptr(&self.tx);
+            self.radio.wait_disabled();
+            // Two CRC errors in a row close the connection event
+            let more = more && crc_errors < 2 && before(self.now(), end_by);
+            let shorts = if more { DISABLED_RXEN } else { 0 };
+            self.radio.set_shorts(READY_START | END_DISABLE | shorts);
+            while !self.radio.take_address() {}
+            self.radio.set_packet_ptr(&self.rx);
+            self.radio.wait_disabled();
+            self.radio.clear_events();
+            if !more {
+                break;
+            }
+            self.radio
+                .set_shorts(READY_START | END_DISABLE | DISABLED_TXEN);
+            let deadline = self
+                .captured(CC_END)
+                .wrapping_add(T_IFS + ADDRESS_DELAY + RX_MARGIN);
+            if !self.wait_for_address(deadline) {
+                break;
+            }
+        }
+    }
+
+    /// Waits for the `ADDRESS` event until `deadline`, disabling the radio if
+    /// it doesn't happen.
+    fn wait_for_address(&mut self, deadline: u32) -> bool {
+        while !self.radio.take_address() {
+            if !before(self.now(), deadline) {
+                self.radio.disable();
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the current time, in µs.
+    fn now(&self) -> u32 {
+        self.timer.tasks_capture[CC_NOW].write(|w| unsafe { w.bits(1) });
+        self.timer.cc[CC_NOW].read().bits()
+    }
+
+    /// Returns the time in a capture register.
+    fn captured(&self, cc: usize) -> u32 {
+        self.timer.cc[cc].read().bits()
+    }
+
+    fn wait_until(&self, time: u32) {
+        while before(self.now(), time) {}
+    }
+}
+
+/// Returns whether time `a` is before time `b`, allowing for the counter
+/// wrapping.
+fn before(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+/// A fixed-capacity queue of data PDUs.
+struct PduQueue {
+    pdus: [[u8; DATA_PDU_LEN]; QUEUE_LEN],
+    head: usize,
+    len: usize,
+}
+
+impl PduQueue {
+    const fn new() -> Self {
+        PduQueue {
+            pdus: [[0; DATA_PDU_LEN]; QUEUE_LEN],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == QUEUE_LEN
+    }
+
+    fn front(&self) -> Option<&[u8; DATA_PDU_LEN]> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(&self.pdus[self.head])
+        }
+    }
+
+    fn pop(&mut self) {
+        if self.len > 0 {
+            self.head = (self.head + 1) % QUEUE_LEN;
+            self.len -= 1;
+        }
+    }
+
+    /// Adds a PDU at the back of the queue, returning it to be filled in.
+    fn push(&mut self) -> Option<&mut [u8; DATA_PDU_LEN]> {
+        if self.is_full() {
+            return None;
+        }
+        let index = (self.head + self.len) % QUEUE_LEN;
+        self.len += 1;
+        Some(&mut self.pdus[index])
+    }
+}
+
+/// The state of a connection, in the slave role.
+///
+/// Returned by [`BlePeripheral::advertise()`] when a central connects.
+/// All times are in µs, on the `BlePeripheral`'s timer.
+pub struct Connection {
+    peer: DeviceAddress,
+    access_address: u32,
+    crc_init: u32,
+    interval: u32,
+    timeout: u32,
+    channel_map: ChannelMap,
+    selector: ChannelSelector,
+    /// The data channel of the next connection event.
+    channel: u8,
+    master_sca_ppm: u32,
+    /// The anchor point of the next connection event, or the start of its
+    /// transmit window.
+    anchor: u32,
+    /// The size of the transmit window of the next connection event, or 0.
+    window: u32,
+    /// The last anchor point actually found.
+    last_anchor: u32,
+    /// The time of the last packet received with a correct CRC.
+    last_rx: u32,
+    established: bool,
+    counter: u16,
+    sn: bool,
+    nesn: bool,
+    /// Whether the PDU at the front of `tx` has been sent but not
+    /// acknowledged.
+    in_flight: bool,
+    tx: PduQueue,
+    rx: PduQueue,
+    update: Option<ConnectionUpdate>,
+    channel_map_update: Option<(ChannelMap, u16)>,
+}
+
+impl Connection {
+    /// Returns the state of the connection created by a `CONNECT_REQ`
+    /// which ended at `end`.
+    fn new(request: ConnectRequest, end: u32) -> Self {
+        let mut selector = ChannelSelector::new(request.hop);
+        let channel = selector.next_channel(&request.channel_map);
+        Connection {
+            peer: request.initiator,
+            access_address: request.access_address,
+            crc_init: request.crc_init,
+            interval: u32::from(request.interval) * 1250,
+            timeout: u32::from(request.timeout) * 10_000,
+            channel_map: request.channel_map,
+            selector,
+            channel,
+            master_sca_ppm: request.master_sca_ppm.into(),
+            anchor: end
+                .wrapping_add(TRANSMIT_WINDOW_DELAY + u32::from(request.window_offset) * 1250),
+            window: u32::from(request.window_size) * 1250,
+            last_anchor: end,
+            last_rx: end,
+            established: false,
+            counter: 0,
+            sn: false,
+            nesn: false,
+            in_flight: false,
+            tx: PduQueue::new(),
+            rx: PduQueue::new(),
+            update: None,
+            channel_map_update: None,
+        }
+    }
+
+    /// Returns the central's address.
+    pub fn peer(&self) -> DeviceAddress {
+        self.peer
+    }
+
+    /// Queues a notification or indication of the current value of the
+    /// characteristic value at `handle`, to be sent in the next connection
+    /// event.
+    pub fn notify<P: AttributeProvider>(
+        &mut self,
+        server: &mut AttServer<P>,
+        handle: u16,
+    ) -> Result<(), NotifyError> {
+        if self.tx.is_full() {
+            return Err(NotifyError::Busy);
+        }
+        let mut pdu = [0; MTU];
+        let len = server.notification(handle, &mut pdu)?;
+        self.send_l2cap(ATT_CHANNEL, &pdu[..len]);
+        Ok(())
+    }
+
+    /// Returns the extra time to listen either side of the anchor point, to
+    /// allow for both sides' clock drift since the last anchor point.
+    fn widening(&self) -> u32 {
+        let elapsed = u64::from(self.anchor.wrapping_sub(self.last_anchor));
+        let ppm = u64::from(self.master_sca_ppm + LOCAL_SCA_PPM);
+        (elapsed * ppm / 1_000_000) as u32 + 16
+    }
+
+    /// Returns the time to start the receiver for the next connection event.
+    fn listen_start(&self) -> u32 {
+        self.anchor
+            .wrapping_sub(self.widening())
+            .wrapping_sub(RX_RAMP_UP)
+    }
+
+    /// Handles a packet with a correct CRC, returning its `MD` bit.
+    fn receive(&mut self, packet: &[u8], time: u32) -> bool {
+        let header = DataHeader::parse([packet[0], packet[1]]);
+        self.last_rx = time;
+        self.established = true;
+        if header.nesn != self.sn {
+            // The last PDU sent was acknowledged, so move on to the next
+            self.sn = !self.sn;
+            if self.in_flight {
+                self.tx.pop();
+            }
+            self.in_flight = self.tx.front().is_some();
+        }
+        if header.sn == self.nesn {
+            let len = usize::from(header.len);
+            if len == 0 || len > MAX_DATA_PAYLOAD {
+                self.nesn = !self.nesn;
+            } else if let Some(pdu) = self.rx.push() {
+                pdu[..2 + len].copy_from_slice(&packet[..2 + len]);
+                self.nesn = !self.nesn;
+            }
+            // Otherwise the queue is full, so the PDU isn't acknowledged
+            // and the central sends it again
+        }
+        header.md
+    }
+
+    /// Writes the next packet to send to `buf`, returning its `MD` bit.
+    fn prepare_tx(&self, buf: &mut [u8]) -> bool {
+        let pdu = if self.in_flight {
+            self.tx.front()
+        } else {
+            None
+        };
+        let (llid, len) = match pdu {
+            Some(pdu) => {
+                let len = usize::from(pdu[1]);
+                buf[..2 + len].copy_from_slice(&pdu[..2 + len]);
+                (DataHeader::parse([pdu[0], pdu[1]]).llid, pdu[1])
+            }
+            None => (Some(Llid::Continuation), 0),
+        };
+        let md = self.tx.len > usize::from(self.in_flight);
+        let header = DataHeader {
+            llid,
+            nesn: self.nesn,
+            sn: self.sn,
+            md,
+            len,
+        };
+        buf[..2].copy_from_slice(&header.encode());
+        md
+    }
+
+    /// Handles the PDUs received in a connection event, queuing any
+    /// responses.
+    fn process<P: AttributeProvider>(
+        &mut self,
+        server: &mut AttServer<P>,
+    ) -> Result<(), Disconnected> {
+        // Each PDU gets at most one response, so stop when there's no room
+        while !self.tx.is_full() {
+            let pdu = match self.rx.front() {
+                Some(pdu) => *pdu,
+                None => break,
+            };
+            self.rx.pop();
+            let header = DataHeader::parse([pdu[0], pdu[1]]);
+            let payload = &pdu[2..2 + usize::from(header.len)];
+            match header.llid {
+                Some(Llid::Control) => self.control(payload)?,
+                Some(Llid::Start) => self.l2cap(payload, server),
+                // L2CAP messages split over several PDUs aren't supported
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn control(&mut self, payload: &[u8]) -> Result<(), Disconnected> {
+        let (&opcode, data) = match payload.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        match opcode {
+            control::CONNECTION_UPDATE_IND => self.update = ConnectionUpdate::parse(data),
+            control::CHANNEL_MAP_IND if data.len() == 7 => {
+                let mut map = [0; 5];
+                map.copy_from_slice(&data[..5]);
+                let instant = u16::from_le_bytes([data[5], data[6]]);
+                self.channel_map_update = ChannelMap::new(map).map(|map| (map, instant));
+            }
+            control::TERMINATE_IND => {
+                return Err(Disconnected::Terminated(data.first().copied().unwrap_or(0)))
+            }
+            control::FEATURE_REQ => {
+                self.send_control(&[control::FEATURE_RSP, 0, 0, 0, 0, 0, 0, 0, 0])
+            }
+            control::VERSION_IND => {
+                // Company identifier 0xffff, for an unassigned implementation
+                self.send_control(&[control::VERSION_IND, BLUETOOTH_4_0, 0xff, 0xff, 0, 0])
+            }
+            control::ENC_REQ => {
+                self.send_control(&[control::REJECT_IND, UNSUPPORTED_REMOTE_FEATURE])
+            }
+            control::FEATURE_RSP | control::UNKNOWN_RSP | control::REJECT_IND => {}
+            _ => self.send_control(&[control::UNKNOWN_RSP, opcode]),
+        }
+        Ok(())
+    }
+
+    fn l2cap<P: AttributeProvider>(&mut self, payload: &[u8], server: &mut AttServer<P>) {
+        if payload.len() < 4 {
+            return;
+        }
+        let len = usize::from(u16::from_le_bytes([payload[0], payload[1]]));
+        let channel = u16::from_le_bytes([payload[2], payload[3]]);
+        let data = &payload[4..];
+        if len != data.len() {
+            return;
+        }
+        match channel {
+            ATT_CHANNEL => {
+                let mut response = [0; MTU];
+                let len = server.handle(data, &mut response);
+                if len > 0 {
+                    self.send_l2cap(ATT_CHANNEL, &response[..len]);
+                }
+            }
+            // No signaling requests are supported, so reject them as not
+            // understood
+            SIGNALING_CHANNEL
+                if data.len() >= 2
+                    && data[0] != COMMAND_REJECT
+                    && data[0] != CONNECTION_PARAMETER_UPDATE_RSP =>
+            {
+                self.send_l2cap(SIGNALING_CHANNEL, &[COMMAND_REJECT, data[1], 2, 0, 0, 0]);
+            }
+            SECURITY_CHANNEL if data.first() == Some(&PAIRING_REQUEST) => {
+                self.send_l2cap(SECURITY_CHANNEL, &[PAIRING_FAILED, PAIRING_NOT_SUPPORTED]);
+            }
+            _ => {}
+        }
+    }
+
+    fn send_control(&mut self, payload: &[u8]) {
+        if let Some(pdu) = self.tx.push() {
+            pdu[0] = Llid::Control as u8;
+            pdu[1] = payload.len() as u8;
+            pdu[2..2 + payload.len()].copy_from_slice(payload);
+        }
+    }
+
+    /// Queues a single-PDU L2CAP message.
+    fn send_l2cap(&mut self, channel: u16, data: &[u8]) {
+        if let Some(pdu) = self.tx.push() {
+            pdu[0] = Llid::Start as u8;
+            pdu[1] = 4 + data.len() as u8;
+            pdu[2..4].copy_from_slice(&(data.len() as u16).to_le_bytes());
+            pdu[4..6].copy_from_slice(&channel.to_le_bytes());
+            pdu[6..6 + data.len()].copy_from_slice(data);
+        }
+    }
+
+    /// Moves on to the next connection event after one has run.
+    fn end_event(&mut self, received: bool) {
+        if received {
+            // `anchor` has been set to the anchor point found
+            self.last_anchor = self.anchor;
+            self.window = 0;
+        }
+        self.advance();
+    }
+
+    /// Moves on to the next connection event, applying any updates which
+    /// take effect at it.
+    fn advance(&mut self) {
+        self.anchor = self.anchor.wrapping_add(self.interval);
+        self.counter = self.counter.wrapping_add(1);
+        if let Some((map, instant)) = self.channel_map_update {
+            if instant == self.counter {
+                self.channel_map = map;
+                self.channel_map_update = None;
+            }
+        }
+        if let Some(update) = self.update {
+            if update.instant == self.counter {
+                // The central sends its first packet with the new parameters
+                // in a transmit window after the old anchor point
+                self.anchor = self
+                    .anchor
+                    .wrapping_add(u32::from(update.window_offset) * 1250);
+                self.window = u32::from(update.window_size) * 1250;
+                self.interval = u32::from(update.interval) * 1250;
+                self.timeout = u32::from(update.timeout) * 10_000;
+                self.update = None;
+            }
+        }
+        self.channel = self.selector.next_channel(&self.channel_map);
+    }
+
+    fn check_timeout(&self, now: u32) -> Result<(), Disconnected> {
+        let timed_out = if self.established {
+            now.wrapping_sub(self.last_rx) > self.timeout
+        } else {
+            self.counter >= ESTABLISH_EVENTS
+        };
+        if timed_out {
+            Err(Disconnected::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+}
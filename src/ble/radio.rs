@@ -0,0 +1,262 @@
+//! The `RADIO` peripheral in BLE mode.
+
+use crate::pac::{FICR, RADIO};
+
+use super::link::{channel_frequency, ADVERTISING_ACCESS_ADDRESS, ADVERTISING_CRC_INIT, CRC_POLY};
+
+/// The radio's transmit power.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TxPower {
+    /// +4dBm
+    Pos4dBm,
+    /// 0dBm
+    ZerodBm,
+    /// -4dBm
+    Neg4dBm,
+    /// -8dBm
+    Neg8dBm,
+    /// -12dBm
+    Neg12dBm,
+    /// -16dBm
+    Neg16dBm,
+    /// -20dBm
+    Neg20dBm,
+    /// -30dBm
+    Neg30dBm,
+}
+
+impl TxPower {
+    /// Returns the power in dBm.
+    pub fn dbm(self) -> i8 {
+        match self {
+            TxPower::Pos4dBm => 4,
+            TxPower::ZerodBm => 0,
+            TxPower::Neg4dBm => -4,
+            TxPower::Neg8dBm => -8,
+            TxPower::Neg12dBm => -12,
+            TxPower::Neg16dBm => -16,
+            TxPower::Neg20dBm => -20,
+            TxPower::Neg30dBm => -30,
+        }
+    }
+}
+
+// SHORTS register bits
+pub(crate) const READY_START: u32 = 1 << 0;
+pub(crate) const END_DISABLE: u32 = 1 << 1;
+pub(crate) const DISABLED_TXEN: u32 = 1 << 2;
+pub(crate) const DISABLED_RXEN: u32 = 1 << 3;
+pub(crate) const ADDRESS_RSSISTART: u32 = 1 << 4;
+
+/// The `RADIO` peripheral, configured for BLE 1Mbit.
+///
+/// The radio reads and writes packets by DMA, in the layout described in
+/// [`link`](super::link): a 2-byte header followed by the payload. Packet
+/// buffers must be in RAM.
+///
+/// The HFCLK crystal oscillator must be running (see
+/// [`start_hfclk()`](crate::clock::start_hfclk)), as the internal
+/// oscillator isn't accurate enough for BLE.
+pub struct BleRadio {
+    radio: RADIO,
+}
+
+impl BleRadio {
+    /// Configures the radio for BLE, on the advertising channel access
+    /// address.
+    ///
+    /// Takes ownership of the `RADIO` peripheral.
+    #[cfg_attr(feature = "v2", allow(unused_variables))]
+    pub fn new(radio: RADIO, ficr: &FICR) -> Self {
+        radio.power.write(|w| w.power().enabled());
+        // Trim values for BLE mode on some nRF51 revisions
+        #[cfg(feature = "v1")]
+        if ficr.overrideen.read().ble_1mbit().is_override_() {
+            unsafe {
+                radio
+                    .override0
+                    .write(|w| w.bits(ficr.ble_1mbit[0].read().bits()));
+                radio
+                    .override1
+                    .write(|w| w.bits(ficr.ble_1mbit[1].read().bits()));
+                radio
+                    .override2
+                    .write(|w| w.bits(ficr.ble_1mbit[2].read().bits()));
+                radio
+                    .override3
+                    .write(|w| w.bits(ficr.ble_1mbit[3].read().bits()));
+                radio
+                    .override4
+                    .write(|w| w.bits(ficr.ble_1mbit[4].read().bits()).enable().enabled());
+            }
+        }
+        radio.mode.write(|w| w.mode().ble_1mbit());
+        radio.txpower.write(|w| w.txpower()._0d_bm());
+        unsafe {
+            // 1-byte S0, 8-bit LENGTH and no S1: the 2-byte PDU header
+            radio
+                .pcnf0
+                .write(|w| w.lflen().bits(8).s0len().set_bit().s1len().bits(0));
+            radio.pcnf1.write(|w| {
+                w.maxlen()
+                    .bits(super::link::MAX_ADVERTISING_PAYLOAD as u8)
+                    .statlen()
+                    .bits(0)
+                    .balen()
+                    .bits(3)
+                    .endian()
+                    .little()
+                    .whiteen()
+                    .enabled()
+            });
+            radio.crccnf.write(|w| w.len().three().skipaddr().skip());
+            radio.crcpoly.write(|w| w.bits(CRC_POLY));
+            radio.txaddress.write(|w| w.bits(0));
+            radio.rxaddresses.write(|w| w.bits(1));
+            radio.tifs.write(|w| w.bits(150));
+        }
+        let mut radio = BleRadio { radio };
+        radio.set_access_address(ADVERTISING_ACCESS_ADDRESS, ADVERTISING_CRC_INIT);
+        radio
+    }
+
+    /// Disables the radio and gives the `RADIO` peripheral back.
+    pub fn free(mut self) -> RADIO {
+        self.disable();
+        self.radio.power.write(|w| w.power().disabled());
+        self.radio
+    }
+
+    /// Sets the transmit power.
+    pub fn set_tx_power(&mut self, power: TxPower) {
+        self.radio.txpower.write(|w| {
+            let w = w.txpower();
+            match power {
+                TxPower::Pos4dBm => w.pos4d_bm(),
+                TxPower::ZerodBm => w._0d_bm(),
+                TxPower::Neg4dBm => w.neg4d_bm(),
+                TxPower::Neg8dBm => w.neg8d_bm(),
+                TxPower::Neg12dBm => w.neg12d_bm(),
+                TxPower::Neg16dBm => w.neg16d_bm(),
+                TxPower::Neg20dBm => w.neg20d_bm(),
+                TxPower::Neg30dBm => w.neg30d_bm(),
+            }
+        });
+    }
+
+    /// Sets the access address and CRC initialisation value.
+    ///
+    /// The radio must be disabled.
+    pub fn set_access_address(&mut self, access_address: u32, crc_init: u32) {
+        unsafe {
+            self.radio.base0.write(|w| w.bits(access_address << 8));
+            self.radio.prefix0.write(|w| w.bits(access_address >> 24));
+            self.radio.crcinit.write(|w| w.bits(crc_init & 0xff_ffff));
+        }
+    }
+
+    /// Sets the channel (0 to 39), which also sets the whitening.
+    ///
+    /// The radio must be disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel is out of range.
+    pub fn set_channel(&mut self, channel: u8) {
+        let frequency = channel_frequency(channel).expect("invalid channel");
+        unsafe {
+            self.radio.frequency.write(|w| w.bits(frequency.into()));
+            self.radio.datawhiteiv.write(|w| w.bits(channel.into()));
+        }
+    }
+
+    /// Transmits a packet, blocking until it has been sent.
+    ///
+    /// The radio must be disabled.
+    pub fn transmit(&mut self, packet: &[u8]) {
+        self.set_packet_ptr(packet);
+        self.set_shorts(READY_START | END_DISABLE);
+        self.clear_events();
+        self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        self.wait_disabled();
+    }
+
+    /// Returns whether the CRC of the last packet received was correct.
+    pub fn crc_ok(&self) -> bool {
+        self.radio.crcstatus.read().crcstatus().is_crcok()
+    }
+
+    /// Returns the signal strength of the last packet received, in dBm.
+    ///
+    /// The radio only measures it for packets received by a
+    /// [`Scanner`](super::scanner::Scanner).
+    pub fn rssi(&self) -> i8 {
+        -(self.radio.rssisample.read().rssisample().bits() as i8)
+    }
+
+    /// Disables the radio, aborting any transfer, and waits until it is
+    /// disabled.
+    pub fn disable(&mut self) {
+        self.set_shorts(0);
+        if self.radio.state.read().bits() != 0 {
+            self.radio.events_disabled.reset();
+            self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+            self.wait_disabled();
+        }
+    }
+
+    pub(crate) fn set_packet_ptr(&mut self, packet: &[u8]) {
+        self.radio
+            .packetptr
+            .write(|w| unsafe { w.bits(packet.as_ptr() as u32) });
+    }
+
+    pub(crate) fn set_shorts(&mut self, shorts: u32) {
+        self.radio.shorts.write(|w| unsafe { w.bits(shorts) });
+    }
+
+    pub(crate) fn clear_events(&mut self) {
+        self.radio.events_ready.reset();
+        self.radio.events_address.reset();
+        self.radio.events_end.reset();
+        self.radio.events_disabled.reset();
+    }
+
+    pub(crate) fn start_tx(&mut self) {
+        self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+    }
+
+    pub(crate) fn start_rx(&mut self) {
+        self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Clears the `DISABLED` event, returning whether it had happened.
+    pub(crate) fn take_disabled(&mut self) -> bool {
+        let disabled = self.radio.events_disabled.read().bits() != 0;
+        if disabled {
+            self.radio.events_disabled.reset();
+        }
+        disabled
+    }
+
+    /// Waits for the `DISABLED` event, and clears it.
+    pub(crate) fn wait_disabled(&mut self) {
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+    }
+
+    /// Waits for the `END` event, and clears it.
+    pub(crate) fn wait_end(&mut self) {
+        while self.radio.events_end.read().bits() == 0 {}
+        self.radio.events_end.reset();
+    }
+
+    /// Clears the `ADDRESS` event, returning whether it had happened.
+    pub(crate) fn take_address(&mut self) -> bool {
+        let address = self.radio.events_address.read().bits() != 0;
+        if address {
+            self.radio.events_address.reset();
+        }
+        address
+    }
+}
@@ -0,0 +1,24 @@
+//! A pseudo-random number generator for link-layer timing.
+
+use crate::pac::FICR;
+
+/// A xorshift generator; good enough for the random advertising delay.
+pub(crate) struct Xorshift(u32);
+
+impl Xorshift {
+    /// Returns a generator seeded from the factory-programmed device
+    /// address, so that each device advertises at its own times.
+    pub(crate) fn from_ficr(ficr: &FICR) -> Self {
+        Xorshift(ficr.deviceaddr[0].read().bits() | 1)
+    }
+
+    /// Returns the next value of the generator.
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+}
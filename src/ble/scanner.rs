@@ -0,0 +1,166 @@
+//! Passive scanning of the advertising channels.
+//!
+//! A [`Scanner`] listens on advertising channels 37, 38 and 39 in turn,
+//! spending a scan window on each, and reports the advertising PDUs it
+//! receives with their signal strength. It never sends scan requests, so
+//! only the advertising data is seen, not scan responses.
+
+use core::ptr;
+
+use crate::{
+    clock::COUNTER_MASK,
+    hal::rtc::{Instance, Rtc},
+    pac::{FICR, RADIO},
+    time::{MillisDurationU32, RtcDuration},
+};
+
+use super::{
+    ad::AdStructures,
+    link::{Advertisement, ADVERTISING_CHANNELS, PACKET_BUFFER_LEN},
+    radio::{BleRadio, ADDRESS_RSSISTART, END_DISABLE, READY_START},
+};
+
+/// The buffer the radio receives into.
+///
+/// The radio keeps receiving between calls to [`Scanner::receive()`], so the
+/// buffer can't move with the `Scanner`. Only the owner of the `RADIO` uses
+/// it, so there is only ever one reference to it.
+static mut RX: [u8; PACKET_BUFFER_LEN] = [0; PACKET_BUFFER_LEN];
+
+/// An advertising PDU received by a [`Scanner`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScanReport<'a> {
+    /// The advertising channel the PDU was received on.
+    pub channel: u8,
+    /// The signal strength, in dBm.
+    pub rssi: i8,
+    /// The PDU.
+    pub advertisement: Advertisement<'a>,
+}
+
+impl<'a> ScanReport<'a> {
+    /// Returns an iterator over the AD structures in the advertising data.
+    pub fn structures(&self) -> AdStructures<'a> {
+        AdStructures::new(self.advertisement.data)
+    }
+}
+
+/// A passive BLE scanner, using the `RADIO` and an `RTC`.
+///
+/// The `RTC` only times the scan windows, so its interrupt isn't needed.
+///
+/// The HFCLK crystal oscillator and the LFCLK must be running (see
+/// [`start_hfclk()`](crate::clock::start_hfclk) and
+/// [`start_lfclk()`](crate::clock::start_lfclk)).
+pub struct Scanner<T: Instance> {
+    radio: BleRadio,
+    rtc: Rtc<T>,
+    /// The time spent listening on each channel.
+    window: RtcDuration,
+    /// The `RTC` counter when listening started on the current channel.
+    window_start: u32,
+    /// The index of the current channel in `ADVERTISING_CHANNELS`.
+    channel: usize,
+    rx: &'static mut [u8; PACKET_BUFFER_LEN],
+    report: [u8; PACKET_BUFFER_LEN],
+}
+
+impl<T: Instance> Scanner<T> {
+    /// Returns a new `Scanner`, and starts listening on channel 37.
+    ///
+    /// Takes ownership of the `RADIO` and `RTC` peripherals, and starts the
+    /// `RTC`.
+    pub fn new(radio: RADIO, rtc: T, ficr: &FICR) -> Self {
+        rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+        // A prescaler of 0 is always in range
+        let rtc = Rtc::new(rtc, 0).unwrap();
+        rtc.enable_counter();
+        let mut scanner = Scanner {
+            radio: BleRadio::new(radio, ficr),
+            rtc,
+            window: RtcDuration::millis(100),
+            window_start: 0,
+            channel: 0,
+            rx: unsafe { &mut *ptr::addr_of_mut!(RX) },
+            report: [0; PACKET_BUFFER_LEN],
+        };
+        scanner.listen();
+        scanner
+    }
+
+    /// Disables the radio, stops the `RTC` and gives the `RADIO` and `RTC`
+    /// peripherals back.
+    pub fn free(self) -> (RADIO, T) {
+        self.rtc.disable_counter();
+        (self.radio.free(), self.rtc.release())
+    }
+
+    /// Sets the time spent listening on each channel (100ms by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window isn't between 10ms and 10.24s.
+    pub fn set_scan_window(&mut self, window: MillisDurationU32) {
+        assert!((10..=10_240).contains(&window.ticks()));
+        self.window = RtcDuration::millis(u64::from(window.to_millis()));
+    }
+
+    /// Returns the advertising PDU received since the last call, if any,
+    /// and moves on to the next channel at the end of the scan window.
+    ///
+    /// This doesn't block, and should be called often: while a PDU is
+    /// waiting to be collected, the radio isn't listening. PDUs with a bad
+    /// CRC and other packets seen on the advertising channels, such as scan
+    /// requests, are dropped.
+    pub fn receive(&mut self) -> Option<ScanReport<'_>> {
+        if self.radio.take_disabled() {
+            let crc_ok = self.radio.crc_ok();
+            let rssi = self.radio.rssi();
+            let channel = ADVERTISING_CHANNELS[self.channel];
+            self.report = *self.rx;
+            self.next_window_if_due();
+            self.listen();
+            if !crc_ok {
+                return None;
+            }
+            return Advertisement::parse(&self.report).map(|advertisement| ScanReport {
+                channel,
+                rssi,
+                advertisement,
+            });
+        }
+        if self.next_window_if_due() {
+            self.radio.disable();
+            self.listen();
+        }
+        None
+    }
+
+    /// Moves on to the next channel if the scan window is over, returning
+    /// whether it did.
+    fn next_window_if_due(&mut self) -> bool {
+        let now = self.rtc.get_counter();
+        let listened = RtcDuration::from_ticks(u64::from(
+            now.wrapping_sub(self.window_start) & COUNTER_MASK,
+        ));
+        if listened < self.window {
+            return false;
+        }
+        self.channel = (self.channel + 1) % ADVERTISING_CHANNELS.len();
+        self.window_start = now;
+        true
+    }
+
+    /// Starts receiving a packet on the current channel.
+    ///
+    /// The radio must be disabled.
+    fn listen(&mut self) {
+        self.radio.set_channel(ADVERTISING_CHANNELS[self.channel]);
+        self.radio.set_packet_ptr(&self.rx[..]);
+        self.radio
+            .set_shorts(READY_START | ADDRESS_RSSISTART | END_DISABLE);
+        self.radio.clear_events();
+        self.radio.start_rx();
+    }
+}
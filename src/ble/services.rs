@@ -0,0 +1,375 @@
+//! The standard micro:bit BLE services.
+//!
+//! [`MicrobitServices`] is an [`AttributeProvider`] implementing the
+//! services of the [micro:bit Bluetooth profile][profile] which the board
+//! can support without extra drivers:
+//! - Generic Access (device name)
+//! - LED service (LED matrix state, LED text, scrolling delay)
+//! - Button service (button A and B state)
+//! - Accelerometer service (data, period)
+//! - UART service (the Nordic UART Service UUIDs, with the micro:bit's
+//!   assignment of TX and RX characteristics).
+//!
+//! The services only hold the characteristic values. The application
+//! updates them (for example with
+//! [`set_button_a()`](MicrobitServices::set_button_a)) and reacts to values
+//! written by the client, which are reported by
+//! [`poll_event()`](MicrobitServices::poll_event).
+//!
+//! [profile]: https://lancaster-university.github.io/microbit-docs/resources/bluetooth/bluetooth_profile.html
+
+use super::gatt::{AttError, Attribute, AttributeProvider, Properties, Uuid};
+
+/// Returns a UUID based on the micro:bit base UUID,
+/// `E95Dxxxx-251D-470A-A062-FA1922DFA9A8`.
+const fn microbit_uuid(short: u16) -> Uuid {
+    Uuid::Uuid128(0xe95d_0000_251d_470a_a062_fa19_22df_a9a8 | (short as u128) << 96)
+}
+
+/// Returns a UUID based on the Nordic UART Service base UUID,
+/// `6E40xxxx-B5A3-F393-E0A9-E50E24DCCA9E`.
+const fn nus_uuid(short: u16) -> Uuid {
+    Uuid::Uuid128(0x6e40_0000_b5a3_f393_e0a9_e50e_24dc_ca9e | (short as u128) << 96)
+}
+
+/// The Generic Access service UUID.
+pub const GENERIC_ACCESS_SERVICE: Uuid = Uuid::Uuid16(0x1800);
+/// The Device Name characteristic UUID.
+pub const DEVICE_NAME: Uuid = Uuid::Uuid16(0x2a00);
+/// The Appearance characteristic UUID.
+pub const APPEARANCE: Uuid = Uuid::Uuid16(0x2a01);
+
+/// The LED service UUID.
+pub const LED_SERVICE: Uuid = microbit_uuid(0xd91d);
+/// The LED Matrix State characteristic UUID.
+pub const LED_MATRIX_STATE: Uuid = microbit_uuid(0x7b77);
+/// The LED Text characteristic UUID.
+pub const LED_TEXT: Uuid = microbit_uuid(0x93ee);
+/// The Scrolling Delay characteristic UUID.
+pub const SCROLLING_DELAY: Uuid = microbit_uuid(0x0d2d);
+
+/// The Button service UUID.
+pub const BUTTON_SERVICE: Uuid = microbit_uuid(0x9882);
+/// The Button A State characteristic UUID.
+pub const BUTTON_A_STATE: Uuid = microbit_uuid(0xda90);
+/// The Button B State characteristic UUID.
+pub const BUTTON_B_STATE: Uuid = microbit_uuid(0xda91);
+
+/// The Accelerometer service UUID.
+pub const ACCELEROMETER_SERVICE: Uuid = microbit_uuid(0x0753);
+/// The Accelerometer Data characteristic UUID.
+pub const ACCELEROMETER_DATA: Uuid = microbit_uuid(0xca4b);
+/// The Accelerometer Period characteristic UUID.
+pub const ACCELEROMETER_PERIOD: Uuid = microbit_uuid(0xfb24);
+
+/// The UART service UUID.
+pub const UART_SERVICE: Uuid = nus_uuid(0x0001);
+/// The UART TX characteristic UUID, indicated by the micro:bit.
+pub const UART_TX: Uuid = nus_uuid(0x0002);
+/// The UART RX characteristic UUID, written by the client.
+pub const UART_RX: Uuid = nus_uuid(0x0003);
+
+const READ: Properties = Properties::READ;
+const READ_WRITE: Properties = Properties::READ.union(Properties::WRITE);
+const READ_NOTIFY: Properties = Properties::READ.union(Properties::NOTIFY);
+const WRITE_ANY: Properties = Properties::WRITE.union(Properties::WRITE_WITHOUT_RESPONSE);
+
+static ATTRIBUTES: [Attribute; 31] = [
+    // 1
+    Attribute::PrimaryService(GENERIC_ACCESS_SERVICE),
+    Attribute::Characteristic(READ, DEVICE_NAME),
+    Attribute::Value(DEVICE_NAME),
+    Attribute::Characteristic(READ, APPEARANCE),
+    // Unknown appearance
+    Attribute::Constant(APPEARANCE, &[0, 0]),
+    // 6
+    Attribute::PrimaryService(LED_SERVICE),
+    Attribute::Characteristic(READ_WRITE, LED_MATRIX_STATE),
+    Attribute::Value(LED_MATRIX_STATE),
+    Attribute::Characteristic(Properties::WRITE, LED_TEXT),
+    Attribute::Value(LED_TEXT),
+    Attribute::Characteristic(READ_WRITE, SCROLLING_DELAY),
+    Attribute::Value(SCROLLING_DELAY),
+    // 13
+    Attribute::PrimaryService(BUTTON_SERVICE),
+    Attribute::Characteristic(READ_NOTIFY, BUTTON_A_STATE),
+    Attribute::Value(BUTTON_A_STATE),
+    Attribute::ClientConfig,
+    Attribute::Characteristic(READ_NOTIFY, BUTTON_B_STATE),
+    Attribute::Value(BUTTON_B_STATE),
+    Attribute::ClientConfig,
+    // 20
+    Attribute::PrimaryService(ACCELEROMETER_SERVICE),
+    Attribute::Characteristic(READ_NOTIFY, ACCELEROMETER_DATA),
+    Attribute::Value(ACCELEROMETER_DATA),
+    Attribute::ClientConfig,
+    Attribute::Characteristic(READ_WRITE, ACCELEROMETER_PERIOD),
+    Attribute::Value(ACCELEROMETER_PERIOD),
+    // 26
+    Attribute::PrimaryService(UART_SERVICE),
+    Attribute::Characteristic(Properties::INDICATE, UART_TX),
+    Attribute::Value(UART_TX),
+    Attribute::ClientConfig,
+    Attribute::Characteristic(WRITE_ANY, UART_RX),
+    Attribute::Value(UART_RX),
+];
+
+/// The longest value of the LED Text and UART characteristics.
+pub const MAX_TEXT_LEN: usize = 20;
+
+/// A characteristic value written by the client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServiceEvent {
+    /// The LED matrix state was written.
+    LedMatrix,
+    /// Text to scroll across the display was written.
+    LedText,
+    /// The scrolling delay was written.
+    ScrollingDelay,
+    /// The accelerometer period was written.
+    AccelerometerPeriod,
+    /// Data was received on the UART service.
+    UartRx,
+}
+
+/// Fixed-capacity text or data received from the client.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Text {
+    buf: [u8; MAX_TEXT_LEN],
+    len: u8,
+}
+
+impl Text {
+    fn set(&mut self, value: &[u8]) -> Result<(), AttError> {
+        if value.len() > MAX_TEXT_LEN {
+            return Err(AttError::InvalidAttributeValueLength);
+        }
+        self.buf[..value.len()].copy_from_slice(value);
+        self.len = value.len() as u8;
+        Ok(())
+    }
+
+    /// Returns the bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+}
+
+/// The micro:bit services' characteristic values.
+pub struct MicrobitServices {
+    name: &'static str,
+    led_matrix: [u8; 5],
+    led_text: Text,
+    scrolling_delay: u16,
+    buttons: [u8; 2],
+    accelerometer: [i16; 3],
+    accelerometer_period: u16,
+    uart_tx: Text,
+    uart_rx: Text,
+    /// One bit per [`ServiceEvent`] which is pending.
+    events: u8,
+}
+
+impl MicrobitServices {
+    /// The handle of the Device Name value.
+    pub const DEVICE_NAME: u16 = 3;
+    /// The handle of the LED Matrix State value.
+    pub const LED_MATRIX_STATE: u16 = 8;
+    /// The handle of the LED Text value.
+    pub const LED_TEXT: u16 = 10;
+    /// The handle of the Scrolling Delay value.
+    pub const SCROLLING_DELAY: u16 = 12;
+    /// The handle of the Button A State value.
+    pub const BUTTON_A_STATE: u16 = 15;
+    /// The handle of the Button B State value.
+    pub const BUTTON_B_STATE: u16 = 18;
+    /// The handle of the Accelerometer Data value.
+    pub const ACCELEROMETER_DATA: u16 = 22;
+    /// The handle of the Accelerometer Period value.
+    pub const ACCELEROMETER_PERIOD: u16 = 25;
+    /// The handle of the UART TX value.
+    pub const UART_TX: u16 = 28;
+    /// The handle of the UART RX value.
+    pub const UART_RX: u16 = 31;
+
+    /// Returns the services, with the device name reported to clients.
+    pub fn new(name: &'static str) -> Self {
+        MicrobitServices {
+            name,
+            led_matrix: [0; 5],
+            led_text: Text::default(),
+            scrolling_delay: 120,
+            buttons: [0; 2],
+            accelerometer: [0; 3],
+            accelerometer_period: 20,
+            uart_tx: Text::default(),
+            uart_rx: Text::default(),
+            events: 0,
+        }
+    }
+
+    /// Returns a value written by the client since the last call, if any.
+    pub fn poll_event(&mut self) -> Option<ServiceEvent> {
+        use ServiceEvent::*;
+        let event = [
+            LedMatrix,
+            LedText,
+            ScrollingDelay,
+            AccelerometerPeriod,
+            UartRx,
+        ]
+        .iter()
+        .copied()
+        .find(|&event| self.events & event_bit(event) != 0)?;
+        self.events &= !event_bit(event);
+        Some(event)
+    }
+
+    /// Returns the LED matrix state, as a brightness (0 or 9) for each LED
+    /// by row.
+    pub fn led_matrix(&self) -> [[u8; 5]; 5] {
+        let mut matrix = [[0; 5]; 5];
+        for (row, bits) in matrix.iter_mut().zip(self.led_matrix.iter()) {
+            for (x, led) in row.iter_mut().enumerate() {
+                if bits & (0x10 >> x) != 0 {
+                    *led = 9;
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Sets the LED matrix state reported to the client, from a brightness
+    /// for each LED by row. Any non-zero brightness counts as lit.
+    pub fn set_led_matrix(&mut self, matrix: &[[u8; 5]; 5]) {
+        for (bits, row) in self.led_matrix.iter_mut().zip(matrix.iter()) {
+            *bits = row
+                .iter()
+                .enumerate()
+                .filter(|(_, &led)| led != 0)
+                .fold(0, |bits, (x, _)| bits | 0x10 >> x);
+        }
+    }
+
+    /// Returns the last text written to the LED Text characteristic.
+    pub fn led_text(&self) -> &[u8] {
+        self.led_text.as_bytes()
+    }
+
+    /// Returns the scrolling delay, in milliseconds.
+    pub fn scrolling_delay(&self) -> u16 {
+        self.scrolling_delay
+    }
+
+    /// Sets the state of button A: 0 for released, 1 for pressed and 2 for
+    /// a long press.
+    pub fn set_button_a(&mut self, state: u8) {
+        self.buttons[0] = state;
+    }
+
+    /// Sets the state of button B: 0 for released, 1 for pressed and 2 for
+    /// a long press.
+    pub fn set_button_b(&mut self, state: u8) {
+        self.buttons[1] = state;
+    }
+
+    /// Sets the accelerometer data, in milli-g.
+    pub fn set_accelerometer(&mut self, x: i16, y: i16, z: i16) {
+        self.accelerometer = [x, y, z];
+    }
+
+    /// Returns the accelerometer period requested by the client, in
+    /// milliseconds.
+    pub fn accelerometer_period(&self) -> u16 {
+        self.accelerometer_period
+    }
+
+    /// Sets the data to send with the next UART TX indication.
+    ///
+    /// Returns an error if `data` is longer than [`MAX_TEXT_LEN`].
+    pub fn set_uart_tx(&mut self, data: &[u8]) -> Result<(), AttError> {
+        self.uart_tx.set(data)
+    }
+
+    /// Returns the last data received on the UART service.
+    pub fn uart_rx(&self) -> &[u8] {
+        self.uart_rx.as_bytes()
+    }
+}
+
+fn event_bit(event: ServiceEvent) -> u8 {
+    1 << event as u8
+}
+
+impl AttributeProvider for MicrobitServices {
+    fn attributes(&self) -> &'static [Attribute] {
+        &ATTRIBUTES
+    }
+
+    fn read(&mut self, handle: u16, buf: &mut [u8]) -> Result<usize, AttError> {
+        let mut value = [0; MAX_TEXT_LEN];
+        let value: &[u8] = match handle {
+            Self::DEVICE_NAME => self.name.as_bytes(),
+            Self::LED_MATRIX_STATE => &self.led_matrix,
+            Self::SCROLLING_DELAY => {
+                value[..2].copy_from_slice(&self.scrolling_delay.to_le_bytes());
+                &value[..2]
+            }
+            Self::BUTTON_A_STATE => &self.buttons[..1],
+            Self::BUTTON_B_STATE => &self.buttons[1..],
+            Self::ACCELEROMETER_DATA => {
+                for (chunk, axis) in value.chunks_mut(2).zip(self.accelerometer.iter()) {
+                    chunk.copy_from_slice(&axis.to_le_bytes());
+                }
+                &value[..6]
+            }
+            Self::ACCELEROMETER_PERIOD => {
+                value[..2].copy_from_slice(&self.accelerometer_period.to_le_bytes());
+                &value[..2]
+            }
+            Self::UART_TX => self.uart_tx.as_bytes(),
+            _ => return Err(AttError::ReadNotPermitted),
+        };
+        let len = value.len().min(buf.len());
+        buf[..len].copy_from_slice(&value[..len]);
+        Ok(len)
+    }
+
+    fn write(&mut self, handle: u16, value: &[u8]) -> Result<(), AttError> {
+        let event = match handle {
+            Self::LED_MATRIX_STATE => {
+                if value.len() != 5 {
+                    return Err(AttError::InvalidAttributeValueLength);
+                }
+                self.led_matrix.copy_from_slice(value);
+                ServiceEvent::LedMatrix
+            }
+            Self::LED_TEXT => {
+                self.led_text.set(value)?;
+                ServiceEvent::LedText
+            }
+            Self::SCROLLING_DELAY => {
+                self.scrolling_delay = u16_value(value)?;
+                ServiceEvent::ScrollingDelay
+            }
+            Self::ACCELEROMETER_PERIOD => {
+                self.accelerometer_period = u16_value(value)?;
+                ServiceEvent::AccelerometerPeriod
+            }
+            Self::UART_RX => {
+                self.uart_rx.set(value)?;
+                ServiceEvent::UartRx
+            }
+            _ => return Err(AttError::WriteNotPermitted),
+        };
+        self.events |= event_bit(event);
+        Ok(())
+    }
+}
+
+fn u16_value(value: &[u8]) -> Result<u16, AttError> {
+    if value.len() != 2 {
+        return Err(AttError::InvalidAttributeValueLength);
+    }
+    Ok(u16::from_le_bytes([value[0], value[1]]))
+}
@@ -0,0 +1,56 @@
+//! All of the micro:bit's peripherals, set up for its board layout.
+//!
+//! [`Board`] splits the PAC [`Peripherals`](crate::pac::Peripherals) into
+//! fields which can be moved out individually, with the GPIO pins already
+//! configured for the LED display, buttons and serial port. This makes it
+//! easy to hand each part to its own RTIC resource:
+//!
+//! ```ignore
+//! #[init]
+//! fn init(_: init::Context) -> init::LateResources {
+//!     let board = Board::take().unwrap();
+//!     let channels = Channels::new(board.GPIOTE);
+//!     init::LateResources {
+//!         display: DisplayDriver::new(board.TIMER1, board.display_pins),
+//!         button_a: Button::new(board.buttons.button_a.degrade(), channels.channel0),
+//!         serial: board.uart,
+//!     }
+//! }
+//! ```
+//!
+//! The GPIO pins and some peripherals differ between the micro:bit v1 and
+//! v2: the `Board` for the board selected by the `v1` or `v2` feature is
+//! exported here.
+
+#![allow(non_snake_case)]
+
+use crate::{
+    gpio::{BTN_A, BTN_B},
+    pac,
+};
+
+#[cfg(feature = "v1")]
+mod v1;
+#[cfg(feature = "v1")]
+pub use v1::{Board, Pins};
+
+#[cfg(feature = "v2")]
+mod v2;
+#[cfg(feature = "v2")]
+pub use v2::{Board, Pins};
+
+/// The micro:bit's buttons.
+pub struct Buttons {
+    /// Button A.
+    pub button_a: BTN_A,
+    /// Button B.
+    pub button_b: BTN_B,
+}
+
+impl Board {
+    /// Takes the peripherals and returns a `Board`, or `None` if the
+    /// peripherals have already been taken.
+    pub fn take() -> Option<Self> {
+        pac::Peripherals::take().map(Board::new)
+    }
+}
@@ -0,0 +1,202 @@
+use super::Buttons;
+use crate::{
+    gpio::{DisplayPins, PAD1, PAD2, PAD3, SCL, SDA},
+    hal::{
+        gpio::{p0, Disconnected, Level},
+        uart::{self, Baudrate, Uart},
+    },
+    pac,
+};
+
+/// The edge connector, sensor interrupt and I2C pins.
+pub struct Pins {
+    /// Big pad 1.
+    pub pad1: PAD1<Disconnected>,
+    /// Big pad 2.
+    pub pad2: PAD2<Disconnected>,
+    /// Big pad 3.
+    pub pad3: PAD3<Disconnected>,
+    /// Edge connector pin 8.
+    pub p0_18: p0::P0_18<Disconnected>,
+    /// Edge connector pin 12.
+    pub p0_20: p0::P0_20<Disconnected>,
+    /// Edge connector pin 15, also SPI MOSI.
+    pub p0_21: p0::P0_21<Disconnected>,
+    /// Edge connector pin 14, also SPI MISO.
+    pub p0_22: p0::P0_22<Disconnected>,
+    /// Edge connector pin 13, also SPI SCK.
+    pub p0_23: p0::P0_23<Disconnected>,
+    /// Edge connector pin 16.
+    pub p0_16: p0::P0_16<Disconnected>,
+    /// Accelerometer interrupt 1.
+    pub p0_28: p0::P0_28<Disconnected>,
+    /// Accelerometer interrupt 2.
+    pub p0_29: p0::P0_29<Disconnected>,
+    /// Magnetometer interrupt.
+    pub p0_27: p0::P0_27<Disconnected>,
+    /// I2C clock, shared by the accelerometer and magnetometer.
+    pub scl: SCL,
+    /// I2C data, shared by the accelerometer and magnetometer.
+    pub sda: SDA,
+}
+
+/// The micro:bit's peripherals.
+///
+/// The GPIO port and `UART0` are used to provide the display pins, buttons,
+/// other pins and serial port; the remaining peripherals are passed through
+/// unchanged.
+pub struct Board {
+    /// The pins connected to the LED display.
+    pub display_pins: DisplayPins,
+    /// The buttons.
+    pub buttons: Buttons,
+    /// The remaining GPIO pins.
+    pub pins: Pins,
+    /// The serial port connected to the interface chip, at 115200 baud.
+    pub uart: Uart<pac::UART0>,
+
+    /// nRF51 peripheral: POWER
+    pub POWER: pac::POWER,
+    /// nRF51 peripheral: CLOCK
+    pub CLOCK: pac::CLOCK,
+    /// nRF51 peripheral: RADIO
+    pub RADIO: pac::RADIO,
+    /// nRF51 peripheral: SPI0
+    pub SPI0: pac::SPI0,
+    /// nRF51 peripheral: TWI0
+    pub TWI0: pac::TWI0,
+    /// nRF51 peripheral: SPI1
+    pub SPI1: pac::SPI1,
+    /// nRF51 peripheral: TWI1
+    pub TWI1: pac::TWI1,
+    /// nRF51 peripheral: SPIS1
+    pub SPIS1: pac::SPIS1,
+    /// nRF51 peripheral: GPIOTE
+    pub GPIOTE: pac::GPIOTE,
+    /// nRF51 peripheral: ADC
+    pub ADC: pac::ADC,
+    /// nRF51 peripheral: TIMER0
+    pub TIMER0: pac::TIMER0,
+    /// nRF51 peripheral: TIMER1
+    pub TIMER1: pac::TIMER1,
+    /// nRF51 peripheral: TIMER2
+    pub TIMER2: pac::TIMER2,
+    /// nRF51 peripheral: RTC0
+    pub RTC0: pac::RTC0,
+    /// nRF51 peripheral: TEMP
+    pub TEMP: pac::TEMP,
+    /// nRF51 peripheral: RNG
+    pub RNG: pac::RNG,
+    /// nRF51 peripheral: ECB
+    pub ECB: pac::ECB,
+    /// nRF51 peripheral: AAR
+    pub AAR: pac::AAR,
+    /// nRF51 peripheral: CCM
+    pub CCM: pac::CCM,
+    /// nRF51 peripheral: WDT
+    pub WDT: pac::WDT,
+    /// nRF51 peripheral: RTC1
+    pub RTC1: pac::RTC1,
+    /// nRF51 peripheral: QDEC
+    pub QDEC: pac::QDEC,
+    /// nRF51 peripheral: LPCOMP
+    pub LPCOMP: pac::LPCOMP,
+    /// nRF51 peripheral: SWI
+    pub SWI: pac::SWI,
+    /// nRF51 peripheral: NVMC
+    pub NVMC: pac::NVMC,
+    /// nRF51 peripheral: PPI
+    pub PPI: pac::PPI,
+    /// nRF51 peripheral: FICR
+    pub FICR: pac::FICR,
+    /// nRF51 peripheral: UICR
+    pub UICR: pac::UICR,
+}
+
+impl Board {
+    /// Returns a `Board` made from the PAC peripherals.
+    pub fn new(p: pac::Peripherals) -> Self {
+        let gpio = p0::Parts::new(p.GPIO);
+
+        let display_pins = DisplayPins {
+            row1: gpio.p0_13.into_push_pull_output(Level::Low),
+            row2: gpio.p0_14.into_push_pull_output(Level::Low),
+            row3: gpio.p0_15.into_push_pull_output(Level::Low),
+            col1: gpio.p0_04.into_push_pull_output(Level::Low),
+            col2: gpio.p0_05.into_push_pull_output(Level::Low),
+            col3: gpio.p0_06.into_push_pull_output(Level::Low),
+            col4: gpio.p0_07.into_push_pull_output(Level::Low),
+            col5: gpio.p0_08.into_push_pull_output(Level::Low),
+            col6: gpio.p0_09.into_push_pull_output(Level::Low),
+            col7: gpio.p0_10.into_push_pull_output(Level::Low),
+            col8: gpio.p0_11.into_push_pull_output(Level::Low),
+            col9: gpio.p0_12.into_push_pull_output(Level::Low),
+        };
+
+        let uart_pins = uart::Pins {
+            rxd: gpio.p0_25.into_floating_input().degrade(),
+            txd: gpio.p0_24.into_push_pull_output(Level::Low).degrade(),
+            cts: None,
+            rts: None,
+        };
+
+        Board {
+            display_pins,
+            buttons: Buttons {
+                button_a: gpio.p0_17.into_floating_input(),
+                button_b: gpio.p0_26.into_floating_input(),
+            },
+            pins: Pins {
+                pad1: gpio.p0_03,
+                pad2: gpio.p0_02,
+                pad3: gpio.p0_01,
+                p0_18: gpio.p0_18,
+                p0_20: gpio.p0_20,
+                p0_21: gpio.p0_21,
+                p0_22: gpio.p0_22,
+                p0_23: gpio.p0_23,
+                p0_16: gpio.p0_16,
+                p0_28: gpio.p0_28,
+                p0_29: gpio.p0_29,
+                p0_27: gpio.p0_27,
+                scl: gpio.p0_00.into_floating_input(),
+                sda: gpio.p0_30.into_floating_input(),
+            },
+            uart: Uart::new(
+                p.UART0,
+                uart_pins,
+                uart::Parity::EXCLUDED,
+                Baudrate::BAUD115200,
+            ),
+
+            POWER: p.POWER,
+            CLOCK: p.CLOCK,
+            RADIO: p.RADIO,
+            SPI0: p.SPI0,
+            TWI0: p.TWI0,
+            SPI1: p.SPI1,
+            TWI1: p.TWI1,
+            SPIS1: p.SPIS1,
+            GPIOTE: p.GPIOTE,
+            ADC: p.ADC,
+            TIMER0: p.TIMER0,
+            TIMER1: p.TIMER1,
+            TIMER2: p.TIMER2,
+            RTC0: p.RTC0,
+            TEMP: p.TEMP,
+            RNG: p.RNG,
+            ECB: p.ECB,
+            AAR: p.AAR,
+            CCM: p.CCM,
+            WDT: p.WDT,
+            RTC1: p.RTC1,
+            QDEC: p.QDEC,
+            LPCOMP: p.LPCOMP,
+            SWI: p.SWI,
+            NVMC: p.NVMC,
+            PPI: p.PPI,
+            FICR: p.FICR,
+            UICR: p.UICR,
+        }
+    }
+}
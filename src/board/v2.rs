@@ -0,0 +1,328 @@
+use super::Buttons;
+use crate::{
+    gpio::{DisplayPins, EXT_SCL, EXT_SDA, PAD1, PAD2, PAD3, SCL, SDA},
+    hal::{
+        gpio::{p0, p1, Disconnected, Level},
+        uarte::{self, Baudrate, Uarte},
+    },
+    pac,
+};
+
+/// The edge connector, sensor, speaker, microphone and I2C pins.
+pub struct Pins {
+    /// Big pad 1.
+    pub pad1: PAD1<Disconnected>,
+    /// Big pad 2.
+    pub pad2: PAD2<Disconnected>,
+    /// Big pad 3.
+    pub pad3: PAD3<Disconnected>,
+    /// Edge connector pin 8.
+    pub p0_10: p0::P0_10<Disconnected>,
+    /// Edge connector pin 9.
+    pub p0_09: p0::P0_09<Disconnected>,
+    /// Edge connector pin 12.
+    pub p0_12: p0::P0_12<Disconnected>,
+    /// Edge connector pin 13, also SPI SCK.
+    pub p0_17: p0::P0_17<Disconnected>,
+    /// Edge connector pin 14, also SPI MISO.
+    pub p0_01: p0::P0_01<Disconnected>,
+    /// Edge connector pin 15, also SPI MOSI.
+    pub p0_13: p0::P0_13<Disconnected>,
+    /// Edge connector pin 16.
+    pub p1_02: p1::P1_02<Disconnected>,
+    /// Accelerometer and magnetometer interrupt.
+    pub p0_25: p0::P0_25<Disconnected>,
+    /// Speaker.
+    pub p0_00: p0::P0_00<Disconnected>,
+    /// Microphone input.
+    pub p0_05: p0::P0_05<Disconnected>,
+    /// Microphone power, which also lights the microphone LED.
+    pub p0_20: p0::P0_20<Disconnected>,
+    /// Touch-sensitive logo.
+    pub p1_04: p1::P1_04<Disconnected>,
+    /// Internal I2C clock, to the accelerometer and magnetometer.
+    pub scl: SCL,
+    /// Internal I2C data, to the accelerometer and magnetometer.
+    pub sda: SDA,
+    /// External I2C clock, edge connector pin 19.
+    pub ext_scl: EXT_SCL,
+    /// External I2C data, edge connector pin 20.
+    pub ext_sda: EXT_SDA,
+}
+
+/// The micro:bit's peripherals.
+///
+/// The GPIO ports and `UARTE0` are used to provide the display pins,
+/// buttons, other pins and serial port; the remaining peripherals are passed
+/// through unchanged. `UART0`, which shares its registers with `UARTE0`,
+/// isn't available.
+pub struct Board {
+    /// The pins connected to the LED display.
+    pub display_pins: DisplayPins,
+    /// The buttons.
+    pub buttons: Buttons,
+    /// The remaining GPIO pins.
+    pub pins: Pins,
+    /// The serial port connected to the interface chip, at 115200 baud.
+    pub uart: Uarte<pac::UARTE0>,
+
+    /// nRF52 peripheral: FICR
+    pub FICR: pac::FICR,
+    /// nRF52 peripheral: UICR
+    pub UICR: pac::UICR,
+    /// nRF52 peripheral: CLOCK
+    pub CLOCK: pac::CLOCK,
+    /// nRF52 peripheral: POWER
+    pub POWER: pac::POWER,
+    /// nRF52 peripheral: RADIO
+    pub RADIO: pac::RADIO,
+    /// nRF52 peripheral: SPI0
+    pub SPI0: pac::SPI0,
+    /// nRF52 peripheral: SPIM0
+    pub SPIM0: pac::SPIM0,
+    /// nRF52 peripheral: SPIS0
+    pub SPIS0: pac::SPIS0,
+    /// nRF52 peripheral: TWI0
+    pub TWI0: pac::TWI0,
+    /// nRF52 peripheral: TWIM0
+    pub TWIM0: pac::TWIM0,
+    /// nRF52 peripheral: TWIS0
+    pub TWIS0: pac::TWIS0,
+    /// nRF52 peripheral: SPI1
+    pub SPI1: pac::SPI1,
+    /// nRF52 peripheral: SPIM1
+    pub SPIM1: pac::SPIM1,
+    /// nRF52 peripheral: SPIS1
+    pub SPIS1: pac::SPIS1,
+    /// nRF52 peripheral: TWI1
+    pub TWI1: pac::TWI1,
+    /// nRF52 peripheral: TWIM1
+    pub TWIM1: pac::TWIM1,
+    /// nRF52 peripheral: TWIS1
+    pub TWIS1: pac::TWIS1,
+    /// nRF52 peripheral: NFCT
+    pub NFCT: pac::NFCT,
+    /// nRF52 peripheral: GPIOTE
+    pub GPIOTE: pac::GPIOTE,
+    /// nRF52 peripheral: SAADC
+    pub SAADC: pac::SAADC,
+    /// nRF52 peripheral: TIMER0
+    pub TIMER0: pac::TIMER0,
+    /// nRF52 peripheral: TIMER1
+    pub TIMER1: pac::TIMER1,
+    /// nRF52 peripheral: TIMER2
+    pub TIMER2: pac::TIMER2,
+    /// nRF52 peripheral: RTC0
+    pub RTC0: pac::RTC0,
+    /// nRF52 peripheral: TEMP
+    pub TEMP: pac::TEMP,
+    /// nRF52 peripheral: RNG
+    pub RNG: pac::RNG,
+    /// nRF52 peripheral: ECB
+    pub ECB: pac::ECB,
+    /// nRF52 peripheral: AAR
+    pub AAR: pac::AAR,
+    /// nRF52 peripheral: CCM
+    pub CCM: pac::CCM,
+    /// nRF52 peripheral: WDT
+    pub WDT: pac::WDT,
+    /// nRF52 peripheral: RTC1
+    pub RTC1: pac::RTC1,
+    /// nRF52 peripheral: QDEC
+    pub QDEC: pac::QDEC,
+    /// nRF52 peripheral: COMP
+    pub COMP: pac::COMP,
+    /// nRF52 peripheral: LPCOMP
+    pub LPCOMP: pac::LPCOMP,
+    /// nRF52 peripheral: EGU0
+    pub EGU0: pac::EGU0,
+    /// nRF52 peripheral: SWI0
+    pub SWI0: pac::SWI0,
+    /// nRF52 peripheral: EGU1
+    pub EGU1: pac::EGU1,
+    /// nRF52 peripheral: SWI1
+    pub SWI1: pac::SWI1,
+    /// nRF52 peripheral: EGU2
+    pub EGU2: pac::EGU2,
+    /// nRF52 peripheral: SWI2
+    pub SWI2: pac::SWI2,
+    /// nRF52 peripheral: EGU3
+    pub EGU3: pac::EGU3,
+    /// nRF52 peripheral: SWI3
+    pub SWI3: pac::SWI3,
+    /// nRF52 peripheral: EGU4
+    pub EGU4: pac::EGU4,
+    /// nRF52 peripheral: SWI4
+    pub SWI4: pac::SWI4,
+    /// nRF52 peripheral: EGU5
+    pub EGU5: pac::EGU5,
+    /// nRF52 peripheral: SWI5
+    pub SWI5: pac::SWI5,
+    /// nRF52 peripheral: TIMER3
+    pub TIMER3: pac::TIMER3,
+    /// nRF52 peripheral: TIMER4
+    pub TIMER4: pac::TIMER4,
+    /// nRF52 peripheral: PWM0
+    pub PWM0: pac::PWM0,
+    /// nRF52 peripheral: PDM
+    pub PDM: pac::PDM,
+    /// nRF52 peripheral: ACL
+    pub ACL: pac::ACL,
+    /// nRF52 peripheral: NVMC
+    pub NVMC: pac::NVMC,
+    /// nRF52 peripheral: PPI
+    pub PPI: pac::PPI,
+    /// nRF52 peripheral: MWU
+    pub MWU: pac::MWU,
+    /// nRF52 peripheral: PWM1
+    pub PWM1: pac::PWM1,
+    /// nRF52 peripheral: PWM2
+    pub PWM2: pac::PWM2,
+    /// nRF52 peripheral: SPI2
+    pub SPI2: pac::SPI2,
+    /// nRF52 peripheral: SPIM2
+    pub SPIM2: pac::SPIM2,
+    /// nRF52 peripheral: SPIS2
+    pub SPIS2: pac::SPIS2,
+    /// nRF52 peripheral: RTC2
+    pub RTC2: pac::RTC2,
+    /// nRF52 peripheral: I2S
+    pub I2S: pac::I2S,
+    /// nRF52 peripheral: USBD
+    pub USBD: pac::USBD,
+    /// nRF52 peripheral: UARTE1
+    pub UARTE1: pac::UARTE1,
+    /// nRF52 peripheral: PWM3
+    pub PWM3: pac::PWM3,
+    /// nRF52 peripheral: SPIM3
+    pub SPIM3: pac::SPIM3,
+}
+
+impl Board {
+    /// Returns a `Board` made from the PAC peripherals.
+    pub fn new(p: pac::Peripherals) -> Self {
+        let p0 = p0::Parts::new(p.P0);
+        let p1 = p1::Parts::new(p.P1);
+
+        let display_pins = DisplayPins {
+            row1: p0.p0_21.into_push_pull_output(Level::Low),
+            row2: p0.p0_22.into_push_pull_output(Level::Low),
+            row3: p0.p0_15.into_push_pull_output(Level::Low),
+            row4: p0.p0_24.into_push_pull_output(Level::Low),
+            row5: p0.p0_19.into_push_pull_output(Level::Low),
+            col1: p0.p0_28.into_push_pull_output(Level::Low),
+            col2: p0.p0_11.into_push_pull_output(Level::Low),
+            col3: p0.p0_31.into_push_pull_output(Level::Low),
+            col4: p1.p1_05.into_push_pull_output(Level::Low),
+            col5: p0.p0_30.into_push_pull_output(Level::Low),
+        };
+
+        let uart_pins = uarte::Pins {
+            rxd: p1.p1_08.into_floating_input().degrade(),
+            txd: p0.p0_06.into_push_pull_output(Level::Low).degrade(),
+            cts: None,
+            rts: None,
+        };
+
+        Board {
+            display_pins,
+            buttons: Buttons {
+                button_a: p0.p0_14.into_floating_input(),
+                button_b: p0.p0_23.into_floating_input(),
+            },
+            pins: Pins {
+                pad1: p0.p0_02,
+                pad2: p0.p0_03,
+                pad3: p0.p0_04,
+                p0_10: p0.p0_10,
+                p0_09: p0.p0_09,
+                p0_12: p0.p0_12,
+                p0_17: p0.p0_17,
+                p0_01: p0.p0_01,
+                p0_13: p0.p0_13,
+                p1_02: p1.p1_02,
+                p0_25: p0.p0_25,
+                p0_00: p0.p0_00,
+                p0_05: p0.p0_05,
+                p0_20: p0.p0_20,
+                p1_04: p1.p1_04,
+                scl: p0.p0_08.into_floating_input(),
+                sda: p0.p0_16.into_floating_input(),
+                ext_scl: p0.p0_26.into_floating_input(),
+                ext_sda: p1.p1_00.into_floating_input(),
+            },
+            uart: Uarte::new(
+                p.UARTE0,
+                uart_pins,
+                uarte::Parity::EXCLUDED,
+                Baudrate::BAUD115200,
+            ),
+
+            FICR: p.FICR,
+            UICR: p.UICR,
+            CLOCK: p.CLOCK,
+            POWER: p.POWER,
+            RADIO: p.RADIO,
+            SPI0: p.SPI0,
+            SPIM0: p.SPIM0,
+            SPIS0: p.SPIS0,
+            TWI0: p.TWI0,
+            TWIM0: p.TWIM0,
+            TWIS0: p.TWIS0,
+            SPI1: p.SPI1,
+            SPIM1: p.SPIM1,
+            SPIS1: p.SPIS1,
+            TWI1: p.TWI1,
+            TWIM1: p.TWIM1,
+            TWIS1: p.TWIS1,
+            NFCT: p.NFCT,
+            GPIOTE: p.GPIOTE,
+            SAADC: p.SAADC,
+            TIMER0: p.TIMER0,
+            TIMER1: p.TIMER1,
+            TIMER2: p.TIMER2,
+            RTC0: p.RTC0,
+            TEMP: p.TEMP,
+            RNG: p.RNG,
+            ECB: p.ECB,
+            AAR: p.AAR,
+            CCM: p.CCM,
+            WDT: p.WDT,
+            RTC1: p.RTC1,
+            QDEC: p.QDEC,
+            COMP: p.COMP,
+            LPCOMP: p.LPCOMP,
+            EGU0: p.EGU0,
+            SWI0: p.SWI0,
+            EGU1: p.EGU1,
+            SWI1: p.SWI1,
+            EGU2: p.EGU2,
+            SWI2: p.SWI2,
+            EGU3: p.EGU3,
+            SWI3: p.SWI3,
+            EGU4: p.EGU4,
+            SWI4: p.SWI4,
+            EGU5: p.EGU5,
+            SWI5: p.SWI5,
+            TIMER3: p.TIMER3,
+            TIMER4: p.TIMER4,
+            PWM0: p.PWM0,
+            PDM: p.PDM,
+            ACL: p.ACL,
+            NVMC: p.NVMC,
+            PPI: p.PPI,
+            MWU: p.MWU,
+            PWM1: p.PWM1,
+            PWM2: p.PWM2,
+            SPI2: p.SPI2,
+            SPIM2: p.SPIM2,
+            SPIS2: p.SPIS2,
+            RTC2: p.RTC2,
+            I2S: p.I2S,
+            USBD: p.USBD,
+            UARTE1: p.UARTE1,
+            PWM3: p.PWM3,
+            SPIM3: p.SPIM3,
+        }
+    }
+}
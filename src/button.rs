@@ -0,0 +1,264 @@
+//! Support for the micro:bit's buttons.
+//!
+//! # Scope
+//!
+//! This module provides a [`Button`] driver for the two user buttons, or any
+//! other active-low push button, with:
+//! - polled state ([`is_pressed()`](Button::is_pressed))
+//! - press and release [`ButtonEvent`]s
+//!   ([`poll_event()`](Button::poll_event))
+//! - async waiting for presses and releases, using a `GPIOTE` channel.
+//!
+//! # Async usage
+//!
+//! Each `Button` owns a `GPIOTE` [`Channel`] to detect edges, split from the
+//! peripheral by [`Channels`]. The `GPIOTE` interrupt must be unmasked in
+//! the NVIC, with [`handle_gpiote_interrupt()`] called from its handler.
+//!
+//! ```no_run
+//! use microbit::{
+//!     board::Board,
+//!     button::{Button, Channels},
+//!     executor,
+//! };
+//!
+//! let board = Board::take().unwrap();
+//! let channels = Channels::new(board.GPIOTE);
+//! let mut button_a = Button::new(board.buttons.button_a.degrade(), channels.channel0);
+//!
+//! executor::block_on(async {
+//!     button_a.wait_for_press().await;
+//! });
+//!
+//! // in the GPIOTE interrupt handler
+//! microbit::button::handle_gpiote_interrupt();
+//! ```
+
+use core::{
+    future::Future,
+    pin::Pin as FuturePin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    executor::WakerCell,
+    hal::{
+        gpio::{Floating, Input, Pin},
+        prelude::*,
+    },
+    pac::GPIOTE,
+};
+
+#[cfg(feature = "v2")]
+use crate::hal::gpio::Port;
+
+/// The number of `GPIOTE` channels on the nRF51, and the number used on the
+/// nRF52833.
+const CHANNELS: usize = 4;
+
+/// The `GPIOTE` channels, split from the peripheral for [`Button`]s.
+pub struct Channels {
+    /// `GPIOTE` channel 0
+    pub channel0: Channel,
+    /// `GPIOTE` channel 1
+    pub channel1: Channel,
+    /// `GPIOTE` channel 2
+    pub channel2: Channel,
+    /// `GPIOTE` channel 3
+    pub channel3: Channel,
+}
+
+impl Channels {
+    /// Splits the `GPIOTE` peripheral into its channels.
+    ///
+    /// Takes ownership of the `GPIOTE` peripheral, and disables all its
+    /// channels and interrupts.
+    pub fn new(gpiote: GPIOTE) -> Self {
+        gpiote.intenclr.write(|w| unsafe { w.bits(u32::MAX) });
+        for config in gpiote.config.iter() {
+            config.reset();
+        }
+        Channels {
+            channel0: Channel { index: 0 },
+            channel1: Channel { index: 1 },
+            channel2: Channel { index: 2 },
+            channel3: Channel { index: 3 },
+        }
+    }
+}
+
+/// A `GPIOTE` channel, owned by a [`Button`].
+pub struct Channel {
+    index: usize,
+}
+
+impl Channel {
+    /// Returns the channel's `GPIOTE` registers.
+    ///
+    /// The `CONFIG` and `EVENTS_IN` registers are this channel's own, and
+    /// `INTENSET` and `INTENCLR` only change the bits written.
+    fn gpiote(&self) -> &crate::pac::gpiote::RegisterBlock {
+        unsafe { &*GPIOTE::ptr() }
+    }
+}
+
+static WAKERS: [WakerCell; CHANNELS] = [
+    WakerCell::new(),
+    WakerCell::new(),
+    WakerCell::new(),
+    WakerCell::new(),
+];
+
+/// A change in a button's state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// The button was pressed.
+    Pressed,
+    /// The button was released.
+    Released,
+}
+
+/// An active-low push button.
+pub struct Button {
+    pin: Pin<Input<Floating>>,
+    channel: Channel,
+    pressed: bool,
+}
+
+impl Button {
+    /// Returns a new `Button` on `pin`, using the `GPIOTE` channel `channel`
+    /// for async waits.
+    pub fn new(pin: Pin<Input<Floating>>, channel: Channel) -> Self {
+        let mut button = Button {
+            pin,
+            channel,
+            pressed: false,
+        };
+        button.pressed = button.is_pressed();
+        button
+    }
+
+    /// Disables the `GPIOTE` channel and gives the pin and channel back.
+    pub fn free(self) -> (Pin<Input<Floating>>, Channel) {
+        let gpiote = self.channel.gpiote();
+        gpiote
+            .intenclr
+            .write(|w| unsafe { w.bits(1 << self.channel.index) });
+        gpiote.config[self.channel.index].reset();
+        (self.pin, self.channel)
+    }
+
+    pub(crate) fn pin(&self) -> &Pin<Input<Floating>> {
+        &self.pin
+    }
+
+    /// Returns whether the button is currently pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.pin.is_low().unwrap()
+    }
+
+    /// Returns an event if the button has changed state since the last call.
+    ///
+    /// Changes shorter than the interval between calls may be missed.
+    pub fn poll_event(&mut self) -> Option<ButtonEvent> {
+        let pressed = self.is_pressed();
+        if pressed == self.pressed {
+            return None;
+        }
+        self.pressed = pressed;
+        Some(if pressed {
+            ButtonEvent::Pressed
+        } else {
+            ButtonEvent::Released
+        })
+    }
+
+    /// Waits until the button is pressed.
+    ///
+    /// If the button is already held down, this waits for the next press.
+    pub async fn wait_for_press(&mut self) {
+        while self.wait_for_event().await != ButtonEvent::Pressed {}
+    }
+
+    /// Waits until the button is released.
+    ///
+    /// Returns immediately if the button isn't held down.
+    pub async fn wait_for_release(&mut self) {
+        if !self.is_pressed() {
+            self.pressed = false;
+            return;
+        }
+        while self.wait_for_event().await != ButtonEvent::Released {}
+    }
+
+    /// Waits for the button to change state.
+    pub async fn wait_for_event(&mut self) -> ButtonEvent {
+        loop {
+            self.listen();
+            if let Some(event) = self.poll_event() {
+                return event;
+            }
+            Edge {
+                channel: &self.channel,
+            }
+            .await;
+        }
+    }
+
+    /// Sets the `GPIOTE` channel to watch for both edges on the pin.
+    fn listen(&self) {
+        let gpiote = self.channel.gpiote();
+        gpiote.config[self.channel.index].write(|w| unsafe {
+            w.mode()
+                .event()
+                .psel()
+                .bits(self.pin.pin())
+                .polarity()
+                .toggle();
+            #[cfg(feature = "v2")]
+            w.port().bit(self.pin.port() == Port::Port1);
+            w
+        });
+        gpiote.events_in[self.channel.index].reset();
+    }
+}
+
+/// A future which completes on the next edge seen by a `GPIOTE` channel.
+struct Edge<'a> {
+    channel: &'a Channel,
+}
+
+impl Future for Edge<'_> {
+    type Output = ();
+
+    fn poll(self: FuturePin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let index = self.channel.index;
+        let gpiote = self.channel.gpiote();
+        let event = &gpiote.events_in[index];
+        if event.read().bits() != 0 {
+            event.reset();
+            return Poll::Ready(());
+        }
+        WAKERS[index].register(cx.waker());
+        gpiote.intenset.write(|w| unsafe { w.bits(1 << index) });
+        Poll::Pending
+    }
+}
+
+/// Wakes any [`Button`] waiting for an edge.
+///
+/// Call this in the interrupt handler for `GPIOTE`.
+///
+/// Disables the interrupt for the channels which have fired; the events are
+/// cleared when the waiting futures are polled.
+pub fn handle_gpiote_interrupt() {
+    // Only the `Button`s waiting on their own channels enable interrupts
+    let gpiote = unsafe { &*GPIOTE::ptr() };
+    let enabled = gpiote.intenset.read().bits();
+    for (channel, waker) in WAKERS.iter().enumerate() {
+        if enabled & 1 << channel != 0 && gpiote.events_in[channel].read().bits() != 0 {
+            gpiote.intenclr.write(|w| unsafe { w.bits(1 << channel) });
+            waker.wake();
+        }
+    }
+}
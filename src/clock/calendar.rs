@@ -0,0 +1,222 @@
+//! A simple date and time type for the wall clock.
+//!
+//! Times are counted in seconds from 2000-01-01 00:00:00, and only years
+//! from 2000 onwards can be represented. There is no concept of time zones
+//! or leap seconds.
+
+use core::{fmt, str::FromStr};
+
+/// Days from 0000-03-01 to 2000-01-01, in the proleptic Gregorian calendar.
+const EPOCH_DAYS: u32 = 730_425;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A day of the week.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Weekday {
+    /// Monday
+    Monday,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+    /// Sunday
+    Sunday,
+}
+
+/// A calendar date and time of day.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+/// Error returned when a [`DateTime`] can't be parsed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseDateTimeError;
+
+impl DateTime {
+    /// Constructs a `DateTime`, checking that it is a valid date and time.
+    ///
+    /// Returns `None` if any of the fields are out of range, or the year is
+    /// before 2000.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        if year < 2000
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        Some(DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Constructs a `DateTime` from the number of seconds since
+    /// 2000-01-01 00:00:00.
+    pub fn from_timestamp(timestamp: u64) -> Self {
+        let days = (timestamp / SECONDS_PER_DAY) as u32 + EPOCH_DAYS;
+        let secs = (timestamp % SECONDS_PER_DAY) as u32;
+
+        // Convert days since 0000-03-01 to a civil date (see
+        // http://howardhinnant.github.io/date_algorithms.html).
+        let era = days / 146_097;
+        let doe = days - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as u32;
+
+        DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+
+    /// Returns the number of seconds since 2000-01-01 00:00:00.
+    pub fn timestamp(&self) -> u64 {
+        let days = u64::from(self.days_since_epoch());
+        days * SECONDS_PER_DAY
+            + u64::from(self.hour) * 3600
+            + u64::from(self.minute) * 60
+            + u64::from(self.second)
+    }
+
+    /// The year (2000 onwards).
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month (1 to 12).
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month (1 to 31).
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour (0 to 23).
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute (0 to 59).
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second (0 to 59).
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// The day of the week.
+    pub fn weekday(&self) -> Weekday {
+        // 2000-01-01 was a Saturday.
+        match (self.days_since_epoch() + 5) % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+
+    fn days_since_epoch(&self) -> u32 {
+        let month = u32::from(self.month);
+        let year = u32::from(self.year) - (month <= 2) as u32;
+        let era = year / 400;
+        let yoe = year - era * 400;
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + u32::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - EPOCH_DAYS
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = ParseDateTimeError;
+
+    /// Parses a date and time in the form `YYYY-MM-DD HH:MM:SS`.
+    ///
+    /// A `T` is also accepted in place of the space, as in ISO 8601.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.as_bytes();
+        if s.len() != 19
+            || s[4] != b'-'
+            || s[7] != b'-'
+            || (s[10] != b' ' && s[10] != b'T')
+            || s[13] != b':'
+            || s[16] != b':'
+        {
+            return Err(ParseDateTimeError);
+        }
+        let number = |range: core::ops::Range<usize>| {
+            s[range].iter().try_fold(0u16, |acc, &c| match c {
+                b'0'..=b'9' => Ok(acc * 10 + u16::from(c - b'0')),
+                _ => Err(ParseDateTimeError),
+            })
+        };
+        DateTime::new(
+            number(0..4)?,
+            number(5..7)? as u8,
+            number(8..10)? as u8,
+            number(11..13)? as u8,
+            number(14..16)? as u8,
+            number(17..19)? as u8,
+        )
+        .ok_or(ParseDateTimeError)
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
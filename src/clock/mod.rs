@@ -0,0 +1,120 @@
+//! Clock control and a real-time clock/calendar.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - functions to start and stop the high-frequency (HFCLK) and
+//!   low-frequency (LFCLK) clocks
+//! - an [`RtcClock`], which keeps a 64-bit monotonic tick count on one of the
+//!   `RTC` peripherals and can track wall-clock time
+//! - a simple [`DateTime`] type for the wall-clock time.
+//!
+//! # Clock sources
+//!
+//! The `RTC` peripherals run from the LFCLK, which has to be started before
+//! any of them will count. The LFCLK can be driven from:
+//! - the internal 32.768kHz RC oscillator ([`LfClockSource::Rc`])
+//! - an external 32.768kHz crystal ([`LfClockSource::Crystal`])
+//! - a clock synthesised from the HFCLK ([`LfClockSource::Synthesized`]).
+//!
+//! The micro:bit v1 doesn't have a 32.768kHz crystal fitted, so
+//! `LfClockSource::Crystal` will never start on an unmodified board.
+//!
+//! Synthesising the LFCLK requires the 16MHz crystal, so
+//! [`start_lfclk()`] starts the HFCLK crystal first in that case.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::clock::{self, DateTime, LfClockSource, RtcClock};
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! clock::start_lfclk(&p.CLOCK, LfClockSource::Rc);
+//!
+//! let mut rtc = RtcClock::new(p.RTC0);
+//! rtc.set_datetime("2021-04-22 12:00:00".parse().unwrap());
+//!
+//! // in the RTC0 interrupt handler
+//! rtc.handle_interrupt();
+//! ```
+//!
+//! See a working example at `examples/rtc_clock_serial.rs`
+
+mod calendar;
+mod rtc;
+
+pub use calendar::{DateTime, ParseDateTimeError, Weekday};
+pub use rtc::{RtcClock, TICKS_PER_SECOND};
+pub(crate) use rtc::{COUNTER_MASK, MIN_COMPARE_TICKS};
+
+use crate::pac::CLOCK;
+
+/// Source for the low-frequency clock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LfClockSource {
+    /// The internal 32.768kHz RC oscillator.
+    Rc,
+    /// An external 32.768kHz crystal (not fitted on the micro:bit v1).
+    Crystal,
+    /// A 32.768kHz clock synthesised from the HFCLK.
+    Synthesized,
+}
+
+/// Starts the 16MHz crystal oscillator as the HFCLK source.
+///
+/// Blocks until the crystal is running.
+pub fn start_hfclk(clock: &CLOCK) {
+    if hfclk_running(clock) {
+        return;
+    }
+    clock.events_hfclkstarted.reset();
+    clock.tasks_hfclkstart.write(|w| unsafe { w.bits(1) });
+    while clock.events_hfclkstarted.read().bits() == 0 {}
+    clock.events_hfclkstarted.reset();
+}
+
+/// Stops the crystal oscillator, falling back to the internal HFCLK source.
+pub fn stop_hfclk(clock: &CLOCK) {
+    clock.tasks_hfclkstop.write(|w| unsafe { w.bits(1) });
+}
+
+/// Returns whether the HFCLK is running from the crystal oscillator.
+pub fn hfclk_running(clock: &CLOCK) -> bool {
+    let stat = clock.hfclkstat.read();
+    stat.state().is_running() && stat.src().is_xtal()
+}
+
+/// Starts the LFCLK from the given source.
+///
+/// Blocks until the LFCLK is running. If the LFCLK is already running it is
+/// stopped first, so that the new source takes effect.
+pub fn start_lfclk(clock: &CLOCK, source: LfClockSource) {
+    if source == LfClockSource::Synthesized {
+        start_hfclk(clock);
+    }
+    if clock.lfclkstat.read().state().is_running() {
+        stop_lfclk(clock);
+    }
+    clock.lfclksrc.write(|w| match source {
+        LfClockSource::Rc => w.src().rc(),
+        LfClockSource::Crystal => w.src().xtal(),
+        LfClockSource::Synthesized => w.src().synth(),
+    });
+    clock.events_lfclkstarted.reset();
+    clock.tasks_lfclkstart.write(|w| unsafe { w.bits(1) });
+    while clock.events_lfclkstarted.read().bits() == 0 {}
+    clock.events_lfclkstarted.reset();
+}
+
+/// Stops the LFCLK.
+///
+/// Any `RTC` peripherals stop counting until the LFCLK is restarted.
+pub fn stop_lfclk(clock: &CLOCK) {
+    clock.tasks_lfclkstop.write(|w| unsafe { w.bits(1) });
+    while clock.lfclkstat.read().state().is_running() {}
+}
+
+/// Returns whether the LFCLK is running.
+pub fn lfclk_running(clock: &CLOCK) -> bool {
+    clock.lfclkstat.read().state().is_running()
+}
@@ -0,0 +1,146 @@
+//! A monotonic clock and calendar on an `RTC` peripheral.
+
+use crate::{
+    hal::rtc::{Instance, Rtc, RtcInterrupt},
+    time::{RtcDuration, RtcInstant},
+};
+
+use super::DateTime;
+
+/// The tick rate of an [`RtcClock`], in Hz.
+pub const TICKS_PER_SECOND: u32 = 32_768;
+
+/// The `RTC` counter is 24 bits wide.
+const COUNTER_BITS: u32 = 24;
+
+/// The bits of the `RTC` counter.
+pub(crate) const COUNTER_MASK: u32 = (1 << COUNTER_BITS) - 1;
+
+/// The minimum distance into the future of an `RTC` compare value, in ticks.
+pub(crate) const MIN_COMPARE_TICKS: u32 = 3;
+
+/// A monotonic clock and calendar running on an `RTC` peripheral.
+///
+/// The `RTC` counts at 32.768kHz, with no prescaling, and its 24-bit counter
+/// overflows every 512 seconds. `RtcClock` counts the overflows to provide a
+/// 64-bit tick count which won't wrap for millions of years.
+///
+/// Overflows are counted from the `RTC`'s interrupt, so the interrupt must
+/// be unmasked in the NVIC and [`handle_interrupt()`](RtcClock::handle_interrupt)
+/// called from its handler at least once every 512 seconds.
+///
+/// The LFCLK must be running for the clock to advance (see
+/// [`start_lfclk()`](super::start_lfclk)).
+pub struct RtcClock<T: Instance> {
+    rtc: Rtc<T>,
+    overflows: u32,
+    /// Ticks since 2000-01-01 00:00:00 at tick 0, if the time has been set.
+    ///
+    /// This is negative if the time set is earlier than the clock's uptime.
+    epoch: Option<i64>,
+}
+
+impl<T: Instance> RtcClock<T> {
+    /// Returns a new `RtcClock` wrapping the passed `RTC`, and starts it.
+    ///
+    /// Takes ownership of the `RTC` peripheral.
+    pub fn new(rtc: T) -> Self {
+        rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+        // A prescaler of 0 is always in range
+        let mut rtc = Rtc::new(rtc, 0).unwrap();
+        rtc.reset_event(RtcInterrupt::Overflow);
+        rtc.enable_event(RtcInterrupt::Overflow);
+        rtc.enable_interrupt(RtcInterrupt::Overflow, None);
+        rtc.enable_counter();
+        RtcClock {
+            rtc,
+            overflows: 0,
+            epoch: None,
+        }
+    }
+
+    /// Stops the clock and gives the underlying `RTC` instance back.
+    pub fn free(self) -> T {
+        self.rtc.disable_counter();
+        self.rtc.release()
+    }
+
+    /// Counts a counter overflow, if one has happened.
+    ///
+    /// Call this from the `RTC`'s interrupt handler.
+    ///
+    /// Takes care of clearing the overflow event.
+    pub fn handle_interrupt(&mut self) {
+        if self.rtc.is_event_triggered(RtcInterrupt::Overflow) {
+            self.rtc.reset_event(RtcInterrupt::Overflow);
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+    }
+
+    /// Returns the number of ticks since the clock was started.
+    ///
+    /// This also accounts for an overflow which hasn't been handled by
+    /// [`handle_interrupt()`](RtcClock::handle_interrupt) yet.
+    pub fn ticks(&self) -> u64 {
+        let before = self.rtc.get_counter();
+        let pending = self.rtc.is_event_triggered(RtcInterrupt::Overflow);
+        let after = self.rtc.get_counter();
+        let (overflows, counter) = if pending {
+            (self.overflows.wrapping_add(1), after)
+        } else {
+            (self.overflows, before)
+        };
+        u64::from(overflows) << COUNTER_BITS | u64::from(counter)
+    }
+
+    /// Returns the current instant.
+    pub fn now(&self) -> RtcInstant {
+        RtcInstant::from_ticks(self.ticks())
+    }
+
+    /// Returns the time since the clock was started.
+    pub fn uptime(&self) -> RtcDuration {
+        RtcDuration::from_ticks(self.ticks())
+    }
+
+    /// Returns the number of whole seconds since the clock was started.
+    pub fn uptime_secs(&self) -> u64 {
+        self.uptime().to_secs()
+    }
+
+    /// Returns a reference to the underlying [`Rtc`].
+    ///
+    /// This can be used to program the compare registers. Don't change the
+    /// prescaler or clear the counter, as this will break the tick count.
+    pub fn rtc(&mut self) -> &mut Rtc<T> {
+        &mut self.rtc
+    }
+
+    /// Sets the wall-clock time.
+    pub fn set_datetime(&mut self, datetime: DateTime) {
+        self.set_datetime_at(datetime, self.now());
+    }
+
+    /// Sets the wall-clock time, given the instant at which it was valid.
+    ///
+    /// This is useful when the time comes from a signal captured earlier,
+    /// such as the start of a DCF77 minute mark.
+    pub fn set_datetime_at(&mut self, datetime: DateTime, at: RtcInstant) {
+        let ticks = datetime.timestamp() as i64 * i64::from(TICKS_PER_SECOND);
+        self.epoch = Some(ticks - at.ticks() as i64);
+    }
+
+    /// Returns the wall-clock time, or `None` if it hasn't been set.
+    pub fn datetime(&self) -> Option<DateTime> {
+        self.epoch.map(|epoch| {
+            let ticks = epoch + self.ticks() as i64;
+            DateTime::from_timestamp((ticks / i64::from(TICKS_PER_SECOND)) as u64)
+        })
+    }
+
+    /// Returns whether the wall-clock time has been set.
+    pub fn is_set(&self) -> bool {
+        self.epoch.is_some()
+    }
+}
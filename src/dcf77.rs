@@ -0,0 +1,402 @@
+//! DCF77 time signal receiver.
+//!
+//! # Scope
+//!
+//! This module decodes the [DCF77] time signal from a receiver module
+//! connected to any GPIO pin, and uses it to set the wall-clock time of an
+//! [`RtcClock`].
+//!
+//! Rather than polling the receiver output, the pin is watched by a `GPIOTE`
+//! channel and each edge is timestamped with the `RtcClock`'s tick count.
+//! The pulse lengths and intervals are then measured from these timestamps.
+//!
+//! A decoded minute is only used to set the clock once its parity checks
+//! have passed and it follows on from the minute decoded just before it.
+//!
+//! # Signal format
+//!
+//! The start of each second is marked by a pulse: 100ms for a 0 bit and
+//! 200ms for a 1 bit. The pulse for second 59 is left out, so the 2s gap
+//! marks the start of the next minute. The 59 bits sent during a minute
+//! describe the time at the *next* minute mark.
+//!
+//! The time transmitted is German local time (CET or CEST), and is stored
+//! in the `RtcClock` as-is.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     board::Board,
+//!     clock::RtcClock,
+//!     dcf77::{Dcf77Receiver, Polarity},
+//!     hal::gpiote::Gpiote,
+//! };
+//!
+//! let board = Board::take().unwrap();
+//! let mut clock = RtcClock::new(board.RTC0);
+//!
+//! // Edge connector pin 16
+//! #[cfg(feature = "v1")]
+//! let pin = board.pins.p0_16.into_floating_input().degrade();
+//! #[cfg(feature = "v2")]
+//! let pin = board.pins.p1_02.into_floating_input().degrade();
+//! let gpiote = Gpiote::new(board.GPIOTE);
+//! gpiote.channel0().input_pin(&pin).toggle().enable_interrupt();
+//! let mut receiver = Dcf77Receiver::new(pin, Polarity::ActiveHigh);
+//!
+//! // in the GPIOTE interrupt handler
+//! gpiote.channel0().reset_events();
+//! receiver.handle_edge(&mut clock);
+//! ```
+//!
+//! See a working example at `examples/gpio_hal_receivedcf77.rs`
+//!
+//! [DCF77]: https://en.wikipedia.org/wiki/DCF77
+
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{
+    clock::{DateTime, RtcClock},
+    hal::rtc::Instance,
+    time::{RtcDuration, RtcInstant},
+};
+
+const fn ms_to_ticks(ms: u64) -> u64 {
+    RtcDuration::millis(ms).ticks()
+}
+
+const MIN_ZERO_PULSE: u64 = ms_to_ticks(40);
+const MAX_ZERO_PULSE: u64 = ms_to_ticks(140);
+const MIN_ONE_PULSE: u64 = ms_to_ticks(160);
+const MAX_ONE_PULSE: u64 = ms_to_ticks(260);
+const MIN_SECOND: u64 = ms_to_ticks(900);
+const MAX_SECOND: u64 = ms_to_ticks(1100);
+const MIN_MINUTE_MARK: u64 = ms_to_ticks(1900);
+const MAX_MINUTE_MARK: u64 = ms_to_ticks(2100);
+
+/// The level the receiver output takes during a pulse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    /// The output is high during a pulse.
+    ActiveHigh,
+    /// The output is low during a pulse.
+    ActiveLow,
+}
+
+/// Errors detected while decoding the signal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A pulse was neither 100ms nor 200ms long.
+    PulseWidth,
+    /// Pulses weren't one second apart, or two seconds at the minute mark.
+    PulseInterval,
+    /// A minute didn't contain 59 (or 60, with a leap second) bits.
+    FrameLength,
+    /// A parity check failed.
+    Parity,
+    /// A field was out of range, or a marker bit had the wrong value.
+    InvalidValue,
+}
+
+/// A time decoded from one minute of the signal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dcf77Time {
+    /// The local time at the minute mark.
+    pub datetime: DateTime,
+    /// Whether the time is summer time (CEST) rather than CET.
+    pub summer_time: bool,
+    /// The instant of the minute mark.
+    pub at: RtcInstant,
+}
+
+/// Something that happened on an edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A bit was received.
+    Bit(bool),
+    /// The signal was corrupt; decoding restarts at the next minute mark.
+    Error(Error),
+    /// A minute was decoded but hasn't been confirmed by the previous
+    /// minute yet.
+    Time(Dcf77Time),
+    /// A minute was decoded and follows on from the previous one.
+    Synchronised(Dcf77Time),
+}
+
+/// DCF77 signal decoder working from edge timestamps.
+///
+/// This contains no hardware access, so it can be fed from any source of
+/// timestamped edges. [`Dcf77Receiver`] feeds it from a GPIO pin.
+///
+/// # Example
+///
+/// Two minutes of the signal, for 14:31 and 14:32 CEST on Tuesday
+/// 2021-06-15, as received:
+///
+/// ```
+/// use microbit::{
+///     clock::DateTime,
+///     dcf77::{Decoder, Error, Event, Polarity},
+///     time::{RtcDuration, RtcInstant},
+/// };
+///
+/// // The bits of seconds 0 to 58 of each minute
+/// const MINUTES: [&str; 2] = [
+///     "01100001100010100100110001101001010010101001001100100001000",
+///     "00010110011000000100101001101001010010101001001100100001000",
+/// ];
+///
+/// /// Sends the pulse starting a second, returning the event at its start.
+/// fn pulse(decoder: &mut Decoder, start: RtcInstant, bit: char) -> Option<Event> {
+///     let width = if bit == '1' { 200 } else { 100 };
+///     let event = decoder.edge(start, true);
+///     decoder.edge(start + RtcDuration::millis(width), false);
+///     event
+/// }
+///
+/// /// Sends the minutes, returning the events at the minute marks.
+/// fn receive(minutes: &[&str]) -> Vec<Event> {
+///     let mut decoder = Decoder::new(Polarity::ActiveHigh);
+///     let mut at = RtcInstant::from_ticks(0);
+///     let mut events = Vec::new();
+///     // Second 58 of the minute before
+///     pulse(&mut decoder, at, '0');
+///     for minute in minutes {
+///         // No pulse for second 59
+///         at += RtcDuration::secs(1);
+///         for bit in minute.chars() {
+///             at += RtcDuration::secs(1);
+///             events.extend(pulse(&mut decoder, at, bit));
+///         }
+///     }
+///     // The minute mark after the last minute
+///     at += RtcDuration::secs(2);
+///     events.extend(pulse(&mut decoder, at, '0'));
+///     events
+/// }
+///
+/// let events = receive(&MINUTES);
+/// match events[..] {
+///     [Event::Time(first), Event::Synchronised(second)] => {
+///         assert_eq!(first.datetime, DateTime::new(2021, 6, 15, 14, 31, 0).unwrap());
+///         assert_eq!(second.datetime, DateTime::new(2021, 6, 15, 14, 32, 0).unwrap());
+///         assert!(second.summer_time);
+///         assert_eq!(second.at - first.at, RtcDuration::secs(60));
+///     }
+///     _ => panic!("unexpected events {:?}", events),
+/// }
+///
+/// // Bit 22, in the first minute's minutes field, flipped by interference
+/// let mut corrupted = MINUTES[0].to_string();
+/// corrupted.replace_range(22..23, "1");
+/// let events = receive(&[&corrupted, MINUTES[1]]);
+/// assert!(matches!(
+///     events[..],
+///     [Event::Error(Error::Parity), Event::Time(_)]
+/// ));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    polarity: Polarity,
+    pulse_start: Option<u64>,
+    last_pulse_start: Option<u64>,
+    in_frame: bool,
+    bits: u64,
+    count: u8,
+    previous: Option<DateTime>,
+}
+
+impl Decoder {
+    /// Returns a new decoder for a receiver with the given output polarity.
+    pub const fn new(polarity: Polarity) -> Self {
+        Decoder {
+            polarity,
+            pulse_start: None,
+            last_pulse_start: None,
+            in_frame: false,
+            bits: 0,
+            count: 0,
+            previous: None,
+        }
+    }
+
+    /// Processes an edge of the receiver output.
+    ///
+    /// `at` is the time of the edge and `high` is the level of the output
+    /// after the edge.
+    pub fn edge(&mut self, at: RtcInstant, high: bool) -> Option<Event> {
+        let ticks = at.ticks();
+        let active = high == (self.polarity == Polarity::ActiveHigh);
+        if active {
+            self.pulse_started(ticks)
+        } else {
+            self.pulse_ended(ticks)
+        }
+    }
+
+    fn pulse_started(&mut self, ticks: u64) -> Option<Event> {
+        let interval = self
+            .last_pulse_start
+            .replace(ticks)
+            .map(|last| ticks.wrapping_sub(last));
+        self.pulse_start = Some(ticks);
+        match interval {
+            Some(MIN_SECOND..=MAX_SECOND) => None,
+            Some(MIN_MINUTE_MARK..=MAX_MINUTE_MARK) => {
+                let event = if self.in_frame {
+                    Some(self.end_of_frame(ticks))
+                } else {
+                    None
+                };
+                self.in_frame = true;
+                self.bits = 0;
+                self.count = 0;
+                event
+            }
+            Some(_) => self.fail(Error::PulseInterval),
+            None => None,
+        }
+    }
+
+    fn pulse_ended(&mut self, ticks: u64) -> Option<Event> {
+        let width = ticks.wrapping_sub(self.pulse_start.take()?);
+        let bit = match width {
+            MIN_ZERO_PULSE..=MAX_ZERO_PULSE => false,
+            MIN_ONE_PULSE..=MAX_ONE_PULSE => true,
+            _ => return self.fail(Error::PulseWidth),
+        };
+        if self.in_frame {
+            if self.count >= 60 {
+                return self.fail(Error::FrameLength);
+            }
+            self.bits |= u64::from(bit) << self.count;
+            self.count += 1;
+        }
+        Some(Event::Bit(bit))
+    }
+
+    fn end_of_frame(&mut self, ticks: u64) -> Event {
+        // A leap second adds a 0 bit as bit 59.
+        if self.count != 59 && self.count != 60 {
+            return Event::Error(Error::FrameLength);
+        }
+        match decode(self.bits) {
+            Ok((datetime, summer_time)) => {
+                let time = Dcf77Time {
+                    datetime,
+                    summer_time,
+                    at: RtcInstant::from_ticks(ticks),
+                };
+                let confirmed = self
+                    .previous
+                    .replace(datetime)
+                    .is_some_and(|previous| previous.timestamp() + 60 == datetime.timestamp());
+                if confirmed {
+                    Event::Synchronised(time)
+                } else {
+                    Event::Time(time)
+                }
+            }
+            Err(err) => {
+                self.previous = None;
+                Event::Error(err)
+            }
+        }
+    }
+
+    fn fail(&mut self, err: Error) -> Option<Event> {
+        self.in_frame = false;
+        self.previous = None;
+        Some(Event::Error(err))
+    }
+}
+
+/// Returns whether bits `first..=last` have even parity.
+fn even_parity(bits: u64, first: u32, last: u32) -> bool {
+    let mask = (1u64 << (last + 1)) - (1u64 << first);
+    (bits & mask).count_ones() & 1 == 0
+}
+
+/// Decodes a BCD field of `len` bits starting at bit `first`.
+fn bcd(bits: u64, first: u32, len: u32) -> Result<u8, Error> {
+    let field = ((bits >> first) & ((1 << len) - 1)) as u8;
+    let (tens, units) = (field >> 4, field & 0xf);
+    if tens > 9 || units > 9 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(tens * 10 + units)
+}
+
+/// Decodes the 59 bits of a minute into a date and time and the summer time
+/// flag.
+fn decode(bits: u64) -> Result<(DateTime, bool), Error> {
+    // Bit 0 is always 0 and bit 20 (start of time) always 1.
+    if bits & 1 != 0 || bits & 1 << 20 == 0 {
+        return Err(Error::InvalidValue);
+    }
+    if !even_parity(bits, 21, 28) || !even_parity(bits, 29, 35) || !even_parity(bits, 36, 58) {
+        return Err(Error::Parity);
+    }
+    // Exactly one of CEST (bit 17) and CET (bit 18) is set.
+    let summer_time = match (bits >> 17) & 0b11 {
+        0b01 => true,
+        0b10 => false,
+        _ => return Err(Error::InvalidValue),
+    };
+    let minute = bcd(bits, 21, 7)?;
+    let hour = bcd(bits, 29, 6)?;
+    let day = bcd(bits, 36, 6)?;
+    let weekday = bcd(bits, 42, 3)?;
+    let month = bcd(bits, 45, 5)?;
+    let year = bcd(bits, 50, 8)?;
+    let datetime = DateTime::new(2000 + u16::from(year), month, day, hour, minute, 0)
+        .ok_or(Error::InvalidValue)?;
+    // Weekdays are transmitted as 1 (Monday) to 7 (Sunday).
+    if weekday != datetime.weekday() as u8 + 1 {
+        return Err(Error::InvalidValue);
+    }
+    Ok((datetime, summer_time))
+}
+
+/// A DCF77 receiver module connected to a GPIO pin.
+///
+/// The pin should be watched by a `GPIOTE` channel in toggle mode, with
+/// [`handle_edge()`](Dcf77Receiver::handle_edge) called from the `GPIOTE`
+/// interrupt handler.
+pub struct Dcf77Receiver<P: InputPin> {
+    pin: P,
+    decoder: Decoder,
+}
+
+impl<P: InputPin> Dcf77Receiver<P> {
+    /// Returns a new `Dcf77Receiver` for a receiver connected to `pin`.
+    pub fn new(pin: P, polarity: Polarity) -> Self {
+        Dcf77Receiver {
+            pin,
+            decoder: Decoder::new(polarity),
+        }
+    }
+
+    /// Gives the pin back.
+    pub fn free(self) -> P {
+        self.pin
+    }
+
+    /// Timestamps and decodes an edge on the pin.
+    ///
+    /// Call this from the `GPIOTE` interrupt handler, after clearing the
+    /// channel's event.
+    ///
+    /// When a minute has been decoded and confirmed, the wall-clock time of
+    /// `clock` is set from it.
+    pub fn handle_edge<T: Instance>(&mut self, clock: &mut RtcClock<T>) -> Option<Event> {
+        let at = clock.now();
+        let high = self.pin.is_high().ok()?;
+        let event = self.decoder.edge(at, high);
+        if let Some(Event::Synchronised(time)) = event {
+            clock.set_datetime_at(time.datetime, time.at);
+        }
+        event
+    }
+}
@@ -7,36 +7,74 @@
 use crate::pac;
 use tiny_led_matrix::DisplayControl;
 
-const fn pin_bits(pins: &[usize]) -> u32 {
+/// The GPIO ports used by the display.
+#[cfg(feature = "v1")]
+const PORTS: usize = 1;
+#[cfg(feature = "v2")]
+const PORTS: usize = 2;
+
+/// Returns the registers of GPIO port `port`.
+#[cfg(feature = "v1")]
+fn gpio(_port: usize) -> &'static pac::gpio::RegisterBlock {
+    unsafe { &*pac::GPIO::ptr() }
+}
+
+/// Returns the registers of GPIO port `port`.
+#[cfg(feature = "v2")]
+fn gpio(port: usize) -> &'static pac::p0::RegisterBlock {
+    match port {
+        0 => unsafe { &*pac::P0::ptr() },
+        _ => unsafe { &*pac::P1::ptr() },
+    }
+}
+
+/// Returns the bits of each port for the pins, numbered from 0 on port 0
+/// and from 32 on port 1.
+const fn pin_bits(pins: &[usize]) -> [u32; PORTS] {
     let mut i: usize = 0;
-    let mut bits: u32 = 0;
+    let mut bits = [0; PORTS];
     while i < pins.len() {
-        bits |= 1 << pins[i];
+        bits[pins[i] / 32] |= 1 << (pins[i] % 32);
         i += 1;
     }
     bits
 }
 
+#[cfg(feature = "v1")]
 pub(crate) const MATRIX_COLS: usize = 9;
+#[cfg(feature = "v1")]
 const COLS: [usize; MATRIX_COLS] = [4, 5, 6, 7, 8, 9, 10, 11, 12];
-const COL_BITS: u32 = pin_bits(&COLS);
+#[cfg(feature = "v2")]
+pub(crate) const MATRIX_COLS: usize = 5;
+#[cfg(feature = "v2")]
+const COLS: [usize; MATRIX_COLS] = [28, 11, 31, 32 + 5, 30];
+const COL_BITS: [u32; PORTS] = pin_bits(&COLS);
 
+#[cfg(feature = "v1")]
 pub(crate) const MATRIX_ROWS: usize = 3;
+#[cfg(feature = "v1")]
 const ROWS: [usize; MATRIX_ROWS] = [13, 14, 15];
-const ROW_BITS: u32 = pin_bits(&ROWS);
+#[cfg(feature = "v2")]
+pub(crate) const MATRIX_ROWS: usize = 5;
+#[cfg(feature = "v2")]
+const ROWS: [usize; MATRIX_ROWS] = [21, 22, 15, 24, 19];
+const ROW_BITS: [u32; PORTS] = pin_bits(&ROWS);
 
-/// Wrapper for `nrf51::GPIO` for passing to the display code.
+/// Wrapper for the GPIO ports for passing to the display code.
 ///
 /// This implements the `DisplayControl` trait.
 ///
 /// [`DisplayControl`]: tiny_led_matrix::DisplayControl
 pub(crate) struct MicrobitGpio;
 
-/// Returns the GPIO pin numbers corresponding to the columns in a Columnt et.
-fn column_pins(mut cols: u32) -> u32 {
+/// Returns the bits of `port` for the pins corresponding to the columns in
+/// a column set.
+fn column_pins(mut cols: u32, port: usize) -> u32 {
     let mut result = 0u32;
-    for pin in COLS.iter() {
-        result |= (cols & 1) << pin;
+    for &pin in COLS.iter() {
+        if pin / 32 == port {
+            result |= (cols & 1) << (pin % 32);
+        }
         cols >>= 1;
     }
     result
@@ -52,40 +90,38 @@ fn column_pins(mut cols: u32) -> u32 {
 /// [`DisplayControl`]: tiny_led_matrix::DisplayControl
 impl DisplayControl for MicrobitGpio {
     fn initialise_for_display(&mut self) {
-        unsafe {
-            let gpio = &*pac::GPIO::ptr();
-            for ii in COLS.iter() {
-                gpio.pin_cnf[*ii].write(|w| w.dir().output());
-            }
-            for ii in ROWS.iter() {
-                gpio.pin_cnf[*ii].write(|w| w.dir().output());
-            }
+        for &pin in COLS.iter().chain(ROWS.iter()) {
+            gpio(pin / 32).pin_cnf[pin % 32].write(|w| w.dir().output());
+        }
 
-            // Set all cols high.
-            gpio.outset
-                .write(|w| w.bits(COLS.iter().map(|pin| 1 << pin).sum()));
+        // Set all cols high.
+        for (port, &bits) in COL_BITS.iter().enumerate() {
+            gpio(port).outset.write(|w| unsafe { w.bits(bits) });
         }
     }
 
     fn display_row_leds(&mut self, row: usize, cols: u32) {
-        unsafe {
-            let gpio = &*pac::GPIO::ptr();
+        for port in 0..PORTS {
+            let gpio = gpio(port);
             // To light an LED, we set the row bit and clear the col bit.
-            let rows_to_set = 1 << ROWS[row];
-            let rows_to_clear = ROW_BITS ^ rows_to_set;
+            let rows_to_set = pin_bits(&ROWS[row..=row])[port];
+            let rows_to_clear = ROW_BITS[port] ^ rows_to_set;
 
-            let cols_to_clear = column_pins(cols);
-            let cols_to_set = COL_BITS ^ cols_to_clear;
+            let cols_to_clear = column_pins(cols, port);
+            let cols_to_set = COL_BITS[port] ^ cols_to_clear;
 
-            gpio.outset.write(|w| w.bits(rows_to_set | cols_to_set));
-            gpio.outclr.write(|w| w.bits(rows_to_clear | cols_to_clear));
+            gpio.outset
+                .write(|w| unsafe { w.bits(rows_to_set | cols_to_set) });
+            gpio.outclr
+                .write(|w| unsafe { w.bits(rows_to_clear | cols_to_clear) });
         }
     }
 
     fn light_current_row_leds(&mut self, cols: u32) {
-        unsafe {
-            let gpio = &*crate::pac::GPIO::ptr();
-            gpio.outclr.write(|w| w.bits(column_pins(cols)));
+        for port in 0..PORTS {
+            gpio(port)
+                .outclr
+                .write(|w| unsafe { w.bits(column_pins(cols, port)) });
         }
     }
 }
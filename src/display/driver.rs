@@ -0,0 +1,90 @@
+//! A [`Display`] bundled with its timer and pins.
+
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::{gpio::DisplayPins, hal::timer::Instance, time::MillisDurationU32};
+
+use super::{
+    brightnesses, control::MicrobitGpio, image::GreyscaleImage, Display, Frame, LedMatrix,
+    MicrobitDisplayTimer, MicrobitFrame, Render,
+};
+
+/// Everything needed to drive the LED display, in a single value.
+///
+/// This is convenient as a single RTIC resource, shared between the timer's
+/// interrupt handler and the tasks which change the image.
+pub struct DisplayDriver<T: Instance> {
+    display: Display<MicrobitFrame>,
+    timer: MicrobitDisplayTimer<T>,
+    pins: DisplayPins,
+    frame: MicrobitFrame,
+    image: [[u8; 5]; 5],
+}
+
+impl<T: Instance> DisplayDriver<T> {
+    /// Initialises the display, driven from `timer`.
+    ///
+    /// Takes ownership of the TIMER peripheral and the display pins.
+    pub fn new(timer: T, mut pins: DisplayPins) -> Self {
+        let mut timer = MicrobitDisplayTimer::new(timer);
+        super::initialise_display(&mut timer, &mut pins);
+        DisplayDriver {
+            display: Display::new(),
+            timer,
+            pins,
+            frame: MicrobitFrame::const_default(),
+            image: [[0; 5]; 5],
+        }
+    }
+
+    /// Gives the underlying TIMER instance and pins back.
+    pub fn free(self) -> (T, DisplayPins) {
+        (self.timer.free(), self.pins)
+    }
+
+    /// Updates the LEDs and timer state.
+    ///
+    /// Call this in the interrupt handler for the timer.
+    ///
+    /// See [`handle_display_event()`](super::handle_display_event).
+    pub fn handle_display_event(&mut self) {
+        self.display
+            .handle_event(&mut self.timer, &mut MicrobitGpio {});
+    }
+
+    /// Shows a frame until something else is shown.
+    ///
+    /// The frame can't be read back, so
+    /// [`brightness()`](LedMatrix::brightness) gives 0 for each LED until
+    /// an image is shown.
+    pub fn set_frame(&mut self, frame: &MicrobitFrame) {
+        self.image = [[0; 5]; 5];
+        self.display.set_frame(frame);
+    }
+
+    /// Shows an image until something else is shown.
+    pub fn show<R: Render>(&mut self, image: &R) {
+        self.image = brightnesses(image);
+        self.frame.set(image);
+        self.display.set_frame(&self.frame);
+    }
+
+    /// Turns all the LEDs off.
+    pub fn clear(&mut self) {
+        self.show(&GreyscaleImage::blank());
+    }
+}
+
+impl<T: Instance> LedMatrix for DisplayDriver<T> {
+    fn show<R: Render>(&mut self, image: &R) {
+        DisplayDriver::show(self, image);
+    }
+
+    fn brightness(&self, x: usize, y: usize) -> u8 {
+        self.image[y][x]
+    }
+
+    fn hold<D: DelayUs<u32>>(&mut self, delay: &mut D, duration: MillisDurationU32) {
+        delay.delay_us(duration.to_micros());
+    }
+}
@@ -17,6 +17,8 @@ impl GreyscaleImage {
     /// # Example
     ///
     /// ```
+    /// use microbit::display::image::GreyscaleImage;
+    ///
     /// const GREY_HEART: GreyscaleImage = GreyscaleImage::new(&[
     ///     [0, 9, 0, 9, 0],
     ///     [9, 5, 9, 5, 9],
@@ -65,6 +67,8 @@ impl BitImage {
     /// # Example
     ///
     /// ```
+    /// use microbit::display::image::BitImage;
+    ///
     /// const HEART: BitImage = BitImage::new(&[
     ///     [0, 1, 0, 1, 0],
     ///     [1, 0, 1, 0, 1],
@@ -99,7 +103,7 @@ impl Render for BitImage {
     fn brightness_at(&self, x: usize, y: usize) -> u8 {
         let rowdata = self.0[y];
         if rowdata & (1 << x) != 0 {
-            MAX_BRIGHTNESS as u8
+            MAX_BRIGHTNESS
         } else {
             0
         }
@@ -0,0 +1,59 @@
+//! The wiring of the 25 LEDs to the matrix rows and columns.
+//!
+//! [`LED_LAYOUT`] is the single description of the wiring, used by the
+//! blocking [`led`](crate::led) driver. [`MATRIX_LAYOUT`], the other
+//! direction used by the non-blocking driver, is generated from it when
+//! building.
+
+use super::control::{MATRIX_COLS, MATRIX_ROWS};
+
+/// Gives the matrix (row, column) for each LED, by (y, x) coordinates.
+#[cfg(feature = "v1")]
+pub(crate) const LED_LAYOUT: [[(usize, usize); 5]; 5] = [
+    [(0, 0), (1, 3), (0, 1), (1, 4), (0, 2)],
+    [(2, 3), (2, 4), (2, 5), (2, 6), (2, 7)],
+    [(1, 1), (0, 8), (1, 2), (2, 8), (1, 0)],
+    [(0, 7), (0, 6), (0, 5), (0, 4), (0, 3)],
+    [(2, 2), (1, 6), (2, 0), (1, 5), (2, 1)],
+];
+
+/// Gives the matrix (row, column) for each LED, by (y, x) coordinates.
+#[cfg(feature = "v2")]
+pub(crate) const LED_LAYOUT: [[(usize, usize); 5]; 5] = [
+    [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)],
+    [(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)],
+    [(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)],
+    [(3, 0), (3, 1), (3, 2), (3, 3), (3, 4)],
+    [(4, 0), (4, 1), (4, 2), (4, 3), (4, 4)],
+];
+
+/// Gives the LED (x, y) coordinates for each matrix column and row, or
+/// `None` where no LED is wired.
+pub(crate) const MATRIX_LAYOUT: [[Option<(usize, usize)>; MATRIX_ROWS]; MATRIX_COLS] =
+    matrix_layout(&LED_LAYOUT);
+
+/// Inverts a layout from LED coordinates to matrix positions.
+///
+/// This fails the build if an LED is outside the matrix, or shares its
+/// position with another, so that each of the 25 LEDs has its own.
+const fn matrix_layout(
+    led_layout: &[[(usize, usize); 5]; 5],
+) -> [[Option<(usize, usize)>; MATRIX_ROWS]; MATRIX_COLS] {
+    let mut layout = [[None; MATRIX_ROWS]; MATRIX_COLS];
+    let mut y = 0;
+    while y < 5 {
+        let mut x = 0;
+        while x < 5 {
+            let (row, col) = led_layout[y][x];
+            assert!(
+                row < MATRIX_ROWS && col < MATRIX_COLS,
+                "LED outside the matrix"
+            );
+            assert!(layout[col][row].is_none(), "two LEDs in a matrix position");
+            layout[col][row] = Some((x, y));
+            x += 1;
+        }
+        y += 1;
+    }
+    layout
+}
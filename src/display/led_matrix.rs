@@ -0,0 +1,94 @@
+//! A common interface to the blocking and non-blocking displays.
+
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::time::MillisDurationU32;
+
+use super::{image::GreyscaleImage, Render, MAX_BRIGHTNESS};
+
+/// The 5×5 LED display, showing images.
+///
+/// This is implemented by the blocking [`led::Display`](crate::led::Display)
+/// and by the non-blocking [`DisplayDriver`](super::DisplayDriver) and
+/// [`AsyncDisplay`](super::AsyncDisplay), so that code written for one works
+/// with the others.
+///
+/// The non-blocking displays light the LEDs from their timer interrupt, so
+/// [`hold()`](LedMatrix::hold) only waits. The blocking display only lights
+/// the LEDs during `hold()`, with each LED either on or off.
+///
+/// # Example
+///
+/// ```no_run
+/// use microbit::{
+///     board::Board,
+///     display::{image::BitImage, LedMatrix},
+///     hal::Timer,
+///     led,
+///     time::ExtU32,
+/// };
+///
+/// fn blink(matrix: &mut impl LedMatrix, timer: &mut Timer<microbit::pac::TIMER0>) {
+///     matrix.show(&BitImage::new(&[
+///         [0, 1, 0, 1, 0],
+///         [1, 0, 1, 0, 1],
+///         [1, 0, 0, 0, 1],
+///         [0, 1, 0, 1, 0],
+///         [0, 0, 1, 0, 0],
+///     ]));
+///     matrix.hold(timer, 500.millis());
+///     matrix.set_pixel(2, 2, 9);
+///     matrix.hold(timer, 500.millis());
+///     matrix.clear();
+/// }
+///
+/// let board = Board::take().unwrap();
+/// let mut timer = Timer::new(board.TIMER0);
+/// let mut matrix = led::Display::new(board.display_pins);
+/// loop {
+///     blink(&mut matrix, &mut timer);
+/// }
+/// ```
+pub trait LedMatrix {
+    /// Shows an image until something else is shown.
+    fn show<R: Render>(&mut self, image: &R);
+
+    /// Turns all the LEDs off.
+    fn clear(&mut self) {
+        self.show(&GreyscaleImage::blank());
+    }
+
+    /// Sets the brightness of the LED at (x, y), from 0 to
+    /// [`MAX_BRIGHTNESS`], keeping the others as they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is over 4.
+    fn set_pixel(&mut self, x: usize, y: usize, brightness: u8) {
+        let mut image = [[0; 5]; 5];
+        for (row_y, row) in image.iter_mut().enumerate() {
+            for (row_x, led) in row.iter_mut().enumerate() {
+                *led = self.brightness(row_x, row_y);
+            }
+        }
+        image[y][x] = brightness.min(MAX_BRIGHTNESS);
+        self.show(&GreyscaleImage::new(&image));
+    }
+
+    /// Returns the brightness of the LED at (x, y) in the image shown.
+    fn brightness(&self, x: usize, y: usize) -> u8;
+
+    /// Keeps showing the image for `duration`.
+    fn hold<D: DelayUs<u32>>(&mut self, delay: &mut D, duration: MillisDurationU32);
+}
+
+/// Returns the brightness of each LED in an image, by (y, x) coordinates.
+pub(crate) fn brightnesses<R: Render>(image: &R) -> [[u8; 5]; 5] {
+    let mut brightnesses = [[0; 5]; 5];
+    for (y, row) in brightnesses.iter_mut().enumerate() {
+        for (x, led) in row.iter_mut().enumerate() {
+            *led = image.brightness_at(x, y).min(MAX_BRIGHTNESS);
+        }
+    }
+    brightnesses
+}
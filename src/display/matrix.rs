@@ -6,7 +6,10 @@
 //! [`Matrix`]: tiny_led_matrix::Matrix
 //! [`Frame`]: tiny_led_matrix::Frame
 
-use crate::display::control::{MATRIX_COLS, MATRIX_ROWS};
+use crate::display::{
+    control::{MATRIX_COLS, MATRIX_ROWS},
+    layout::MATRIX_LAYOUT,
+};
 use tiny_led_matrix::{Frame, Matrix, RowPlan};
 
 /// Implementation of [`Matrix`] for the microbit's LED display.
@@ -14,24 +17,11 @@ use tiny_led_matrix::{Frame, Matrix, RowPlan};
 /// [`Matrix`]: tiny_led_matrix::Matrix
 pub struct MicrobitMatrix();
 
-/// Gives the LED (x, y) coordinates for a given pin row and column.
-/// The origin is in the top-left.
-const MICROBIT_LED_LAYOUT: [[Option<(usize, usize)>; 3]; 9] = [
-    [Some((0, 0)), Some((4, 2)), Some((2, 4))],
-    [Some((2, 0)), Some((0, 2)), Some((4, 4))],
-    [Some((4, 0)), Some((2, 2)), Some((0, 4))],
-    [Some((4, 3)), Some((1, 0)), Some((0, 1))],
-    [Some((3, 3)), Some((3, 0)), Some((1, 1))],
-    [Some((2, 3)), Some((3, 4)), Some((2, 1))],
-    [Some((1, 3)), Some((1, 4)), Some((3, 1))],
-    [Some((0, 3)), None, Some((4, 1))],
-    [Some((1, 2)), None, Some((3, 2))],
-];
-
 impl Matrix for MicrobitMatrix {
-    /// The number of pins connected to LED columns (3).
+    /// The number of pins connected to LED columns (9 on the v1, 5 on the
+    /// v2).
     const MATRIX_COLS: usize = MATRIX_COLS;
-    /// The number of pins connected to LED rows (9).
+    /// The number of pins connected to LED rows (3 on the v1, 5 on the v2).
     const MATRIX_ROWS: usize = MATRIX_ROWS;
     /// The number of visible LED columns (5).
     const IMAGE_COLS: usize = 5;
@@ -39,7 +29,7 @@ impl Matrix for MicrobitMatrix {
     const IMAGE_ROWS: usize = 5;
 
     fn image_coordinates(col: usize, row: usize) -> Option<(usize, usize)> {
-        MICROBIT_LED_LAYOUT[col][row]
+        MATRIX_LAYOUT[col][row]
     }
 }
 
@@ -13,48 +13,58 @@
 //!
 //! # Example
 //!
-//! This shows general usage but is not a working example.
-//! For a working exaple see `examples/led_nonblocking.rs`.
+//! The display is driven from a timer's interrupt handler, which shares a
+//! [`DisplayDriver`] with the code changing the image.
+//! For a working example see `examples/led_nonblocking.rs`.
 //!
 //! ```no_run
+//! use core::cell::RefCell;
+//!
+//! use cortex_m::interrupt::Mutex;
+//! use microbit::{
+//!     board::Board,
+//!     display::{image::GreyscaleImage, DisplayDriver},
+//!     hal::{prelude::*, Timer},
+//!     pac::{self, TIMER1},
+//! };
+//!
+//! static DISPLAY: Mutex<RefCell<Option<DisplayDriver<TIMER1>>>> = Mutex::new(RefCell::new(None));
+//!
 //! // in your main function
-//! {
-//!     let p = pac::Peripherals::take().unwrap();
-//!     let mut timer = MicrobitDisplayTimer::new(p.TIMER1);
-//!     let p0parts = P0Parts::new(p.GPIO);
-//!     let mut pins = display_pins!(p0parts);
-//!     display::initialise_display(&mut timer, &mut pins);
-//!     let display = Display::new();
-//!
-//!     static mut FRAME: MicrobitFrame = MicrobitFrame::const_default();
-//!
-//!     loop {
-//!         FRAME.set(GreyscaleImage::new(&[
-//!             [0, 7, 0, 7, 0],
-//!             [7, 0, 7, 0, 7],
-//!             [7, 0, 0, 0, 7],
-//!             [0, 7, 0, 7, 0],
-//!             [0, 0, 7, 0, 0],
-//!         ]));
-//!         display.set_frame(&FRAME);
-//!         timer2.delay_ms(1000);
-//!
-//!         FRAME.set(GreyscaleImage::new(&[
-//!             [0, 0, 0, 0, 0],
-//!             [0, 0, 0, 0, 0],
-//!             [0, 0, 0, 0, 0],
-//!             [0, 0, 0, 0, 0],
-//!             [0, 0, 0, 0, 0],
-//!         ]));
-//!         display.set_frame(&FRAME);
-//!         timer2.delay_ms(1000);
-//!     }
+//! let board = Board::take().unwrap();
+//! let mut timer = Timer::new(board.TIMER0);
+//! let display = DisplayDriver::new(board.TIMER1, board.display_pins);
+//! cortex_m::interrupt::free(|cs| *DISPLAY.borrow(cs).borrow_mut() = Some(display));
+//! unsafe { pac::NVIC::unmask(pac::Interrupt::TIMER1) };
+//!
+//! let heart = GreyscaleImage::new(&[
+//!     [0, 7, 0, 7, 0],
+//!     [7, 0, 7, 0, 7],
+//!     [7, 0, 0, 0, 7],
+//!     [0, 7, 0, 7, 0],
+//!     [0, 0, 7, 0, 0],
+//! ]);
+//! loop {
+//!     cortex_m::interrupt::free(|cs| {
+//!         if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+//!             display.show(&heart);
+//!         }
+//!     });
+//!     timer.delay_ms(1000_u32);
+//!     cortex_m::interrupt::free(|cs| {
+//!         if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+//!             display.clear();
+//!         }
+//!     });
+//!     timer.delay_ms(1000_u32);
 //! }
 //!
-//! // in a timer interrupt
-//! {
-//!     display::handle_display_event(display, timer, pins);
-//! }
+//! // in the TIMER1 interrupt handler
+//! cortex_m::interrupt::free(|cs| {
+//!     if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+//!         display.handle_display_event();
+//!     }
+//! });
 //! ```
 //!
 //! # Coordinate system
@@ -130,8 +140,8 @@
 //!
 //! ## Technical details
 //!
-//! The timer is set to 16-bit mode, using a 62.5kHz clock (16 µs ticks). It
-//! resets every 375 ticks.
+//! The timer is set to 16-bit mode, using a 62.5kHz clock (16 µs ticks, see
+//! [`TICK_RATE`] and [`TICK`]). It resets every 375 ticks.
 //!
 //! # Usage
 //!
@@ -140,9 +150,9 @@
 //!
 //! When your program starts:
 //! * create a [`MicrobitDisplayTimer`] struct, passing the timer you chose to
-//! [`MicrobitDisplayTimer::new()`]
+//!   [`MicrobitDisplayTimer::new()`]
 //! * call [`initialise_display()`], passing it the `MicrobitDisplayTimer` and the
-//! [`crate::gpio::DisplayPins`]
+//!   [`crate::gpio::DisplayPins`]
 //! * create a [`Display`] struct (a `Display<MicrobitFrame>`).
 //!
 //! In an interrupt handler for the timer, call [`handle_display_event()`].
@@ -158,8 +168,27 @@
 //! Once you've called `set_frame()`, you are free to reuse the
 //! `MicrobitFrame`.
 //!
+//! # Single-value driver
+//!
+//! [`DisplayDriver`] holds the `Display`, the timer and the pins together,
+//! so that they can be kept in a single RTIC resource.
+//!
+//! # Async usage
+//!
+//! Put the `Display` in a `static` [`SharedDisplay`] used by the timer
+//! interrupt handler, and wrap it in an [`AsyncDisplay`] to show images from
+//! async code, for example with
+//! [`show_for()`](AsyncDisplay::show_for).
+//!
 //! See [`led_rtfm`](https://github.com/therealprof/microbit/blob/master/examples/led_rtfm.rs) example for a complete working example.
 //!
+//! # Common interface
+//!
+//! [`DisplayDriver`], [`AsyncDisplay`] and the blocking
+//! [`led::Display`](crate::led::Display) implement the [`LedMatrix`] trait,
+//! to show images, clear the display and set single LEDs, so that code can
+//! move between them.
+//!
 //! [dal]: https://lancaster-university.github.io/microbit-docs/
 //! [micropython]: https://microbit-micropython.readthedocs.io/
 
@@ -167,17 +196,27 @@
 pub use tiny_led_matrix::{Display, Frame, Render, MAX_BRIGHTNESS};
 
 mod control;
+mod driver;
+mod layout;
+mod led_matrix;
 mod matrix;
+mod shared;
 mod timer;
 
 pub mod image;
 
+pub use driver::DisplayDriver;
+pub use led_matrix::LedMatrix;
 pub use matrix::MicrobitFrame;
-pub use timer::MicrobitDisplayTimer;
+pub use shared::{AsyncDisplay, SharedDisplay};
+pub use timer::{MicrobitDisplayTimer, TICK, TICK_RATE};
 
 use crate::{gpio::DisplayPins, hal::timer::Instance};
 
 use control::MicrobitGpio;
+pub(crate) use control::{MATRIX_COLS, MATRIX_ROWS};
+pub(crate) use layout::LED_LAYOUT;
+pub(crate) use led_matrix::brightnesses;
 
 /// Initialises the micro:bit hardware to use the display driver.
 ///
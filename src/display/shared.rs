@@ -0,0 +1,89 @@
+//! Async access to a [`Display`] shared with its timer interrupt.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::time::{MillisDurationU32, Timer};
+
+use super::{
+    brightnesses, image::GreyscaleImage, Display, Frame, LedMatrix, MicrobitFrame, Render,
+};
+
+/// A [`Display`] shared between the main program and the timer interrupt
+/// handler, suitable for a `static`.
+///
+/// The timer interrupt handler should borrow it and pass the `Display` to
+/// [`handle_display_event()`](super::handle_display_event).
+pub type SharedDisplay = Mutex<RefCell<Option<Display<MicrobitFrame>>>>;
+
+/// Async wrapper for a [`SharedDisplay`], for use with the
+/// [`executor`](crate::executor).
+///
+/// Showing images with `AsyncDisplay` takes care of the `MicrobitFrame` and
+/// critical section, and [`show_for()`](AsyncDisplay::show_for) uses a
+/// [`Timer`], so the time driver must be running (see
+/// [`time::init()`](crate::time::init)).
+pub struct AsyncDisplay {
+    display: &'static SharedDisplay,
+    frame: MicrobitFrame,
+    image: [[u8; 5]; 5],
+}
+
+impl AsyncDisplay {
+    /// Returns a new `AsyncDisplay` for a display.
+    ///
+    /// The `Option` in `display` must be filled before showing anything.
+    pub fn new(display: &'static SharedDisplay) -> Self {
+        AsyncDisplay {
+            display,
+            frame: MicrobitFrame::const_default(),
+            image: [[0; 5]; 5],
+        }
+    }
+
+    /// Shows an image until something else is shown.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared display hasn't been filled.
+    pub fn show<R: Render>(&mut self, image: &R) {
+        self.image = brightnesses(image);
+        self.frame.set(image);
+        let frame = &self.frame;
+        cortex_m::interrupt::free(|cs| {
+            if let Some(display) = self.display.borrow(cs).borrow_mut().as_mut() {
+                display.set_frame(frame);
+            } else {
+                panic!("display not initialised");
+            }
+        });
+    }
+
+    /// Turns all the LEDs off.
+    pub fn clear(&mut self) {
+        self.show(&GreyscaleImage::blank());
+    }
+
+    /// Shows an image for `duration`, then clears the display.
+    pub async fn show_for<R: Render>(&mut self, image: &R, duration: MillisDurationU32) {
+        self.show(image);
+        Timer::after(duration).await;
+        self.clear();
+    }
+}
+
+impl LedMatrix for AsyncDisplay {
+    fn show<R: Render>(&mut self, image: &R) {
+        AsyncDisplay::show(self, image);
+    }
+
+    fn brightness(&self, x: usize, y: usize) -> u8 {
+        self.image[y][x]
+    }
+
+    fn hold<D: DelayUs<u32>>(&mut self, delay: &mut D, duration: MillisDurationU32) {
+        delay.delay_us(duration.to_micros());
+    }
+}
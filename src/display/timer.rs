@@ -4,14 +4,29 @@
 
 use tiny_led_matrix::DisplayTimer;
 
-use crate::hal::timer::Instance;
+use crate::{
+    hal::timer::Instance,
+    time::{HertzU32, MicrosDurationU32},
+};
+
+/// The frequency of the clock feeding the TIMER prescaler.
+const BASE_FREQ: HertzU32 = HertzU32::MHz(16);
+
+/// The tick rate of a [`MicrobitDisplayTimer`] (62.5kHz).
+pub const TICK_RATE: HertzU32 = HertzU32::Hz(62_500);
+
+/// The length of one [`MicrobitDisplayTimer`] tick (16 µs).
+pub const TICK: MicrosDurationU32 = TICK_RATE.into_duration();
+
+/// The TIMER divides its base clock by 2^`PRESCALER`.
+const PRESCALER: u32 = (BASE_FREQ.raw() / TICK_RATE.raw()).trailing_zeros();
 
 /// A TIMER peripheral programmed to manage the display.
 ///
 /// `MicrobitDisplayTimer` instances implement the [`DisplayTimer`] trait.
 ///
-/// The timer is set to 16-bit mode, using a 62.5kHz clock (16 µs ticks).
-/// The primary cycle takes 6ms.
+/// The timer is set to 16-bit mode, using a 62.5kHz clock (16 µs ticks, see
+/// [`TICK_RATE`] and [`TICK`]). The primary cycle takes 6ms.
 ///
 /// Uses CC0 for the primary cycle and CC1 for the secondary alarm. Uses the
 /// CC0_CLEAR shortcut to implement the primary cycle.
@@ -43,8 +58,8 @@ impl<T: Instance> DisplayTimer for MicrobitDisplayTimer<T> {
         // set as 16 bits
         timer0.bitmode.write(|w| w.bitmode()._16bit());
 
-        // set frequency to 62500Hz
-        timer0.prescaler.write(|w| unsafe { w.bits(8) });
+        // set frequency to TICK_RATE
+        timer0.prescaler.write(|w| unsafe { w.bits(PRESCALER) });
 
         // set compare register
         timer0.cc[0].write(|w| unsafe { w.bits(ticks.into()) });
@@ -0,0 +1,199 @@
+//! A minimal single-threaded executor for `async` code.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - [`block_on()`], which runs a future to completion, sleeping with `WFE`
+//!   whenever it is waiting
+//! - [`join()`], which runs two futures concurrently
+//! - [`WakerCell`], for storing the waker of a future which is woken from an
+//!   interrupt handler.
+//!
+//! The crate's async drivers don't define interrupt handlers directly;
+//! instead each provides a function to be called from the relevant
+//! interrupt handler, which wakes the waiting future:
+//! - [`time::handle_rtc_interrupt()`](crate::time::handle_rtc_interrupt)
+//!   for [`Timer`](crate::time::Timer), on `RTC1`
+//! - [`button::handle_gpiote_interrupt()`](crate::button::handle_gpiote_interrupt)
+//!   for [`Button`](crate::button::Button), on `GPIOTE`
+//! - [`serial::handle_uart_interrupt()`](crate::serial::handle_uart_interrupt)
+//!   for [`AsyncSerial`](crate::serial::AsyncSerial), on `UART0`.
+//!
+//! Each driver keeps a single waker, so each should only be awaited from
+//! one place at a time. Running everything under one `block_on()` (using
+//! [`join()`] for concurrency) satisfies this.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{executor, time::{ExtU32, Timer}};
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! microbit::time::init(p.RTC1);
+//!
+//! executor::block_on(async {
+//!     loop {
+//!         Timer::after(500.millis()).await;
+//!         // ...
+//!     }
+//! });
+//!
+//! // in the RTC1 interrupt handler
+//! microbit::time::handle_rtc_interrupt();
+//! ```
+//!
+//! See a working example at `examples/async_display_buttons.rs`
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use cortex_m::interrupt::Mutex;
+
+/// Set when the future run by `block_on()` has been woken.
+static WOKEN: AtomicBool = AtomicBool::new(true);
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+fn clone(_: *const ()) -> RawWaker {
+    RawWaker::new(core::ptr::null(), &VTABLE)
+}
+
+fn wake(_: *const ()) {
+    WOKEN.store(true, Ordering::SeqCst);
+    // Make sure a WFE about to be executed doesn't sleep.
+    cortex_m::asm::sev();
+}
+
+fn drop(_: *const ()) {}
+
+/// Runs a future to completion.
+///
+/// Between polls the processor sleeps with `WFE` until an interrupt handler
+/// wakes the future.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = future;
+    // The future is shadowed, so it can't be moved again.
+    let mut future = unsafe { Pin::new_unchecked(&mut future) };
+    let waker = unsafe { Waker::from_raw(clone(core::ptr::null())) };
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if take_woken() {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        } else {
+            cortex_m::asm::wfe();
+        }
+    }
+}
+
+/// Clears `WOKEN`, returning its previous value.
+///
+/// `AtomicBool::swap()` isn't available on the nRF51's Cortex-M0.
+fn take_woken() -> bool {
+    cortex_m::interrupt::free(|_| {
+        let woken = WOKEN.load(Ordering::SeqCst);
+        WOKEN.store(false, Ordering::SeqCst);
+        woken
+    })
+}
+
+/// Storage for the waker of a future woken from an interrupt handler.
+pub struct WakerCell(Mutex<RefCell<Option<Waker>>>);
+
+impl WakerCell {
+    /// Returns a new, empty `WakerCell`.
+    pub const fn new() -> Self {
+        WakerCell(Mutex::new(RefCell::new(None)))
+    }
+
+    /// Stores a waker, replacing any stored before.
+    pub fn register(&self, waker: &Waker) {
+        cortex_m::interrupt::free(|cs| {
+            let mut stored = self.0.borrow(cs).borrow_mut();
+            match stored.as_ref() {
+                Some(old) if old.will_wake(waker) => {}
+                _ => *stored = Some(waker.clone()),
+            }
+        });
+    }
+
+    /// Wakes and removes the stored waker, if there is one.
+    pub fn wake(&self) {
+        if let Some(waker) = cortex_m::interrupt::free(|cs| self.0.borrow(cs).borrow_mut().take()) {
+            waker.wake();
+        }
+    }
+}
+
+impl Default for WakerCell {
+    fn default() -> Self {
+        WakerCell::new()
+    }
+}
+
+enum MaybeDone<F: Future> {
+    Pending(F),
+    Done(F::Output),
+    Taken,
+}
+
+impl<F: Future> MaybeDone<F> {
+    /// Polls the future if it hasn't completed, returning whether it has.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
+        // The future is never moved out of `Pending`, only dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let MaybeDone::Pending(future) = this {
+            match unsafe { Pin::new_unchecked(future) }.poll(cx) {
+                Poll::Ready(output) => *this = MaybeDone::Done(output),
+                Poll::Pending => return false,
+            }
+        }
+        true
+    }
+
+    fn take(self: Pin<&mut Self>) -> F::Output {
+        let this = unsafe { self.get_unchecked_mut() };
+        match core::mem::replace(this, MaybeDone::Taken) {
+            MaybeDone::Done(output) => output,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Future for [`join()`].
+pub struct Join<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+/// Runs two futures concurrently, completing when both have completed.
+pub fn join<A: Future, B: Future>(a: A, b: B) -> Join<A, B> {
+    Join {
+        a: MaybeDone::Pending(a),
+        b: MaybeDone::Pending(b),
+    }
+}
+
+impl<A: Future, B: Future> Future for Join<A, B> {
+    type Output = (A::Output, B::Output);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The fields are structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut a = unsafe { Pin::new_unchecked(&mut this.a) };
+        let mut b = unsafe { Pin::new_unchecked(&mut this.b) };
+        let a_done = a.as_mut().poll(cx);
+        let b_done = b.as_mut().poll(cx);
+        if a_done && b_done {
+            Poll::Ready((a.take(), b.take()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
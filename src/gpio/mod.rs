@@ -0,0 +1,20 @@
+//! Named GPIO pin types
+//!
+//! This module maps the GPIO pin names as described in the
+//! [Pins and Signals section of the micro:bit site](https://tech.microbit.org/hardware/edgeconnector/#pins-and-signals)
+//! Where appropriate the pins are restricted with the appropriate `MODE`
+//! from `nrf-hal`.
+#![allow(clippy::upper_case_acronyms, missing_docs)]
+//!
+//! The pins differ between the micro:bit v1 and v2: the types for the board
+//! selected by the `v1` or `v2` feature are exported here.
+
+#[cfg(feature = "v1")]
+mod v1;
+#[cfg(feature = "v1")]
+pub use v1::*;
+
+#[cfg(feature = "v2")]
+mod v2;
+#[cfg(feature = "v2")]
+pub use v2::*;
@@ -1,10 +1,3 @@
-//! Named GPIO pin types
-//!
-//! This module maps the GPIO pin names as described in the
-//! [Pins and Signals section of the micro:bit site](https://tech.microbit.org/hardware/edgeconnector/#pins-and-signals)
-//! Where appropriate the pins are restricted with the appropriate `MODE`
-//! from `nrf-hal`.
-#![allow(clippy::upper_case_acronyms, missing_docs)]
 use crate::hal::gpio::{p0, Floating, Input, Output, PushPull};
 
 /* GPIO pads */
@@ -0,0 +1,230 @@
+//! Interface chip awareness: USB connection and serial break detection.
+//!
+//! # Scope
+//!
+//! The interface chip (the KL26 on the v1, the KL27 on the v2) runs the
+//! DAPLink firmware, which bridges the `UART` to the USB serial port. This
+//! module provides:
+//! - serial break detection on the `UART_RX` line ([`rx_break()`]), and
+//!   breaks seen by a running `UART` ([`take_uart_break()`])
+//! - USB detection from the `UART_RX` line ([`usb_connected()`]), debounced
+//!   by a [`UsbMonitor`]
+//! - [`PowerSource`] detection from the supply voltage.
+//!
+//! The interface chip is only powered from USB. While it runs, it holds the
+//! `UART_RX` line high when idle; on battery, the line is pulled low, which
+//! looks like a break that never ends. A host can also send a short break,
+//! which DAPLink passes on. USB power from a charger can't be told apart
+//! from a host.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     hal::Timer,
+//!     interface::{self, UsbEvent, UsbMonitor},
+//! };
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let mut timer = Timer::new(p.TIMER0);
+//! let mut usb = UsbMonitor::new();
+//!
+//! loop {
+//!     match usb.update(interface::usb_connected(&mut timer)) {
+//!         Some(UsbEvent::Connected) => {
+//!             // stream the log over the serial port
+//!         }
+//!         Some(UsbEvent::Disconnected) => {
+//!             // buffer the log to flash
+//!         }
+//!         None => {}
+//!     }
+//! }
+//! ```
+//!
+//! See a working example at `examples/usb_logger.rs`
+
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::pac;
+
+/// How long the `UART_RX` line must stay low to be a break, in
+/// microseconds: about two frames at 9600 baud.
+pub const BREAK_MICROS: u32 = 2_000;
+
+/// The interval between samples of the `UART_RX` line, in microseconds.
+const SAMPLE_MICROS: u32 = 100;
+
+/// The supply voltage above which the micro:bit is taken to run from USB,
+/// in millivolts.
+pub const USB_MILLIVOLTS: u16 = 3_150;
+
+/// The number of consecutive readings a [`UsbMonitor`] needs to report a
+/// change.
+const DEBOUNCE: u8 = 3;
+
+#[cfg(feature = "v1")]
+const RX_PIN: usize = 25;
+#[cfg(feature = "v2")]
+const RX_PIN: usize = 8;
+
+/// Returns whether the `UART_RX` line is currently high, as when idle.
+pub fn rx_is_high() -> bool {
+    rx_port().in_.read().bits() & 1 << RX_PIN != 0
+}
+
+/// Returns whether the `UART_RX` line stays low for [`BREAK_MICROS`],
+/// sampling it every 100µs.
+///
+/// A pull-down is set meanwhile so that the line reads low when the
+/// interface chip is off. This works whether or not the `UART` is enabled.
+pub fn rx_break<D: DelayUs<u32>>(delay: &mut D) -> bool {
+    let port = rx_port();
+    let cnf = port.pin_cnf[RX_PIN].read().bits();
+    port.pin_cnf[RX_PIN].modify(|_, w| w.input().connect().pull().pulldown());
+    let low = (0..BREAK_MICROS / SAMPLE_MICROS).all(|_| {
+        delay.delay_us(SAMPLE_MICROS);
+        !rx_is_high()
+    });
+    port.pin_cnf[RX_PIN].write(|w| unsafe { w.bits(cnf) });
+    low
+}
+
+/// Returns whether the enabled `UART` has received a break since the last
+/// call.
+pub fn take_uart_break() -> bool {
+    // Only the BREAK error source is used
+    #[cfg(feature = "v1")]
+    let uart = unsafe { &*pac::UART0::ptr() };
+    #[cfg(feature = "v2")]
+    let uart = unsafe { &*pac::UARTE0::ptr() };
+    let received = uart.errorsrc.read().break_().bit_is_set();
+    if received {
+        uart.errorsrc.write(|w| w.break_().set_bit());
+    }
+    received
+}
+
+/// Returns whether the interface chip is powered, from USB, as the
+/// `UART_RX` line isn't in a break.
+///
+/// This takes up to [`BREAK_MICROS`].
+pub fn usb_connected<D: DelayUs<u32>>(delay: &mut D) -> bool {
+    !rx_break(delay)
+}
+
+/// What the micro:bit runs from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    /// USB, through the interface chip's 3.3V regulator.
+    Usb,
+    /// The battery connector, through a diode.
+    Battery,
+}
+
+impl PowerSource {
+    /// Returns the power source for a supply voltage, in millivolts, as from
+    /// `battery::Battery::millivolts()` on the v1.
+    ///
+    /// A battery can't reach [`USB_MILLIVOLTS`] through the diode, except
+    /// for a fresh 3.6V lithium one, which is then taken for USB.
+    ///
+    /// ```
+    /// use microbit::interface::PowerSource;
+    ///
+    /// assert_eq!(PowerSource::from_millivolts(3_300), PowerSource::Usb);
+    /// assert_eq!(PowerSource::from_millivolts(2_900), PowerSource::Battery);
+    /// ```
+    pub fn from_millivolts(millivolts: u16) -> Self {
+        if millivolts >= USB_MILLIVOLTS {
+            PowerSource::Usb
+        } else {
+            PowerSource::Battery
+        }
+    }
+}
+
+/// A change of the USB connection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UsbEvent {
+    /// The interface chip has been powered.
+    Connected,
+    /// The interface chip has been unpowered.
+    Disconnected,
+}
+
+/// Debounces USB readings, so that a host's short breaks aren't taken for a
+/// disconnection.
+///
+/// A change is reported after 3 consecutive readings agree. The first
+/// reading is reported at once.
+///
+/// ```
+/// use microbit::interface::{UsbEvent, UsbMonitor};
+///
+/// let mut usb = UsbMonitor::new();
+/// assert_eq!(usb.update(true), Some(UsbEvent::Connected));
+/// assert_eq!(usb.update(false), None);
+/// assert_eq!(usb.update(true), None);
+/// assert_eq!(usb.update(false), None);
+/// assert_eq!(usb.update(false), None);
+/// assert_eq!(usb.update(false), Some(UsbEvent::Disconnected));
+/// assert!(!usb.is_connected());
+/// ```
+#[derive(Debug, Default)]
+pub struct UsbMonitor {
+    connected: Option<bool>,
+    changes: u8,
+}
+
+impl UsbMonitor {
+    /// Returns a new `UsbMonitor`, with no reading yet.
+    pub const fn new() -> Self {
+        UsbMonitor {
+            connected: None,
+            changes: 0,
+        }
+    }
+
+    /// Records a reading, as from [`usb_connected()`], and returns an event
+    /// if the connection has changed.
+    pub fn update(&mut self, connected: bool) -> Option<UsbEvent> {
+        match self.connected {
+            Some(current) if current == connected => {
+                self.changes = 0;
+                return None;
+            }
+            Some(_) => {
+                self.changes += 1;
+                if self.changes < DEBOUNCE {
+                    return None;
+                }
+            }
+            None => {}
+        }
+        self.connected = Some(connected);
+        self.changes = 0;
+        Some(if connected {
+            UsbEvent::Connected
+        } else {
+            UsbEvent::Disconnected
+        })
+    }
+
+    /// Returns whether USB was connected at the last reported event.
+    pub fn is_connected(&self) -> bool {
+        self.connected == Some(true)
+    }
+}
+
+#[cfg(feature = "v1")]
+fn rx_port() -> &'static pac::gpio::RegisterBlock {
+    // Only the UART_RX pin's registers are used
+    unsafe { &*pac::GPIO::ptr() }
+}
+
+#[cfg(feature = "v2")]
+fn rx_port() -> &'static pac::p0::RegisterBlock {
+    // Only the UART_RX pin's registers are used
+    unsafe { &*pac::P1::ptr() }
+}
@@ -8,19 +8,17 @@
 //!
 //! ```no_run
 //! use microbit::{
-//!     display_pins,
-//!     hal::{gpio::p0::Parts, prelude::*, Timer},
+//!     board::Board,
+//!     hal::{prelude::*, Timer},
+//!     led,
+//!     time::ExtU32,
 //! };
-//! // take the peripherals
-//! let p = microbit::pac::Peripherals::take().unwrap();
+//! // take the board
+//! let board = Board::take().unwrap();
 //! // make a timer
-//! let mut timer = Timer::new(p.TIMER0);
-//! // split off the p0::Parts
-//! let p0parts = Parts::new(p.GPIO);
-//! // create the DisplayPins struct
-//! let pins = display_pins!(p0parts);
+//! let mut timer = Timer::new(board.TIMER0);
 //! // create the Display
-//! let mut leds = led::Display::new(pins);
+//! let mut leds = led::Display::new(board.display_pins);
 //! // and light up some LEDs
 //! let heart = [
 //!     [0, 1, 0, 1, 0],
@@ -30,39 +28,46 @@
 //!     [0, 0, 1, 0, 0],
 //! ];
 //! loop {
-//!     leds.display(&mut timer, heart, 1000);
+//!     leds.display(&mut timer, heart, 1000.millis());
 //!     leds.clear();
-//!     timer.delay_ms(250);
+//!     timer.delay_ms(250_u32);
 //! }
 //! ```
 //!
+//! `led::Display` also implements [`LedMatrix`], like the non-blocking
+//! displays, to show any [`Render`] image, such as a
+//! [`GreyscaleImage`](crate::display::image::GreyscaleImage) or a
+//! [`BitImage`](crate::display::image::BitImage), lit while
+//! [`hold()`](LedMatrix::hold) runs.
+//!
 //! See a working example at `examples/led_blocking.rs`
 use crate::hal::{
     gpio::{Output, Pin, PushPull},
     prelude::*,
 };
 
-use crate::gpio::DisplayPins;
+use crate::{
+    display::{brightnesses, LedMatrix, Render, LED_LAYOUT, MATRIX_COLS, MATRIX_ROWS},
+    gpio::DisplayPins,
+    time::{HertzU32, MicrosDurationU32, MillisDurationU32},
+};
 
 use embedded_hal::blocking::delay::DelayUs;
 
 #[allow(clippy::upper_case_acronyms)]
 pub(crate) type LED = Pin<Output<PushPull>>;
 
-const DEFAULT_DELAY_MS: u32 = 2;
-const LED_LAYOUT: [[(usize, usize); 5]; 5] = [
-    [(0, 0), (1, 3), (0, 1), (1, 4), (0, 2)],
-    [(2, 3), (2, 4), (2, 5), (2, 6), (2, 7)],
-    [(1, 1), (0, 8), (1, 2), (2, 8), (1, 0)],
-    [(0, 7), (0, 6), (0, 5), (0, 4), (0, 3)],
-    [(2, 2), (1, 6), (2, 0), (1, 5), (2, 1)],
-];
+const DEFAULT_DELAY: MicrosDurationU32 = MicrosDurationU32::millis(2);
+
+/// The shortest time spent on each matrix row.
+const MIN_DELAY: MicrosDurationU32 = MicrosDurationU32::micros(1);
 
 /// Blocking interface to the on board LED display
 pub struct Display {
-    delay_ms: u32,
-    rows: [LED; 3],
-    cols: [LED; 9],
+    delay: MicrosDurationU32,
+    rows: [LED; MATRIX_ROWS],
+    cols: [LED; MATRIX_COLS],
+    image: [[u8; 5]; 5],
 }
 
 impl Display {
@@ -71,32 +76,21 @@ impl Display {
     /// The [`display_pins!`](crate::display_pins) macro can be used
     /// to create [`DisplayPins`].
     pub fn new(pins: DisplayPins) -> Self {
+        let (rows, cols) = matrix_pins(pins);
         let mut retval = Display {
-            delay_ms: DEFAULT_DELAY_MS,
-            rows: [
-                pins.row1.degrade(),
-                pins.row2.degrade(),
-                pins.row3.degrade(),
-            ],
-            cols: [
-                pins.col1.degrade(),
-                pins.col2.degrade(),
-                pins.col3.degrade(),
-                pins.col4.degrade(),
-                pins.col5.degrade(),
-                pins.col6.degrade(),
-                pins.col7.degrade(),
-                pins.col8.degrade(),
-                pins.col9.degrade(),
-            ],
+            delay: DEFAULT_DELAY,
+            rows,
+            cols,
+            image: [[0; 5]; 5],
         };
         // This is needed to reduce flickering on reset
         retval.clear();
         retval
     }
 
-    /// Clear display
+    /// Clear display, and the image held for [`LedMatrix`]
     pub fn clear(&mut self) {
+        self.image = [[0; 5]; 5];
         for row in &mut self.rows {
             row.set_low().ok();
         }
@@ -105,19 +99,61 @@ impl Display {
         }
     }
 
-    /// Set delay, time spent on each matrix row, in ms
-    pub fn set_delay_ms(&mut self, delay_ms: u32) {
-        self.delay_ms = delay_ms;
+    /// Set delay, time spent on each matrix row
+    ///
+    /// A delay of 0 is raised to 1µs.
+    pub fn set_delay(&mut self, delay: MillisDurationU32) {
+        self.delay = MicrosDurationU32::micros(delay.to_micros()).max(MIN_DELAY);
     }
 
     /// Set refresh rate, time for matrix scan
-    pub fn set_refresh_rate(&mut self, freq_hz: u32) {
-        self.delay_ms = 1000 / freq_hz / 3;
+    ///
+    /// Rates too high to spend a whole microsecond on each matrix row are
+    /// clamped to 1µs per row, and a rate of 0 is raised to 1Hz.
+    pub fn set_refresh_rate(&mut self, freq: HertzU32) {
+        let scan = 1_000_000 / freq.to_Hz().max(1);
+        self.delay = MicrosDurationU32::micros(scan / self.rows.len() as u32).max(MIN_DELAY);
     }
 
-    /// Convert 5x5 display image to 3x9 matrix image
-    pub fn display2matrix(led_display: [[u8; 5]; 5]) -> [[u8; 9]; 3] {
-        let mut led_matrix: [[u8; 9]; 3] = [[0; 9]; 3];
+    /// Convert 5x5 display image to matrix image, 3x9 on the v1 and 5x5 on
+    /// the v2
+    ///
+    /// This uses the same wiring as the [`display`](crate::display) module:
+    /// each of the 25 LEDs has its own matrix position, which the
+    /// non-blocking driver maps back to the LED.
+    ///
+    /// ```
+    /// use microbit::{
+    ///     display::{Frame, MicrobitFrame},
+    ///     led,
+    /// };
+    /// use tiny_led_matrix::Matrix;
+    ///
+    /// // Number the LEDs from 1 to 25
+    /// let mut image = [[0; 5]; 5];
+    /// for (i, led) in image.iter_mut().flatten().enumerate() {
+    ///     *led = i as u8 + 1;
+    /// }
+    ///
+    /// let matrix = led::Display::display2matrix(image);
+    /// let mut seen = [false; 25];
+    /// for (row, matrix_row) in matrix.iter().enumerate() {
+    ///     for (col, &led) in matrix_row.iter().enumerate() {
+    ///         let coordinates = <MicrobitFrame as Frame>::Mtx::image_coordinates(col, row);
+    ///         match coordinates {
+    ///             Some((x, y)) => assert_eq!(image[y][x], led),
+    ///             None => assert_eq!(led, 0),
+    ///         }
+    ///         if led != 0 {
+    ///             assert!(!seen[usize::from(led) - 1]);
+    ///             seen[usize::from(led) - 1] = true;
+    ///         }
+    ///     }
+    /// }
+    /// assert!(seen.iter().all(|&seen| seen));
+    /// ```
+    pub fn display2matrix(led_display: [[u8; 5]; 5]) -> [[u8; MATRIX_COLS]; MATRIX_ROWS] {
+        let mut led_matrix = [[0; MATRIX_COLS]; MATRIX_ROWS];
         for (led_display_row, layout_row) in led_display.iter().zip(LED_LAYOUT.iter()) {
             for (led_display_val, layout_loc) in led_display_row.iter().zip(layout_row) {
                 led_matrix[layout_loc.0][layout_loc.1] = *led_display_val;
@@ -131,21 +167,23 @@ impl Display {
         &mut self,
         delay: &mut D,
         led_display: [[u8; 5]; 5],
-        duration_ms: u32,
+        duration: MillisDurationU32,
     ) {
         let led_matrix = Display::display2matrix(led_display);
-        self.display_pre(delay, led_matrix, duration_ms);
+        self.display_pre(delay, led_matrix, duration);
     }
 
-    /// Display 3x9 matrix image for a given duration
+    /// Display matrix image for a given duration
     pub fn display_pre<D: DelayUs<u32>>(
         &mut self,
         delay: &mut D,
-        led_matrix: [[u8; 9]; 3],
-        duration_ms: u32,
+        led_matrix: [[u8; MATRIX_COLS]; MATRIX_ROWS],
+        duration: MillisDurationU32,
     ) {
         // TODO: something more intelligent with timers
-        let loops = duration_ms / (self.rows.len() as u32 * self.delay_ms);
+        // In u64, as durations over 71 minutes overflow in microseconds
+        let row_us = u64::from(self.delay.ticks()) * self.rows.len() as u64;
+        let loops = u64::from(duration.to_millis()) * 1_000 / row_us;
         for _ in 0..loops {
             for (row_line, led_matrix_row) in self.rows.iter_mut().zip(led_matrix.iter()) {
                 row_line.set_high().ok();
@@ -155,7 +193,7 @@ impl Display {
                         col_line.set_low().ok();
                     }
                 }
-                delay.delay_us(self.delay_ms * 1000);
+                delay.delay_us(self.delay.ticks());
                 for col_line in &mut self.cols {
                     col_line.set_high().ok();
                 }
@@ -164,3 +202,66 @@ impl Display {
         }
     }
 }
+
+/// Splits the display pins into the matrix rows and columns.
+#[cfg(feature = "v1")]
+fn matrix_pins(pins: DisplayPins) -> ([LED; MATRIX_ROWS], [LED; MATRIX_COLS]) {
+    (
+        [
+            pins.row1.degrade(),
+            pins.row2.degrade(),
+            pins.row3.degrade(),
+        ],
+        [
+            pins.col1.degrade(),
+            pins.col2.degrade(),
+            pins.col3.degrade(),
+            pins.col4.degrade(),
+            pins.col5.degrade(),
+            pins.col6.degrade(),
+            pins.col7.degrade(),
+            pins.col8.degrade(),
+            pins.col9.degrade(),
+        ],
+    )
+}
+
+/// Splits the display pins into the matrix rows and columns.
+#[cfg(feature = "v2")]
+fn matrix_pins(pins: DisplayPins) -> ([LED; MATRIX_ROWS], [LED; MATRIX_COLS]) {
+    (
+        [
+            pins.row1.degrade(),
+            pins.row2.degrade(),
+            pins.row3.degrade(),
+            pins.row4.degrade(),
+            pins.row5.degrade(),
+        ],
+        [
+            pins.col1.degrade(),
+            pins.col2.degrade(),
+            pins.col3.degrade(),
+            pins.col4.degrade(),
+            pins.col5.degrade(),
+        ],
+    )
+}
+
+impl LedMatrix for Display {
+    /// Holds an image, to be shown by [`hold()`](LedMatrix::hold).
+    fn show<R: Render>(&mut self, image: &R) {
+        self.image = brightnesses(image);
+    }
+
+    fn clear(&mut self) {
+        Display::clear(self);
+    }
+
+    fn brightness(&self, x: usize, y: usize) -> u8 {
+        self.image[y][x]
+    }
+
+    fn hold<D: DelayUs<u32>>(&mut self, delay: &mut D, duration: MillisDurationU32) {
+        self.display(delay, self.image, duration);
+    }
+}
@@ -1,23 +1,70 @@
 //! microbit contains everything required to get started with the use of Rust
 //! to create firmwares for the fabulous [BBC micro:bit](https://microbit.org)
 //! microcontroller board.
-#![no_std]
+//!
+//! The board version is selected with the `v1` feature, on by default, or
+//! the `v2` feature. The `sim` feature builds for `std` targets, with the
+//! `sim` module to test application logic on the host.
+#![cfg_attr(not(feature = "sim"), no_std)]
 #![deny(missing_docs)]
 #![allow(non_camel_case_types)]
 
+#[cfg(all(feature = "ble", any(feature = "s110", feature = "s130")))]
+compile_error!("the ble feature drives the radio, which a SoftDevice owns");
+
+#[cfg(all(feature = "v1", feature = "v2"))]
+compile_error!("the v1 and v2 features select the board version, only one can be used");
+#[cfg(not(any(feature = "v1", feature = "v2")))]
+compile_error!("select the board version with the v1 or v2 feature");
+
 pub use hal::pac;
 pub use hal::pac::Peripherals;
+#[cfg(feature = "v1")]
 pub use nrf51_hal as hal;
+#[cfg(feature = "v2")]
+pub use nrf52833_hal as hal;
 
+#[cfg(feature = "v1")]
+pub mod battery;
+#[cfg(feature = "ble")]
+pub mod ble;
+pub mod board;
+pub mod button;
+pub mod clock;
+#[cfg(feature = "dcf77")]
+pub mod dcf77;
 pub mod display;
+pub mod executor;
 pub mod gpio;
+pub mod interface;
 pub mod led;
+#[cfg(all(feature = "rtic", feature = "v2"))]
+pub mod monotonic;
+pub mod power;
+pub mod radio;
+pub mod serial;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "v2")]
+pub mod sound;
+pub mod storage;
+pub mod temperature;
+pub mod time;
+pub mod touch;
+pub mod watchdog;
 
 /// Create a [Uart](hal::uart::Uart) client with the default pins
+///
+/// The speed can be a [Baudrate](serial::Baudrate) or a
+/// [HertzU32](time::HertzU32) (see [serial::IntoBaudrate]).
+#[cfg(feature = "v1")]
 #[macro_export]
 macro_rules! serial_port {
     ( $gpio:expr, $uart:expr, $speed:expr ) => {{
-        use microbit::hal::{gpio::Level, uart};
+        use microbit::{
+            hal::{gpio::Level, uart},
+            serial::IntoBaudrate,
+        };
 
         /* Configure RX and TX pins accordingly */
         let pins = uart::Pins {
@@ -28,6 +75,38 @@ macro_rules! serial_port {
         };
 
         /* Set up serial port using the prepared pins */
-        uart::Uart::new($uart, pins, uart::Parity::EXCLUDED, $speed)
+        uart::Uart::new($uart, pins, uart::Parity::EXCLUDED, $speed.into_baudrate())
+    }};
+}
+
+/// Create a [Uarte](hal::uarte::Uarte) client with the default pins
+///
+/// The TX pin is on port 0 and the RX pin on port 1. The speed can be a
+/// [Baudrate](serial::Baudrate) or a [HertzU32](time::HertzU32) (see
+/// [serial::IntoBaudrate]).
+#[cfg(feature = "v2")]
+#[macro_export]
+macro_rules! serial_port {
+    ( $p0parts:expr, $p1parts:expr, $uarte:expr, $speed:expr ) => {{
+        use microbit::{
+            hal::{gpio::Level, uarte},
+            serial::IntoBaudrate,
+        };
+
+        /* Configure RX and TX pins accordingly */
+        let pins = uarte::Pins {
+            rxd: $p1parts.p1_08.into_floating_input().degrade(),
+            txd: $p0parts.p0_06.into_push_pull_output(Level::Low).degrade(),
+            cts: None,
+            rts: None,
+        };
+
+        /* Set up serial port using the prepared pins */
+        uarte::Uarte::new(
+            $uarte,
+            pins,
+            uarte::Parity::EXCLUDED,
+            $speed.into_baudrate(),
+        )
     }};
 }
@@ -0,0 +1,267 @@
+//! A monotonic timer for RTIC v0.5.
+//!
+//! RTIC's own `CYCCNT` monotonic counts core clock cycles, which stop while
+//! the core sleeps, so [`MonoTimer`] counts microseconds on `TIMER0`
+//! instead. RTIC still times the `schedule` API with SysTick, converting
+//! with [`ratio()`](Monotonic::ratio).
+//!
+//! This module is only available with the `rtic` feature, on the v2: the
+//! nRF51's Cortex-M0 doesn't implement SysTick, so scheduled tasks would
+//! never run on the v1.
+//!
+//! # Example
+//!
+//! ```ignore
+//! #[rtic::app(device = microbit::pac, peripherals = true,
+//!             monotonic = microbit::monotonic::MonoTimer)]
+//! const APP: () = {
+//!     #[init(schedule = [tick])]
+//!     fn init(cx: init::Context) {
+//!         MonoTimer::new(cx.device.TIMER0);
+//!         cx.schedule.tick(cx.start + Duration::from_millis(500)).unwrap();
+//!     }
+//!
+//!     #[task(schedule = [tick])]
+//!     fn tick(cx: tick::Context) {
+//!         cx.schedule.tick(cx.scheduled + Duration::from_millis(500)).unwrap();
+//!     }
+//!
+//!     extern "C" {
+//!         fn SWI0_EGU0();
+//!     }
+//! };
+//! ```
+//!
+//! See a working example at `examples/rtic_monotonic.rs`.
+
+use core::{cmp::Ordering, fmt, ops};
+
+use rtic::{Fraction, Monotonic};
+
+use crate::{
+    pac::TIMER0,
+    time::{MicrosDurationU32, MillisDurationU32},
+};
+
+/// The `TIMER` prescaler giving 1MHz from the 16MHz base clock.
+const PRESCALER: u32 = 4;
+
+/// The core clock, which SysTick runs from, in MHz.
+const CORE_CLOCK_MHZ: u32 = 64;
+
+/// The `TIMER` capture register used to read the counter.
+const CAPTURE: usize = 3;
+
+/// A monotonic timer counting microseconds on `TIMER0`.
+///
+/// The counter wraps every 71 minutes, so [`Instant`]s can only be compared
+/// if they're less than 35 minutes apart.
+pub struct MonoTimer(TIMER0);
+
+impl MonoTimer {
+    /// Starts `TIMER0` counting microseconds.
+    ///
+    /// Call this from `#[init]`; RTIC resets the counter to zero when `init`
+    /// returns.
+    pub fn new(timer: TIMER0) -> Self {
+        timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.bits(PRESCALER) });
+        timer.shorts.reset();
+        timer.intenclr.write(|w| unsafe { w.bits(0xffff_ffff) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        MonoTimer(timer)
+    }
+
+    /// Stops the timer and gives the underlying `TIMER0` back.
+    pub fn free(self) -> TIMER0 {
+        self.0.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.0
+    }
+}
+
+impl Monotonic for MonoTimer {
+    type Instant = Instant;
+
+    fn ratio() -> Fraction {
+        // SysTick ticks per microsecond
+        Fraction {
+            numerator: CORE_CLOCK_MHZ,
+            denominator: 1,
+        }
+    }
+
+    fn now() -> Instant {
+        let timer = unsafe { &*TIMER0::ptr() };
+        timer.tasks_capture[CAPTURE].write(|w| unsafe { w.bits(1) });
+        Instant {
+            inner: timer.cc[CAPTURE].read().bits() as i32,
+        }
+    }
+
+    unsafe fn reset() {
+        (*TIMER0::ptr()).tasks_clear.write(|w| w.bits(1));
+    }
+
+    fn zero() -> Instant {
+        Instant { inner: 0 }
+    }
+}
+
+/// A reading of a [`MonoTimer`].
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct Instant {
+    inner: i32,
+}
+
+impl Instant {
+    /// Returns the current instant.
+    pub fn now() -> Self {
+        MonoTimer::now()
+    }
+
+    /// Returns the time elapsed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// Returns the time elapsed from another instant to this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `earlier` is later than this instant.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        let diff = self.inner.wrapping_sub(earlier.inner);
+        assert!(diff >= 0, "second instant is later than self");
+        Duration { inner: diff as u32 }
+    }
+}
+
+impl fmt::Debug for Instant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Instant")
+            .field(&(self.inner as u32))
+            .finish()
+    }
+}
+
+impl Ord for Instant {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        self.inner.wrapping_sub(rhs.inner).cmp(&0)
+    }
+}
+
+impl PartialOrd for Instant {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl ops::AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, dur: Duration) {
+        debug_assert!(dur.inner < (1 << 31));
+        self.inner = self.inner.wrapping_add(dur.inner as i32);
+    }
+}
+
+impl ops::Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(mut self, dur: Duration) -> Self {
+        self += dur;
+        self
+    }
+}
+
+impl ops::SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, dur: Duration) {
+        debug_assert!(dur.inner < (1 << 31));
+        self.inner = self.inner.wrapping_sub(dur.inner as i32);
+    }
+}
+
+impl ops::Sub<Duration> for Instant {
+    type Output = Self;
+
+    fn sub(mut self, dur: Duration) -> Self {
+        self -= dur;
+        self
+    }
+}
+
+impl ops::Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, other: Instant) -> Duration {
+        self.duration_since(other)
+    }
+}
+
+/// A span of time measured by a [`MonoTimer`], in microseconds.
+///
+/// Create one with [`from_micros()`](Duration::from_micros) or
+/// [`from_millis()`](Duration::from_millis), or convert from the crate's
+/// [`time`](crate::time) types with `into()`.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Duration {
+    inner: u32,
+}
+
+impl Duration {
+    /// Returns a `Duration` of `micros` microseconds.
+    pub const fn from_micros(micros: u32) -> Self {
+        Duration { inner: micros }
+    }
+
+    /// Returns a `Duration` of `millis` milliseconds.
+    pub const fn from_millis(millis: u32) -> Self {
+        Duration {
+            inner: millis * 1_000,
+        }
+    }
+
+    /// Returns the number of microseconds in the `Duration`.
+    pub const fn as_micros(&self) -> u32 {
+        self.inner
+    }
+}
+
+impl From<MicrosDurationU32> for Duration {
+    fn from(duration: MicrosDurationU32) -> Self {
+        Duration::from_micros(duration.ticks())
+    }
+}
+
+impl From<MillisDurationU32> for Duration {
+    fn from(duration: MillisDurationU32) -> Self {
+        Duration::from_micros(duration.to_micros())
+    }
+}
+
+impl From<Duration> for u32 {
+    fn from(duration: Duration) -> u32 {
+        duration.as_micros()
+    }
+}
+
+impl ops::Add for Duration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Duration {
+            inner: self.inner + other.inner,
+        }
+    }
+}
+
+impl ops::Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Duration {
+            inner: self.inner - other.inner,
+        }
+    }
+}
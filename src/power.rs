@@ -0,0 +1,225 @@
+//! Low-power modes and reset reasons.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - [`idle()`], which sleeps in System ON until an event or interrupt
+//! - [`system_off()`], the deepest sleep, which only a GPIO pin can wake
+//!   from, configured with [`wake_on()`] or [`wake_on_press()`]
+//! - a byte which is always kept ([`set_retained()`]), and on the v1 the RAM
+//!   blocks to keep in System OFF ([`set_ram_retention()`])
+//! - the [reason](ResetReason) for the last reset.
+//!
+//! # System OFF
+//!
+//! In System OFF, the micro:bit draws a few microamps, less the LED
+//! display, the accelerometer, the magnetometer and the interface chip.
+//! Waking up from it is a reset: the program starts again, and
+//! [`reset_reason()`] tells why. The RAM is lost, unless retained on the
+//! v1.
+//!
+//! A wake-up pin which is already at its level when entering System OFF
+//! wakes the micro:bit at once, so wait for a button to be released before
+//! calling [`system_off()`].
+//!
+//! ```no_run
+//! use microbit::{
+//!     board::Board,
+//!     button::{Button, Channels},
+//!     power,
+//! };
+//!
+//! let board = Board::take().unwrap();
+//! let channels = Channels::new(board.GPIOTE);
+//! let button_a = Button::new(board.buttons.button_a.degrade(), channels.channel0);
+//!
+//! if power::reset_reason(&board.POWER).gpio {
+//!     // woken up by button A
+//! }
+//!
+//! power::wake_on_press(&button_a);
+//! power::system_off(&board.POWER);
+//! ```
+//!
+//! See a working example at `examples/power_system_off.rs`
+
+#[cfg(feature = "v1")]
+use crate::pac::{gpio::PIN_CNF, GPIO as P0};
+use crate::{
+    button::Button,
+    hal::gpio::{Input, Level, Pin},
+    pac::POWER,
+};
+#[cfg(feature = "v2")]
+use crate::{
+    hal::gpio::Port,
+    pac::{p0::PIN_CNF, P0, P1},
+};
+
+/// Sleeps in System ON until an event or interrupt.
+///
+/// Events include interrupts which are pending but masked in the NVIC if
+/// `SEVONPEND` is set in the System Control Register. Only the clocks and
+/// peripherals in use keep running.
+pub fn idle() {
+    cortex_m::asm::wfe();
+}
+
+/// Sets whether the CPU wakes up from [`idle()`] with a constant latency,
+/// rather than in the low-power mode where the latency depends on which
+/// clocks are running.
+///
+/// The constant latency mode keeps some regulators and clocks running, so
+/// it draws more power.
+pub fn set_constant_latency(power: &POWER, enabled: bool) {
+    if enabled {
+        power.tasks_constlat.write(|w| unsafe { w.bits(1) });
+    } else {
+        power.tasks_lowpwr.write(|w| unsafe { w.bits(1) });
+    }
+}
+
+/// Enters System OFF, until a wake-up pin resets the micro:bit.
+///
+/// With a debugger attached, System OFF is only emulated, and this
+/// function keeps waiting.
+pub fn system_off(power: &POWER) -> ! {
+    power.systemoff.write(|w| w.systemoff().enter());
+    loop {
+        cortex_m::asm::wfe();
+    }
+}
+
+/// Makes `pin` wake the micro:bit from System OFF when it is at `level`.
+///
+/// The pin must stay an input, with any pull it needs to keep it at the
+/// other level.
+pub fn wake_on<MODE>(pin: &Pin<Input<MODE>>, level: Level) {
+    // Only the pin's SENSE field is changed
+    pin_cnf(pin).modify(|_, w| match level {
+        Level::Low => w.sense().low(),
+        Level::High => w.sense().high(),
+    });
+}
+
+/// Makes pressing `button` wake the micro:bit from System OFF.
+pub fn wake_on_press(button: &Button) {
+    wake_on(button.pin(), Level::Low);
+}
+
+/// Stops `pin` waking the micro:bit from System OFF.
+pub fn no_wake_on<MODE>(pin: &Pin<Input<MODE>>) {
+    pin_cnf(pin).modify(|_, w| w.sense().disabled());
+}
+
+/// Returns the configuration register of `pin`, in its port.
+fn pin_cnf<MODE>(pin: &Pin<Input<MODE>>) -> &'static PIN_CNF {
+    let gpio = unsafe { &*P0::ptr() };
+    #[cfg(feature = "v2")]
+    let gpio = match pin.port() {
+        Port::Port0 => gpio,
+        Port::Port1 => unsafe { &*P1::ptr() },
+    };
+    &gpio.pin_cnf[usize::from(pin.pin())]
+}
+
+/// Sets the RAM blocks kept in System OFF: bit `n` of `blocks` keeps
+/// block `n`.
+///
+/// The RAM is made of 8K blocks, from `0x2000_0000`: blocks 0 and 1, and
+/// blocks 2 and 3 on the nRF51822 with 32K of RAM. Each block kept draws
+/// about 1µA.
+#[cfg(feature = "v1")]
+pub fn set_ram_retention(power: &POWER, blocks: u8) {
+    let kept = |block: u8| blocks & 1 << block != 0;
+    power.ramon.modify(|_, w| {
+        w.offram0()
+            .bit(kept(0))
+            .offram1()
+            .bit(kept(1))
+            .onram0()
+            .ram0on()
+            .onram1()
+            .ram1on()
+    });
+    power.ramonb.modify(|_, w| {
+        w.offram2()
+            .bit(kept(2))
+            .offram3()
+            .bit(kept(3))
+            .onram2()
+            .ram2on()
+            .onram3()
+            .ram3on()
+    });
+}
+
+/// Sets a byte which is kept in System OFF and through resets, other than
+/// power-on, unlike the RAM.
+pub fn set_retained(power: &POWER, value: u8) {
+    power
+        .gpregret
+        .write(|w| unsafe { w.gpregret().bits(value) });
+}
+
+/// Returns the byte set by [`set_retained()`], which is 0 after power-on.
+pub fn retained(power: &POWER) -> u8 {
+    power.gpregret.read().gpregret().bits()
+}
+
+/// Why the micro:bit was last reset.
+///
+/// No reason is set after power-on or a brownout.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResetReason {
+    /// The reset button was pressed.
+    pub pin: bool,
+    /// The watchdog timed out.
+    pub watchdog: bool,
+    /// The program asked for a reset.
+    pub soft_reset: bool,
+    /// The CPU locked up.
+    pub lockup: bool,
+    /// A GPIO pin woke it up from System OFF.
+    pub gpio: bool,
+    /// The low-power comparator woke it up from System OFF.
+    pub lpcomp: bool,
+    /// A debugger woke it up from System OFF.
+    pub debug: bool,
+    /// An NFC field woke it up from System OFF.
+    #[cfg(feature = "v2")]
+    pub nfc: bool,
+    /// USB power woke it up from System OFF.
+    #[cfg(feature = "v2")]
+    pub vbus: bool,
+}
+
+impl ResetReason {
+    /// Returns whether the reset was a power-on or a brownout.
+    pub fn is_power_on(&self) -> bool {
+        *self == ResetReason::default()
+    }
+}
+
+/// Returns why the micro:bit was last reset, and clears the reasons.
+///
+/// The reasons add up until they are cleared, so call this once, early.
+pub fn reset_reason(power: &POWER) -> ResetReason {
+    let reasons = power.resetreas.read();
+    let reason = ResetReason {
+        pin: reasons.resetpin().is_detected(),
+        watchdog: reasons.dog().is_detected(),
+        soft_reset: reasons.sreq().is_detected(),
+        lockup: reasons.lockup().is_detected(),
+        gpio: reasons.off().is_detected(),
+        lpcomp: reasons.lpcomp().is_detected(),
+        debug: reasons.dif().is_detected(),
+        #[cfg(feature = "v2")]
+        nfc: reasons.nfc().is_detected(),
+        #[cfg(feature = "v2")]
+        vbus: reasons.vbus().is_detected(),
+    };
+    // Writing 1 clears a reason
+    power.resetreas.write(|w| unsafe { w.bits(reasons.bits()) });
+    reason
+}
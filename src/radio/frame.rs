@@ -0,0 +1,272 @@
+//! The micro:bit runtime's radio packet formats.
+//!
+//! Everything here is pure logic, independent of the `RADIO` peripheral.
+//!
+//! Packets are handled without the length byte which the radio adds, as
+//! returned by [`Radio::receive_bytes()`](super::Radio::receive_bytes).
+//!
+//! The micro:bit runtime sends datagrams with a 3-byte header: a version
+//! (always 1), the radio group and a protocol (1 for datagrams). MicroPython's
+//! `radio.send()` uses the same header with group 0:
+//!
+//! ```
+//! use microbit::radio::frame::Datagram;
+//!
+//! // Received after `radio.send("hi")` in MicroPython
+//! let packet = [0x01, 0x00, 0x01, b'h', b'i'];
+//! let datagram = Datagram::parse(&packet).unwrap();
+//! assert_eq!(datagram.group, 0);
+//! assert_eq!(datagram.payload, b"hi");
+//! ```
+//!
+//! MakeCode's `radio` blocks send datagrams whose payload is a
+//! [`MakeCodePacket`]:
+//!
+//! ```
+//! use microbit::radio::frame::{Datagram, MakeCodePacket, MakeCodeValue};
+//!
+//! // Received after `radio.sendNumber(42)` in MakeCode, in group 1, 1s after
+//! // starting
+//! let packet = [
+//!     0x01, 0x01, 0x01, // datagram header
+//!     0x00, // number packet
+//!     0xe8, 0x03, 0x00, 0x00, // time
+//!     0x00, 0x00, 0x00, 0x00, // serial number
+//!     0x2a, 0x00, 0x00, 0x00, // value
+//! ];
+//! let datagram = Datagram::parse(&packet).unwrap();
+//! let message = MakeCodePacket::parse(datagram.payload).unwrap();
+//! assert_eq!(message.time, 1000);
+//! assert_eq!(message.value, MakeCodeValue::Number(42));
+//! ```
+//!
+//! Names and strings are prefixed by their length, after the value for
+//! name-value packets, as laid out by the runtime's `radio.cpp`:
+//!
+//! ```
+//! use microbit::radio::frame::{Datagram, MakeCodePacket, MakeCodeValue};
+//!
+//! // After `radio.sendValue("temp", 21)` in group 1, 2.5s after starting,
+//! // then `radio.sendString("hello")` 3s after starting
+//! let value_packet = [
+//!     0x01, 0x01, 0x01, 0x01, 0xc4, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00,
+//!     0x00, 0x04, b't', b'e', b'm', b'p',
+//! ];
+//! let string_packet = [
+//!     0x01, 0x01, 0x01, 0x02, 0xb8, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, b'h', b'e',
+//!     b'l', b'l', b'o',
+//! ];
+//!
+//! let mut buf = [0; 32];
+//! for (packet, time, value) in [
+//!     (&value_packet[..], 2500, MakeCodeValue::Value("temp", 21)),
+//!     (&string_packet[..], 3000, MakeCodeValue::String("hello")),
+//! ] {
+//!     let datagram = Datagram::parse(packet).unwrap();
+//!     let message = MakeCodePacket::parse(datagram.payload).unwrap();
+//!     assert_eq!((message.time, message.serial, message.value), (time, 0, value));
+//!
+//!     let len = message.encode(&mut buf).unwrap();
+//!     assert_eq!(&buf[..len], datagram.payload);
+//! }
+//! ```
+
+use core::str;
+
+/// The version in the micro:bit runtime's datagram header.
+const VERSION: u8 = 1;
+
+/// The protocol in the micro:bit runtime's datagram header for datagrams.
+const PROTOCOL_DATAGRAM: u8 = 1;
+
+/// The length of the micro:bit runtime's datagram header.
+pub const DATAGRAM_HEADER_LEN: usize = 3;
+
+/// The longest payload the micro:bit runtime sends in a datagram.
+pub const MAX_DATAGRAM_PAYLOAD: usize = 32;
+
+/// The longest name in a MakeCode name-value packet.
+pub const MAX_NAME_LEN: usize = 8;
+
+/// The longest string or buffer in a MakeCode packet.
+pub const MAX_STRING_LEN: usize = 19;
+
+/// The length of a MakeCode packet's type, time and serial number.
+const MAKECODE_HEADER_LEN: usize = 9;
+
+/// The error returned when encoding a packet which doesn't fit in the
+/// buffer or exceeds the format's limits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PacketTooLong;
+
+/// A datagram in the micro:bit runtime's format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Datagram<'a> {
+    /// The radio group of the sender.
+    pub group: u8,
+    /// The datagram's payload.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Datagram<'a> {
+    /// Parses a received packet, returning `None` if it doesn't have a
+    /// datagram header.
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+        match packet {
+            [VERSION, group, PROTOCOL_DATAGRAM, payload @ ..] => Some(Datagram {
+                group: *group,
+                payload,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Encodes the datagram into `buf`, returning its length.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, PacketTooLong> {
+        let len = DATAGRAM_HEADER_LEN + self.payload.len();
+        if self.payload.len() > MAX_DATAGRAM_PAYLOAD || len > buf.len() {
+            return Err(PacketTooLong);
+        }
+        buf[..DATAGRAM_HEADER_LEN].copy_from_slice(&[VERSION, self.group, PROTOCOL_DATAGRAM]);
+        buf[DATAGRAM_HEADER_LEN..len].copy_from_slice(self.payload);
+        Ok(len)
+    }
+}
+
+/// The value carried by a [`MakeCodePacket`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MakeCodeValue<'a> {
+    /// An integer, from `radio.sendNumber()`.
+    Number(i32),
+    /// A name and integer, from `radio.sendValue()`.
+    Value(&'a str, i32),
+    /// A string, from `radio.sendString()`.
+    String(&'a str),
+    /// Bytes, from `radio.sendBuffer()`.
+    Buffer(&'a [u8]),
+    /// A non-integer number, from `radio.sendNumber()`.
+    Double(f64),
+    /// A name and non-integer number, from `radio.sendValue()`.
+    DoubleValue(&'a str, f64),
+}
+
+impl MakeCodeValue<'_> {
+    fn packet_type(&self) -> u8 {
+        match self {
+            MakeCodeValue::Number(_) => 0,
+            MakeCodeValue::Value(..) => 1,
+            MakeCodeValue::String(_) => 2,
+            MakeCodeValue::Buffer(_) => 3,
+            MakeCodeValue::Double(_) => 4,
+            MakeCodeValue::DoubleValue(..) => 5,
+        }
+    }
+}
+
+/// A packet sent by MakeCode's `radio` blocks, in the payload of a
+/// [`Datagram`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MakeCodePacket<'a> {
+    /// The sender's running time, in milliseconds.
+    pub time: u32,
+    /// The sender's serial number, or 0 if it isn't sent.
+    pub serial: u32,
+    /// The value sent.
+    pub value: MakeCodeValue<'a>,
+}
+
+impl<'a> MakeCodePacket<'a> {
+    /// Parses a datagram payload, returning `None` if it isn't a valid
+    /// MakeCode packet.
+    pub fn parse(payload: &'a [u8]) -> Option<Self> {
+        if payload.len() < MAKECODE_HEADER_LEN {
+            return None;
+        }
+        let u32_at = |i: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(payload.get(i..i + 4)?);
+            Some(u32::from_le_bytes(bytes))
+        };
+        let f64_at = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(payload.get(i..i + 8)?);
+            Some(f64::from_le_bytes(bytes))
+        };
+        // A length-prefixed string or buffer
+        let bytes_at = |i: usize| {
+            let len = usize::from(*payload.get(i)?);
+            payload.get(i + 1..i + 1 + len)
+        };
+        let str_at = |i: usize| str::from_utf8(bytes_at(i)?).ok();
+        let data = MAKECODE_HEADER_LEN;
+        let value = match payload[0] {
+            0 => MakeCodeValue::Number(u32_at(data)? as i32),
+            1 => MakeCodeValue::Value(str_at(data + 4)?, u32_at(data)? as i32),
+            2 => MakeCodeValue::String(str_at(data)?),
+            3 => MakeCodeValue::Buffer(bytes_at(data)?),
+            4 => MakeCodeValue::Double(f64_at(data)?),
+            5 => MakeCodeValue::DoubleValue(str_at(data + 8)?, f64_at(data)?),
+            _ => return None,
+        };
+        Some(MakeCodePacket {
+            time: u32_at(1)?,
+            serial: u32_at(5)?,
+            value,
+        })
+    }
+
+    /// Encodes the packet into `buf`, returning its length.
+    ///
+    /// Returns an error if a name is longer than [`MAX_NAME_LEN`], a string
+    /// or buffer is longer than [`MAX_STRING_LEN`], or `buf` is too short.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, PacketTooLong> {
+        let mut writer = Writer { buf, len: 0 };
+        writer.put(&[self.value.packet_type()])?;
+        writer.put(&self.time.to_le_bytes())?;
+        writer.put(&self.serial.to_le_bytes())?;
+        match self.value {
+            MakeCodeValue::Number(number) => writer.put(&number.to_le_bytes())?,
+            MakeCodeValue::Value(name, number) => {
+                writer.put(&number.to_le_bytes())?;
+                writer.put_prefixed(name.as_bytes(), MAX_NAME_LEN)?;
+            }
+            MakeCodeValue::String(string) => {
+                writer.put_prefixed(string.as_bytes(), MAX_STRING_LEN)?
+            }
+            MakeCodeValue::Buffer(bytes) => writer.put_prefixed(bytes, MAX_STRING_LEN)?,
+            MakeCodeValue::Double(number) => writer.put(&number.to_le_bytes())?,
+            MakeCodeValue::DoubleValue(name, number) => {
+                writer.put(&number.to_le_bytes())?;
+                writer.put_prefixed(name.as_bytes(), MAX_NAME_LEN)?;
+            }
+        }
+        Ok(writer.len)
+    }
+}
+
+/// Appends bytes to a buffer.
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) -> Result<(), PacketTooLong> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(PacketTooLong)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Appends bytes prefixed by their length, which must be at most `max`.
+    fn put_prefixed(&mut self, bytes: &[u8], max: usize) -> Result<(), PacketTooLong> {
+        if bytes.len() > max {
+            return Err(PacketTooLong);
+        }
+        self.put(&[bytes.len() as u8])?;
+        self.put(bytes)
+    }
+}
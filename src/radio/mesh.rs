@@ -0,0 +1,287 @@
+//! Flood messaging, relaying messages beyond the range of a single radio.
+//!
+//! Everything here is pure logic, independent of the `RADIO` peripheral: a
+//! [`Router`] turns messages into packets to send, and tells what to do with
+//! each packet received. Each node relays every new message it hears once,
+//! until its time to live (TTL) runs out, so a message can reach nodes many
+//! hops away. Messages are identified by their source and a message ID, so
+//! that each node ignores those it has seen before.
+//!
+//! Messages are addressed to a group: all nodes relay them, but only the
+//! members of the group receive them. All nodes are members of
+//! [`BROADCAST`].
+//!
+//! All the nodes of a mesh must use the same radio settings, including the
+//! radio [group](super::Config::group), which is unrelated to the mesh
+//! groups.
+//!
+//! # Simulation
+//!
+//! Here six nodes stand in a line, each one only in range of its neighbours,
+//! and the first sends a message to a group which only the last has joined:
+//!
+//! ```
+//! use microbit::radio::mesh::{Router, MAX_PACKET_LEN};
+//!
+//! const NODES: usize = 6;
+//! let mut routers = [Router::new(1, 0), Router::new(2, 0), Router::new(3, 0),
+//!                    Router::new(4, 0), Router::new(5, 0), Router::new(6, 0)];
+//! routers[NODES - 1].join(7);
+//!
+//! // The packets in the air in each round, with the node which sent them
+//! let mut air: Vec<(usize, Vec<u8>)> = Vec::new();
+//! let mut packet = [0; MAX_PACKET_LEN];
+//! routers[0].set_ttl(NODES as u8 - 1);
+//! let len = routers[0].send(7, b"hello", &mut packet).unwrap();
+//! air.push((0, packet[..len].to_vec()));
+//!
+//! let mut sent = 1;
+//! let mut received = Vec::new();
+//! while !air.is_empty() {
+//!     let mut next = Vec::new();
+//!     for &(from, ref packet) in &air {
+//!         // Only the neighbours hear the packet
+//!         for to in (from.saturating_sub(1)..=from + 1).filter(|&to| to != from && to < NODES) {
+//!             let mut relay = [0; MAX_PACKET_LEN];
+//!             let result = routers[to].receive(packet, &mut relay);
+//!             if let Some(message) = result.message {
+//!                 received.push((to, message.source, message.payload.to_vec()));
+//!             }
+//!             if let Some(len) = result.relay {
+//!                 next.push((to, relay[..len].to_vec()));
+//!             }
+//!         }
+//!     }
+//!     sent += next.len();
+//!     air = next;
+//! }
+//!
+//! // Only the last node received the message, once, from the first
+//! assert_eq!(received, vec![(NODES - 1, 1, b"hello".to_vec())]);
+//! // Each node other than the last sent the message once
+//! assert_eq!(sent, NODES - 1);
+//! ```
+//!
+//! See a working example at `examples/radio_mesh.rs`
+
+use super::frame::PacketTooLong;
+
+/// The first byte of a mesh packet, telling it apart from other packets,
+/// such as the micro:bit runtime's [datagrams](super::frame::Datagram).
+const MARKER: u8 = b'm';
+
+/// The length of the header of a mesh packet.
+pub const HEADER_LEN: usize = 7;
+
+/// The longest mesh packet, which fits in the radio's default
+/// [`length`](super::Config::length).
+pub const MAX_PACKET_LEN: usize = 32;
+
+/// The longest message payload.
+pub const MAX_PAYLOAD_LEN: usize = MAX_PACKET_LEN - HEADER_LEN;
+
+/// The group of which all nodes are members.
+pub const BROADCAST: u8 = 0xff;
+
+/// The TTL of new messages, unless set with [`Router::set_ttl()`].
+pub const DEFAULT_TTL: u8 = 4;
+
+/// The number of recent messages remembered to suppress duplicates.
+const SEEN_LEN: usize = 32;
+
+/// A mesh message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// The address of the node which sent the message.
+    pub source: u16,
+    /// The ID of the message, unique among the recent messages from its
+    /// source.
+    pub id: u16,
+    /// The number of times the message may still be sent: 1 means that it
+    /// won't be relayed again.
+    pub ttl: u8,
+    /// The group the message is addressed to.
+    pub group: u8,
+    /// The message's payload.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    /// Parses a received packet, returning `None` if it isn't a mesh
+    /// packet.
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+        match packet {
+            [MARKER, ttl, group, source_low, source_high, id_low, id_high, payload @ ..]
+                if *ttl > 0 && payload.len() <= MAX_PAYLOAD_LEN =>
+            {
+                Some(Message {
+                    source: u16::from_le_bytes([*source_low, *source_high]),
+                    id: u16::from_le_bytes([*id_low, *id_high]),
+                    ttl: *ttl,
+                    group: *group,
+                    payload,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the message into `buf`, returning its length.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, PacketTooLong> {
+        let len = HEADER_LEN + self.payload.len();
+        if self.payload.len() > MAX_PAYLOAD_LEN || len > buf.len() {
+            return Err(PacketTooLong);
+        }
+        buf[..3].copy_from_slice(&[MARKER, self.ttl, self.group]);
+        buf[3..5].copy_from_slice(&self.source.to_le_bytes());
+        buf[5..7].copy_from_slice(&self.id.to_le_bytes());
+        buf[HEADER_LEN..len].copy_from_slice(self.payload);
+        Ok(len)
+    }
+}
+
+/// What to do with a received packet, returned by [`Router::receive()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Received<'a> {
+    /// The message, if it is new and addressed to a group this node is a
+    /// member of.
+    pub message: Option<Message<'a>>,
+    /// The length of the packet to send to relay the message, if it should
+    /// be relayed.
+    pub relay: Option<usize>,
+}
+
+/// The routing state of a node in a mesh.
+#[derive(Clone, Debug)]
+pub struct Router {
+    address: u16,
+    ttl: u8,
+    next_id: u16,
+    /// A bit for each group this node is a member of.
+    groups: [u32; 8],
+    /// The source and ID of recent messages, oldest overwritten first.
+    seen: [(u16, u16); SEEN_LEN],
+    seen_len: usize,
+    seen_next: usize,
+}
+
+impl Router {
+    /// Returns the router of the node with this address, which should be
+    /// unique in the mesh.
+    ///
+    /// The low bits of the nRF51's factory-programmed `DEVICEID` make a
+    /// good address.
+    ///
+    /// `first_id` is the ID of the first message sent. The other nodes
+    /// remember the IDs of recent messages across this node's resets, so
+    /// take it from the `RNG` (see [`Rng`](crate::hal::rng::Rng)): starting
+    /// from the same ID at each boot would get the first messages dropped as
+    /// duplicates.
+    pub fn new(address: u16, first_id: u16) -> Self {
+        Router {
+            address,
+            ttl: DEFAULT_TTL,
+            next_id: first_id,
+            groups: [0; 8],
+            seen: [(0, 0); SEEN_LEN],
+            seen_len: 0,
+            seen_next: 0,
+        }
+    }
+
+    /// Returns the node's address.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Sets the TTL of new messages: the number of hops they can travel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ttl` is 0.
+    pub fn set_ttl(&mut self, ttl: u8) {
+        assert!(ttl > 0, "invalid TTL");
+        self.ttl = ttl;
+    }
+
+    /// Makes the node a member of a group.
+    pub fn join(&mut self, group: u8) {
+        self.groups[usize::from(group / 32)] |= 1 << (group % 32);
+    }
+
+    /// Removes the node from a group. The node stays a member of
+    /// [`BROADCAST`].
+    pub fn leave(&mut self, group: u8) {
+        self.groups[usize::from(group / 32)] &= !(1 << (group % 32));
+    }
+
+    /// Returns whether the node is a member of a group.
+    pub fn is_member(&self, group: u8) -> bool {
+        group == BROADCAST || self.groups[usize::from(group / 32)] & 1 << (group % 32) != 0
+    }
+
+    /// Encodes a new message into `buf`, returning the length of the packet
+    /// to send.
+    pub fn send(
+        &mut self,
+        group: u8,
+        payload: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, PacketTooLong> {
+        let message = Message {
+            source: self.address,
+            id: self.next_id,
+            ttl: self.ttl,
+            group,
+            payload,
+        };
+        let len = message.encode(buf)?;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.remember(self.address, message.id);
+        Ok(len)
+    }
+
+    /// Handles a received packet, writing the packet to send to relay it
+    /// into `relay`.
+    ///
+    /// Packets which aren't mesh packets, and messages seen before, are
+    /// ignored.
+    pub fn receive<'a>(&mut self, packet: &'a [u8], relay: &mut [u8]) -> Received<'a> {
+        let ignored = Received {
+            message: None,
+            relay: None,
+        };
+        let message = match Message::parse(packet) {
+            Some(message) => message,
+            None => return ignored,
+        };
+        if message.source == self.address || self.has_seen(message.source, message.id) {
+            return ignored;
+        }
+        self.remember(message.source, message.id);
+        let relay = if message.ttl > 1 {
+            Message {
+                ttl: message.ttl - 1,
+                ..message
+            }
+            .encode(relay)
+            .ok()
+        } else {
+            None
+        };
+        Received {
+            message: Some(message).filter(|message| self.is_member(message.group)),
+            relay,
+        }
+    }
+
+    fn has_seen(&self, source: u16, id: u16) -> bool {
+        self.seen[..self.seen_len].contains(&(source, id))
+    }
+
+    fn remember(&mut self, source: u16, id: u16) {
+        self.seen[self.seen_next] = (source, id);
+        self.seen_next = (self.seen_next + 1) % SEEN_LEN;
+        self.seen_len = (self.seen_len + 1).min(SEEN_LEN);
+    }
+}
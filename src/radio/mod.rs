@@ -0,0 +1,480 @@
+//! Simple packet radio, compatible with MicroPython's `radio` module and the
+//! micro:bit runtime used by MakeCode.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - a [`Radio`], which sends and receives packets on the `RADIO`
+//!   peripheral in Nordic's proprietary mode, with the same on-air format
+//!   and [`Config`] settings as MicroPython
+//! - the micro:bit runtime's [packet formats](frame)
+//! - [flood messaging](mesh), relaying messages from node to node.
+//!
+//! The packet formats and the flood messaging are pure logic.
+//!
+//! Received packets are queued by an interrupt handler, which calls
+//! [`handle_radio_interrupt()`]; if the queue is full, new packets are
+//! dropped.
+//!
+//! The HFCLK crystal oscillator must be running (see
+//! [`start_hfclk()`](crate::clock::start_hfclk)), as the internal
+//! oscillator isn't accurate enough for the radio.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{clock, radio::{Config, Radio}};
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! clock::start_hfclk(&p.CLOCK);
+//!
+//! let mut radio = Radio::new(p.RADIO, Config { group: 1, ..Config::default() });
+//! unsafe { microbit::pac::NVIC::unmask(microbit::pac::Interrupt::RADIO) };
+//!
+//! radio.send_bytes(b"hello");
+//! let mut buf = [0; 32];
+//! if let Some(len) = radio.receive_bytes(&mut buf) {
+//!     // ...
+//! }
+//!
+//! // in the RADIO interrupt handler
+//! microbit::radio::handle_radio_interrupt();
+//! ```
+//!
+//! See a working example at `examples/radio_send_receive.rs`
+
+pub mod frame;
+pub mod mesh;
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::{CriticalSection, Mutex};
+
+use crate::pac::RADIO;
+
+/// The longest packet which can be sent or received.
+pub const MAX_LENGTH: usize = 251;
+
+/// The longest receive queue.
+pub const MAX_QUEUE_LEN: usize = 8;
+
+/// The length of a packet buffer: the length byte and the longest payload.
+const BUFFER_LEN: usize = 1 + MAX_LENGTH;
+
+/// The highest channel, for 2483MHz.
+const MAX_CHANNEL: u8 = 83;
+
+/// The highest power level.
+const MAX_POWER: u8 = 7;
+
+/// The radio's data rate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DataRate {
+    /// 250kbit/s (not supported by all nRF51 revisions, nor by the v2's
+    /// nRF52833).
+    #[cfg(feature = "v1")]
+    Rate250Kbit,
+    /// 1Mbit/s.
+    Rate1Mbit,
+    /// 2Mbit/s.
+    Rate2Mbit,
+}
+
+/// The radio's settings, with the same meaning and defaults as MicroPython's
+/// `radio.config()`.
+///
+/// Radios only receive each other's packets if their channel, address,
+/// group and data rate are the same.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// The channel, from 0 to 83: the frequency is 2400MHz plus the channel
+    /// number in MHz. Defaults to 7.
+    pub channel: u8,
+    /// The 32-bit address. Defaults to `0x75626974` ("ubit").
+    pub address: u32,
+    /// The group, added to the address. Defaults to 0.
+    pub group: u8,
+    /// The transmit power level, from 0 (-30dBm) to 7 (+4dBm). Defaults to 6
+    /// (0dBm).
+    pub power: u8,
+    /// The data rate. Defaults to 1Mbit/s.
+    pub data_rate: DataRate,
+    /// The longest packet sent or received, up to [`MAX_LENGTH`]. Defaults
+    /// to 32.
+    pub length: u8,
+    /// The number of received packets which can be queued, up to
+    /// [`MAX_QUEUE_LEN`]. Defaults to 3.
+    pub queue: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            channel: 7,
+            address: 0x7562_6974,
+            group: 0,
+            power: 6,
+            data_rate: DataRate::Rate1Mbit,
+            length: 32,
+            queue: 3,
+        }
+    }
+}
+
+/// A received packet.
+#[derive(Copy, Clone)]
+pub struct Packet {
+    buf: [u8; MAX_LENGTH],
+    len: u8,
+    rssi: i8,
+}
+
+impl Packet {
+    const fn empty() -> Self {
+        Packet {
+            buf: [0; MAX_LENGTH],
+            len: 0,
+            rssi: 0,
+        }
+    }
+
+    /// Returns the packet's data.
+    pub fn data(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+
+    /// Returns the received signal strength, in dBm.
+    pub fn rssi(&self) -> i8 {
+        self.rssi
+    }
+}
+
+/// The radio state shared with the interrupt handler.
+///
+/// The packet buffers are read and written by DMA, so this is only used
+/// once it's in `STATE`, where it doesn't move.
+struct State {
+    radio: RADIO,
+    config: Config,
+    rx: [u8; BUFFER_LEN],
+    tx: [u8; BUFFER_LEN],
+    queue: [Packet; MAX_QUEUE_LEN],
+    head: usize,
+    len: usize,
+    on: bool,
+    sending: bool,
+}
+
+static STATE: Mutex<RefCell<Option<State>>> = Mutex::new(RefCell::new(None));
+
+/// The packet radio, using the `RADIO` peripheral.
+///
+/// There can only be one `Radio`, as it owns the `RADIO` peripheral, which
+/// it keeps in a `static` shared with [`handle_radio_interrupt()`].
+pub struct Radio {
+    _private: (),
+}
+
+impl Radio {
+    /// Configures the radio and starts receiving.
+    ///
+    /// Takes ownership of the `RADIO` peripheral. The `RADIO` interrupt
+    /// must be unmasked in the NVIC with [`handle_radio_interrupt()`] called
+    /// from its handler.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a setting in `config` is out of range.
+    pub fn new(radio: RADIO, config: Config) -> Self {
+        validate(&config);
+        cortex_m::interrupt::free(|cs| {
+            *STATE.borrow(cs).borrow_mut() = Some(State {
+                radio,
+                config,
+                rx: [0; BUFFER_LEN],
+                tx: [0; BUFFER_LEN],
+                queue: [Packet::empty(); MAX_QUEUE_LEN],
+                head: 0,
+                len: 0,
+                on: false,
+                sending: false,
+            });
+            with_state(cs, |state| state.set_on(true))
+        });
+        Radio { _private: () }
+    }
+
+    /// Turns the radio off and gives the `RADIO` peripheral back.
+    pub fn free(self) -> RADIO {
+        cortex_m::interrupt::free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut().take().unwrap();
+            state.set_on(false);
+            state.radio
+        })
+    }
+
+    /// Turns the radio on, so it receives packets.
+    pub fn on(&mut self) {
+        cortex_m::interrupt::free(|cs| with_state(cs, |state| state.set_on(true)));
+    }
+
+    /// Turns the radio off to save power. Packets can't be sent or received
+    /// until it's turned on again.
+    pub fn off(&mut self) {
+        cortex_m::interrupt::free(|cs| with_state(cs, |state| state.set_on(false)));
+    }
+
+    /// Returns the current settings.
+    pub fn config(&self) -> Config {
+        cortex_m::interrupt::free(|cs| with_state(cs, |state| state.config))
+    }
+
+    /// Changes the settings, clearing the receive queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a setting in `config` is out of range.
+    pub fn set_config(&mut self, config: Config) {
+        validate(&config);
+        cortex_m::interrupt::free(|cs| {
+            with_state(cs, |state| {
+                state.config = config;
+                state.len = 0;
+                if state.on {
+                    state.set_on(true);
+                }
+            })
+        });
+    }
+
+    /// Sends a packet, blocking until it has been sent.
+    ///
+    /// Does nothing if the radio is off. Interrupts are handled while the
+    /// packet is sent, which takes up to about 8ms at 250kbit/s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` is longer than the configured
+    /// [`length`](Config::length).
+    pub fn send_bytes(&mut self, data: &[u8]) {
+        cortex_m::interrupt::free(|cs| with_state(cs, |state| state.send(data)));
+        // Either this or the interrupt handler sees the END event first
+        while !cortex_m::interrupt::free(|cs| with_state(cs, |state| state.finish_send())) {}
+    }
+
+    /// Takes the oldest packet from the receive queue.
+    pub fn receive(&mut self) -> Option<Packet> {
+        cortex_m::interrupt::free(|cs| with_state(cs, |state| state.pop()))
+    }
+
+    /// Takes the oldest packet from the receive queue, copying its data into
+    /// `buf`.
+    ///
+    /// Returns the length of the packet, which is truncated if `buf` is too
+    /// short.
+    pub fn receive_bytes(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let packet = self.receive()?;
+        let len = packet.data().len().min(buf.len());
+        buf[..len].copy_from_slice(&packet.data()[..len]);
+        Some(packet.data().len())
+    }
+}
+
+/// Queues a received packet and carries on receiving.
+///
+/// Call this in the interrupt handler for `RADIO`.
+pub fn handle_radio_interrupt() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(state) = STATE.borrow(cs).borrow_mut().as_mut() {
+            state.handle_end();
+        }
+    });
+}
+
+fn with_state<R>(cs: &CriticalSection, f: impl FnOnce(&mut State) -> R) -> R {
+    f(STATE
+        .borrow(cs)
+        .borrow_mut()
+        .as_mut()
+        .expect("radio not initialised"))
+}
+
+fn validate(config: &Config) {
+    assert!(config.channel <= MAX_CHANNEL, "invalid channel");
+    assert!(config.power <= MAX_POWER, "invalid power");
+    assert!(
+        (1..=MAX_LENGTH).contains(&usize::from(config.length)),
+        "invalid length"
+    );
+    assert!(
+        (1..=MAX_QUEUE_LEN).contains(&usize::from(config.queue)),
+        "invalid queue length"
+    );
+}
+
+impl State {
+    /// Programs the radio with the settings.
+    fn configure(&mut self) {
+        let config = self.config;
+        let radio = &self.radio;
+        radio.mode.write(|w| {
+            let w = w.mode();
+            match config.data_rate {
+                #[cfg(feature = "v1")]
+                DataRate::Rate250Kbit => w.nrf_250kbit(),
+                DataRate::Rate1Mbit => w.nrf_1mbit(),
+                DataRate::Rate2Mbit => w.nrf_2mbit(),
+            }
+        });
+        radio.txpower.write(|w| {
+            let w = w.txpower();
+            match config.power {
+                0 => w.neg30d_bm(),
+                1 => w.neg20d_bm(),
+                2 => w.neg16d_bm(),
+                3 => w.neg12d_bm(),
+                4 => w.neg8d_bm(),
+                5 => w.neg4d_bm(),
+                6 => w._0d_bm(),
+                _ => w.pos4d_bm(),
+            }
+        });
+        unsafe {
+            radio.frequency.write(|w| w.bits(config.channel.into()));
+            // An 8-bit length field before the payload
+            radio
+                .pcnf0
+                .write(|w| w.lflen().bits(8).s0len().clear_bit().s1len().bits(0));
+            // As the micro:bit runtime's `PCNF1 = 0x02040000 | length`: a
+            // 4-byte base address, little-endian and whitened
+            radio.pcnf1.write(|w| {
+                w.maxlen()
+                    .bits(config.length)
+                    .statlen()
+                    .bits(0)
+                    .balen()
+                    .bits(4)
+                    .endian()
+                    .little()
+                    .whiteen()
+                    .enabled()
+            });
+            radio.datawhiteiv.write(|w| w.bits(0x18));
+            radio.base0.write(|w| w.bits(config.address));
+            radio.prefix0.write(|w| w.bits(config.group.into()));
+            radio.txaddress.write(|w| w.bits(0));
+            radio.rxaddresses.write(|w| w.bits(1));
+            // A 16-bit CCITT CRC, including the address
+            radio.crccnf.write(|w| w.len().two().skipaddr().include());
+            radio.crcinit.write(|w| w.bits(0xffff));
+            radio.crcpoly.write(|w| w.bits(0x1_1021));
+        }
+        radio.intenset.write(|w| w.end().set());
+    }
+
+    fn set_on(&mut self, on: bool) {
+        if on {
+            // Powering the radio on resets its registers
+            self.radio.power.write(|w| w.power().enabled());
+            self.disable();
+            self.configure();
+            self.start_rx();
+        } else {
+            self.disable();
+            self.radio.power.write(|w| w.power().disabled());
+        }
+        self.on = on;
+    }
+
+    fn disable(&mut self) {
+        let radio = &self.radio;
+        radio.shorts.reset();
+        if radio.state.read().bits() != 0 {
+            radio.events_disabled.reset();
+            radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+            while radio.events_disabled.read().bits() == 0 {}
+        }
+        radio.events_end.reset();
+        radio.events_disabled.reset();
+        self.sending = false;
+    }
+
+    fn start_rx(&mut self) {
+        let radio = &self.radio;
+        radio
+            .packetptr
+            .write(|w| unsafe { w.bits(self.rx.as_ptr() as u32) });
+        radio
+            .shorts
+            .write(|w| w.ready_start().enabled().address_rssistart().enabled());
+        radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn send(&mut self, data: &[u8]) {
+        assert!(
+            data.len() <= usize::from(self.config.length),
+            "packet too long"
+        );
+        if !self.on {
+            return;
+        }
+        self.disable();
+        self.tx[0] = data.len() as u8;
+        self.tx[1..1 + data.len()].copy_from_slice(data);
+        let radio = &self.radio;
+        radio
+            .packetptr
+            .write(|w| unsafe { w.bits(self.tx.as_ptr() as u32) });
+        radio.shorts.write(|w| w.ready_start().enabled());
+        radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        self.sending = true;
+    }
+
+    /// Goes back to receiving once the packet being sent has been sent,
+    /// returning whether there's none left.
+    fn finish_send(&mut self) -> bool {
+        if !self.sending {
+            return true;
+        }
+        if self.radio.events_end.read().bits() == 0 {
+            return false;
+        }
+        self.disable();
+        self.start_rx();
+        true
+    }
+
+    fn handle_end(&mut self) {
+        if self.sending {
+            self.finish_send();
+            return;
+        }
+        let radio = &self.radio;
+        if radio.events_end.read().bits() == 0 {
+            return;
+        }
+        radio.events_end.reset();
+        let len = usize::from(self.rx[0]);
+        if radio.crcstatus.read().crcstatus().is_crcok()
+            && len <= usize::from(self.config.length)
+            && self.len < usize::from(self.config.queue)
+        {
+            let packet = &mut self.queue[(self.head + self.len) % MAX_QUEUE_LEN];
+            packet.buf[..len].copy_from_slice(&self.rx[1..1 + len]);
+            packet.len = len as u8;
+            packet.rssi = -(radio.rssisample.read().rssisample().bits() as i8);
+            self.len += 1;
+        }
+        radio.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn pop(&mut self) -> Option<Packet> {
+        if self.len == 0 {
+            return None;
+        }
+        let packet = self.queue[self.head];
+        self.head = (self.head + 1) % MAX_QUEUE_LEN;
+        self.len -= 1;
+        Some(packet)
+    }
+}
@@ -0,0 +1,267 @@
+//! Serial port support.
+//!
+//! The [`serial_port!`](crate::serial_port) macro creates a `Uart` (a
+//! `Uarte` on the v2) on the pins connected to the interface chip. Its speed
+//! can be given either as a [`Baudrate`] or as a [`HertzU32`]:
+//!
+//! ```no_run
+//! # #[cfg(feature = "v1")]
+//! # {
+//! use microbit::{hal::gpio::p0::Parts, time::RateExtU32};
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let gpio = Parts::new(p.GPIO);
+//! let serial = microbit::serial_port!(gpio, p.UART0, 115_200.Hz());
+//! # }
+//! ```
+//!
+//! On the v2, the RX pin is on port 1, so the macro takes both ports:
+//!
+//! ```no_run
+//! # #[cfg(feature = "v2")]
+//! # {
+//! use microbit::{
+//!     hal::gpio::{p0, p1},
+//!     time::RateExtU32,
+//! };
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let (p0, p1) = (p0::Parts::new(p.P0), p1::Parts::new(p.P1));
+//! let serial = microbit::serial_port!(p0, p1, p.UARTE0, 115_200.Hz());
+//! # }
+//! ```
+//!
+//! On the v1, [`AsyncSerial`] wraps the `Uart` to provide async reads and
+//! writes, for use with the [`executor`](crate::executor).
+
+#[cfg(feature = "v1")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::time::HertzU32;
+#[cfg(feature = "v1")]
+use crate::{executor::WakerCell, hal::uart::Uart, pac::UART0};
+
+#[cfg(feature = "v1")]
+pub use crate::hal::uart::Baudrate;
+#[cfg(feature = "v2")]
+pub use crate::hal::uarte::Baudrate;
+
+/// Conversion into a [`Baudrate`] supported by the `UART`.
+pub trait IntoBaudrate {
+    /// Returns the equivalent `Baudrate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `UART` doesn't support the rate.
+    fn into_baudrate(self) -> Baudrate;
+}
+
+impl IntoBaudrate for Baudrate {
+    fn into_baudrate(self) -> Baudrate {
+        self
+    }
+}
+
+impl IntoBaudrate for HertzU32 {
+    fn into_baudrate(self) -> Baudrate {
+        baudrate(self).expect("unsupported baud rate")
+    }
+}
+
+/// Returns the [`Baudrate`] for a rate, or `None` if the `UART` doesn't
+/// support it.
+pub fn baudrate(rate: HertzU32) -> Option<Baudrate> {
+    Some(match rate.raw() {
+        1_200 => Baudrate::BAUD1200,
+        2_400 => Baudrate::BAUD2400,
+        4_800 => Baudrate::BAUD4800,
+        9_600 => Baudrate::BAUD9600,
+        14_400 => Baudrate::BAUD14400,
+        19_200 => Baudrate::BAUD19200,
+        28_800 => Baudrate::BAUD28800,
+        31_250 => Baudrate::BAUD31250,
+        38_400 => Baudrate::BAUD38400,
+        56_000 => Baudrate::BAUD56000,
+        57_600 => Baudrate::BAUD57600,
+        76_800 => Baudrate::BAUD76800,
+        115_200 => Baudrate::BAUD115200,
+        230_400 => Baudrate::BAUD230400,
+        250_000 => Baudrate::BAUD250000,
+        460_800 => Baudrate::BAUD460800,
+        921_600 => Baudrate::BAUD921600,
+        1_000_000 => Baudrate::BAUD1M,
+        _ => return None,
+    })
+}
+
+/// An async serial port on `UART0`.
+///
+/// Only available on the v1: the v2's serial port is a `UARTE`, which
+/// reads and writes whole buffers by DMA.
+///
+/// The `UART0` interrupt must be unmasked in the NVIC, with
+/// [`handle_uart_interrupt()`] called from its handler.
+///
+/// ```no_run
+/// use microbit::{executor, hal::gpio::p0::Parts, serial::AsyncSerial, time::RateExtU32};
+///
+/// let p = microbit::Peripherals::take().unwrap();
+/// let gpio = Parts::new(p.GPIO);
+/// let mut serial = AsyncSerial::new(microbit::serial_port!(gpio, p.UART0, 115_200.Hz()));
+///
+/// executor::block_on(async {
+///     let mut buf = [0; 16];
+///     loop {
+///         let len = serial.read(&mut buf).await;
+///         serial.write_all(&buf[..len]).await;
+///     }
+/// });
+/// ```
+#[cfg(feature = "v1")]
+pub struct AsyncSerial {
+    uart: UART0,
+}
+
+#[cfg(feature = "v1")]
+static RX_WAKER: WakerCell = WakerCell::new();
+#[cfg(feature = "v1")]
+static TX_WAKER: WakerCell = WakerCell::new();
+
+#[cfg(feature = "v1")]
+impl AsyncSerial {
+    /// Returns a new `AsyncSerial` using a configured `Uart`.
+    ///
+    /// Takes ownership of the `Uart`'s `UART0` peripheral, which keeps the
+    /// `Uart`'s configuration.
+    pub fn new(uart: Uart<UART0>) -> Self {
+        AsyncSerial { uart: uart.free() }
+    }
+
+    /// Gives the `UART0` peripheral back, still configured.
+    pub fn free(self) -> UART0 {
+        self.uart
+            .intenclr
+            .write(|w| w.rxdrdy().clear().txdrdy().clear());
+        self.uart
+    }
+
+    /// Waits for at least one byte to be received, then reads as many bytes
+    /// as are available into `buf`.
+    ///
+    /// Returns the number of bytes read, which is only 0 if `buf` is empty.
+    pub async fn read(&mut self, buf: &mut [u8]) -> usize {
+        let (first, rest) = match buf.split_first_mut() {
+            Some(split) => split,
+            None => return 0,
+        };
+        *first = ReadByte { uart: &self.uart }.await;
+        let mut len = 1;
+        for byte in rest {
+            match read_byte(&self.uart) {
+                Some(read) => *byte = read,
+                None => break,
+            }
+            len += 1;
+        }
+        len
+    }
+
+    /// Writes all of `buf`.
+    pub async fn write_all(&mut self, buf: &[u8]) {
+        for &byte in buf {
+            WriteByte {
+                uart: &self.uart,
+                byte,
+            }
+            .await;
+        }
+    }
+}
+
+/// Reads a received byte, if there is one.
+#[cfg(feature = "v1")]
+fn read_byte(uart: &UART0) -> Option<u8> {
+    if uart.events_rxdrdy.read().bits() == 0 {
+        return None;
+    }
+    uart.events_rxdrdy.reset();
+    Some(uart.rxd.read().bits() as u8)
+}
+
+/// Sends a byte, if the previous one has been sent.
+#[cfg(feature = "v1")]
+fn write_byte(uart: &UART0, byte: u8) -> bool {
+    if uart.events_txdrdy.read().bits() == 0 {
+        return false;
+    }
+    uart.events_txdrdy.reset();
+    uart.txd.write(|w| unsafe { w.bits(u32::from(byte)) });
+    true
+}
+
+#[cfg(feature = "v1")]
+struct ReadByte<'a> {
+    uart: &'a UART0,
+}
+
+#[cfg(feature = "v1")]
+impl Future for ReadByte<'_> {
+    type Output = u8;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u8> {
+        RX_WAKER.register(cx.waker());
+        match read_byte(self.uart) {
+            Some(byte) => Poll::Ready(byte),
+            None => {
+                self.uart.intenset.write(|w| w.rxdrdy().set());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "v1")]
+struct WriteByte<'a> {
+    uart: &'a UART0,
+    byte: u8,
+}
+
+#[cfg(feature = "v1")]
+impl Future for WriteByte<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        TX_WAKER.register(cx.waker());
+        if write_byte(self.uart, self.byte) {
+            Poll::Ready(())
+        } else {
+            self.uart.intenset.write(|w| w.txdrdy().set());
+            Poll::Pending
+        }
+    }
+}
+
+/// Wakes an [`AsyncSerial`] waiting to read or write.
+///
+/// Call this in the interrupt handler for `UART0`.
+///
+/// Disables the interrupts which have fired; the events are cleared when
+/// the waiting futures are polled.
+#[cfg(feature = "v1")]
+pub fn handle_uart_interrupt() {
+    // Only the `AsyncSerial` owning `UART0` enables its interrupts
+    let uart = unsafe { &*UART0::ptr() };
+    let enabled = uart.intenset.read();
+    if enabled.rxdrdy().is_enabled() && uart.events_rxdrdy.read().bits() != 0 {
+        uart.intenclr.write(|w| w.rxdrdy().clear());
+        RX_WAKER.wake();
+    }
+    if enabled.txdrdy().is_enabled() && uart.events_txdrdy.read().bits() != 0 {
+        uart.intenclr.write(|w| w.txdrdy().clear());
+        TX_WAKER.wake();
+    }
+}
@@ -0,0 +1,104 @@
+//! A simulated button, pressed from a script.
+
+use std::collections::VecDeque;
+
+use crate::button::ButtonEvent;
+
+/// A simulated push button, with the polled API of a
+/// [`Button`](crate::button::Button).
+///
+/// The button is pressed and released from a script of times, in
+/// milliseconds, at which it changes state, as time passes with
+/// [`advance_to()`](SimButton::advance_to). It can also be pressed and
+/// released directly.
+///
+/// ```
+/// use microbit::{button::ButtonEvent, sim::SimButton};
+///
+/// let mut button = SimButton::with_script(&[(100, true), (300, false)]);
+/// assert!(!button.is_pressed());
+///
+/// button.advance_to(150);
+/// assert_eq!(button.poll_event(), Some(ButtonEvent::Pressed));
+/// assert_eq!(button.poll_event(), None);
+///
+/// button.advance_to(400);
+/// assert_eq!(button.poll_event(), Some(ButtonEvent::Released));
+/// ```
+#[derive(Debug, Default)]
+pub struct SimButton {
+    script: VecDeque<(u32, bool)>,
+    now: u32,
+    pressed: bool,
+    reported: bool,
+}
+
+impl SimButton {
+    /// Returns a new `SimButton`, released, with an empty script.
+    pub fn new() -> Self {
+        SimButton::default()
+    }
+
+    /// Returns a new `SimButton`, released, following a script of
+    /// `(milliseconds, pressed)` changes in time order.
+    pub fn with_script(script: &[(u32, bool)]) -> Self {
+        let mut button = SimButton::new();
+        for &(at, pressed) in script {
+            button.schedule(at, pressed);
+        }
+        button
+    }
+
+    /// Adds a change to the script, after those already there.
+    pub fn schedule(&mut self, at: u32, pressed: bool) {
+        self.script.push_back((at, pressed));
+    }
+
+    /// Lets time pass until `now`, in milliseconds, applying the changes of
+    /// the script up to then.
+    pub fn advance_to(&mut self, now: u32) {
+        self.now = self.now.max(now);
+        while let Some(&(at, pressed)) = self.script.front() {
+            if at > self.now {
+                break;
+            }
+            self.pressed = pressed;
+            self.script.pop_front();
+        }
+    }
+
+    /// Returns the simulated time, in milliseconds.
+    pub fn now(&self) -> u32 {
+        self.now
+    }
+
+    /// Presses the button now.
+    pub fn press(&mut self) {
+        self.pressed = true;
+    }
+
+    /// Releases the button now.
+    pub fn release(&mut self) {
+        self.pressed = false;
+    }
+
+    /// Returns whether the button is currently pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Returns an event if the button has changed state since the last call.
+    ///
+    /// Changes shorter than the interval between calls may be missed.
+    pub fn poll_event(&mut self) -> Option<ButtonEvent> {
+        if self.pressed == self.reported {
+            return None;
+        }
+        self.reported = self.pressed;
+        Some(if self.pressed {
+            ButtonEvent::Pressed
+        } else {
+            ButtonEvent::Released
+        })
+    }
+}
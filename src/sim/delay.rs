@@ -0,0 +1,56 @@
+//! A simulated delay.
+
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+
+/// A simulated delay, which returns at once and adds up the time it should
+/// have waited.
+///
+/// ```
+/// use microbit::{
+///     display::{image::BitImage, LedMatrix},
+///     sim::{SimDelay, SimDisplay},
+///     time::ExtU32,
+/// };
+///
+/// // Application code, for any display
+/// fn blink(matrix: &mut impl LedMatrix, delay: &mut SimDelay) {
+///     matrix.show(&BitImage::new(&[[1; 5]; 5]));
+///     matrix.set_pixel(2, 2, 0);
+///     matrix.hold(delay, 500.millis());
+/// }
+///
+/// let mut display = SimDisplay::new();
+/// let mut delay = SimDelay::new();
+/// blink(&mut display, &mut delay);
+///
+/// assert_eq!(display.refresh().to_string(), "99999\n99999\n99.99\n99999\n99999\n");
+/// assert_eq!(delay.elapsed_us(), 500_000);
+/// ```
+#[derive(Debug, Default)]
+pub struct SimDelay {
+    elapsed_us: u64,
+}
+
+impl SimDelay {
+    /// Returns a new `SimDelay`, with no time elapsed.
+    pub const fn new() -> Self {
+        SimDelay { elapsed_us: 0 }
+    }
+
+    /// Returns the time waited for so far, in microseconds.
+    pub fn elapsed_us(&self) -> u64 {
+        self.elapsed_us
+    }
+}
+
+impl DelayUs<u32> for SimDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.elapsed_us += u64::from(us);
+    }
+}
+
+impl DelayMs<u32> for SimDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        self.elapsed_us += u64::from(ms) * 1_000;
+    }
+}
@@ -0,0 +1,398 @@
+//! A simulated LED display, driven by the [`display`](crate::display)
+//! driver.
+
+use core::fmt;
+
+use embedded_hal::blocking::delay::DelayUs;
+use tiny_led_matrix::{DisplayControl, DisplayTimer, Matrix};
+
+use super::record::{for_each_lit, Alarm, PinWrite, PinWriteKind, Recording};
+use crate::{
+    display::{
+        brightnesses, image::GreyscaleImage, Display, Frame, LedMatrix, MicrobitFrame, Render,
+        MATRIX_COLS, MATRIX_ROWS,
+    },
+    time::MillisDurationU32,
+};
+
+/// The matrix layout of the display.
+type Mtx = <MicrobitFrame as Frame>::Mtx;
+
+/// Simulated display pins, implementing [`DisplayControl`].
+///
+/// This records which LEDs are lit, by their (x, y) coordinates, and the
+/// writes to the pins.
+#[derive(Debug, Default)]
+pub struct SimControl {
+    row: usize,
+    cols: u32,
+    now: u32,
+    writes: Vec<PinWrite>,
+}
+
+impl SimControl {
+    /// Returns a new `SimControl`, with all the LEDs off.
+    pub const fn new() -> Self {
+        SimControl {
+            row: 0,
+            cols: 0,
+            now: 0,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Returns whether each LED is lit, by (y, x) coordinates.
+    pub fn lit(&self) -> [[bool; 5]; 5] {
+        let mut lit = [[false; 5]; 5];
+        for_each_lit(self.row, self.cols, |x, y| lit[y][x] = true);
+        lit
+    }
+
+    /// Returns the writes to the pins, in order.
+    pub fn writes(&self) -> &[PinWrite] {
+        &self.writes
+    }
+
+    /// Sets the time the next writes happen at, in ticks.
+    pub fn set_now(&mut self, now: u32) {
+        self.now = now;
+    }
+
+    /// Returns the writes to the pins, and forgets them.
+    pub fn take_writes(&mut self) -> Vec<PinWrite> {
+        core::mem::take(&mut self.writes)
+    }
+
+    fn record(&mut self, kind: PinWriteKind, cols: u32) {
+        self.writes.push(PinWrite {
+            at: self.now,
+            kind,
+            row: self.row,
+            cols,
+        });
+    }
+}
+
+impl DisplayControl for SimControl {
+    fn initialise_for_display(&mut self) {
+        self.cols = 0;
+    }
+
+    fn display_row_leds(&mut self, row: usize, cols: u32) {
+        self.row = row;
+        self.cols = cols;
+        self.record(PinWriteKind::Row, cols);
+    }
+
+    fn light_current_row_leds(&mut self, cols: u32) {
+        self.cols |= cols;
+        self.record(PinWriteKind::Light, cols);
+    }
+}
+
+/// A simulated timer, implementing [`DisplayTimer`].
+///
+/// Time only passes with [`advance()`](SimTimer::advance). This records the
+/// secondary alarms programmed.
+#[derive(Debug, Default)]
+pub struct SimTimer {
+    cycle: u16,
+    now: u16,
+    elapsed: u32,
+    alarm: u16,
+    secondary: bool,
+    primary_fired: bool,
+    secondary_fired: bool,
+    alarms: Vec<Alarm>,
+}
+
+impl SimTimer {
+    /// Returns a new `SimTimer`, not yet initialised.
+    pub const fn new() -> Self {
+        SimTimer {
+            cycle: 0,
+            now: 0,
+            elapsed: 0,
+            alarm: 0,
+            secondary: false,
+            primary_fired: false,
+            secondary_fired: false,
+            alarms: Vec::new(),
+        }
+    }
+
+    /// Returns the ticks until the next primary cycle or secondary alarm.
+    pub fn ticks_to_event(&self) -> u16 {
+        if self.secondary && self.alarm > self.now {
+            self.alarm - self.now
+        } else {
+            self.cycle - self.now
+        }
+    }
+
+    /// Lets `ticks` pass, firing the primary cycle and secondary alarm on
+    /// the way.
+    pub fn advance(&mut self, ticks: u16) {
+        let then = self.now;
+        self.now += ticks;
+        self.elapsed += u32::from(ticks);
+        if self.secondary && then < self.alarm && self.alarm <= self.now {
+            self.secondary_fired = true;
+        }
+        if self.now >= self.cycle {
+            self.now -= self.cycle;
+            self.primary_fired = true;
+        }
+    }
+
+    /// Returns the ticks that have passed since the timer was created.
+    pub fn elapsed(&self) -> u32 {
+        self.elapsed
+    }
+
+    /// Returns the secondary alarms programmed, in order.
+    pub fn alarms(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    /// Returns the secondary alarms programmed, and forgets them.
+    pub fn take_alarms(&mut self) -> Vec<Alarm> {
+        core::mem::take(&mut self.alarms)
+    }
+}
+
+impl DisplayTimer for SimTimer {
+    fn initialise_cycle(&mut self, ticks: u16) {
+        self.cycle = ticks;
+        self.now = 0;
+    }
+
+    fn enable_secondary(&mut self) {
+        self.secondary = true;
+    }
+
+    fn disable_secondary(&mut self) {
+        self.secondary = false;
+    }
+
+    fn program_secondary(&mut self, ticks: u16) {
+        self.alarm = ticks;
+        self.alarms.push(Alarm {
+            at: self.elapsed,
+            ticks,
+        });
+    }
+
+    fn check_primary(&mut self) -> bool {
+        core::mem::take(&mut self.primary_fired)
+    }
+
+    fn check_secondary(&mut self) -> bool {
+        core::mem::take(&mut self.secondary_fired)
+    }
+}
+
+/// A simulated display, with the API of a
+/// [`DisplayDriver`](crate::display::DisplayDriver).
+///
+/// The [`Display`] runs as on the board, against a [`SimControl`] and a
+/// [`SimTimer`]. [`refresh()`](SimDisplay::refresh) runs it for a whole
+/// refresh, then gives each LED's brightness from the time it was lit.
+pub struct SimDisplay {
+    display: Display<MicrobitFrame>,
+    control: SimControl,
+    timer: SimTimer,
+    frame: MicrobitFrame,
+    image: [[u8; 5]; 5],
+}
+
+impl SimDisplay {
+    /// Returns a new `SimDisplay`, initially blank.
+    pub fn new() -> Self {
+        let mut control = SimControl::new();
+        let mut timer = SimTimer::new();
+        tiny_led_matrix::initialise_control(&mut control);
+        tiny_led_matrix::initialise_timer(&mut timer);
+        SimDisplay {
+            display: Display::new(),
+            control,
+            timer,
+            frame: MicrobitFrame::const_default(),
+            image: [[0; 5]; 5],
+        }
+    }
+
+    /// Shows a frame until something else is shown.
+    ///
+    /// As with a [`DisplayDriver`](crate::display::DisplayDriver),
+    /// [`brightness()`](LedMatrix::brightness) gives 0 for each LED until
+    /// an image is shown.
+    pub fn set_frame(&mut self, frame: &MicrobitFrame) {
+        self.image = [[0; 5]; 5];
+        self.display.set_frame(frame);
+    }
+
+    /// Shows an image until something else is shown.
+    pub fn show<R: Render>(&mut self, image: &R) {
+        self.image = brightnesses(image);
+        self.frame.set(image);
+        self.display.set_frame(&self.frame);
+    }
+
+    /// Shows a matrix image, as from
+    /// [`led::Display::display2matrix()`](crate::led::Display::display2matrix),
+    /// until something else is shown.
+    ///
+    /// ```
+    /// use microbit::{led, sim::SimDisplay};
+    ///
+    /// let heart = [
+    ///     [0, 9, 0, 9, 0],
+    ///     [9, 0, 9, 0, 9],
+    ///     [9, 0, 0, 0, 9],
+    ///     [0, 9, 0, 9, 0],
+    ///     [0, 0, 9, 0, 0],
+    /// ];
+    /// let mut display = SimDisplay::new();
+    /// display.show_matrix(led::Display::display2matrix(heart));
+    /// assert_eq!(display.refresh().0, heart);
+    /// ```
+    pub fn show_matrix(&mut self, led_matrix: [[u8; MATRIX_COLS]; MATRIX_ROWS]) {
+        let mut image = [[0; 5]; 5];
+        for (row, matrix_row) in led_matrix.iter().enumerate() {
+            for (col, &value) in matrix_row.iter().enumerate() {
+                if let Some((x, y)) = Mtx::image_coordinates(col, row) {
+                    image[y][x] = value;
+                }
+            }
+        }
+        self.show(&GreyscaleImage::new(&image));
+    }
+
+    /// Turns all the LEDs off.
+    pub fn clear(&mut self) {
+        self.show(&GreyscaleImage::blank());
+    }
+
+    /// Returns the simulated display pins.
+    pub fn control(&self) -> &SimControl {
+        &self.control
+    }
+
+    /// Runs the display for a whole refresh, once per matrix row, and
+    /// returns what it showed.
+    ///
+    /// The row being shown is finished first, so that an image shown since
+    /// is used for the whole refresh.
+    pub fn refresh(&mut self) -> Snapshot {
+        self.record().brightness()
+    }
+
+    /// Runs the display for a whole refresh, as
+    /// [`refresh()`](SimDisplay::refresh) does, and returns a recording of
+    /// the pin writes and secondary alarms.
+    pub fn record(&mut self) -> Recording {
+        self.control.take_writes();
+        self.timer.take_alarms();
+        while !self.step_row() {}
+        let start = self.timer.elapsed();
+        let mut rows = 0;
+        while rows < MATRIX_ROWS {
+            if self.step_row() {
+                rows += 1;
+            }
+        }
+        // The last row switch belongs to the next refresh
+        let recorded = start..self.timer.elapsed();
+        let writes = self
+            .control
+            .take_writes()
+            .into_iter()
+            .filter(|write| recorded.contains(&write.at))
+            .map(|write| PinWrite {
+                at: write.at - start,
+                ..write
+            })
+            .collect();
+        let alarms = self
+            .timer
+            .take_alarms()
+            .into_iter()
+            .filter(|alarm| recorded.contains(&alarm.at))
+            .map(|alarm| Alarm {
+                at: alarm.at - start,
+                ..alarm
+            })
+            .collect();
+        Recording::new(writes, alarms, recorded.end - start)
+    }
+
+    /// Runs the display until the next primary cycle or secondary alarm,
+    /// returning whether it switched to a new row.
+    fn step_row(&mut self) -> bool {
+        let ticks = self.timer.ticks_to_event();
+        self.timer.advance(ticks);
+        self.control.set_now(self.timer.elapsed());
+        self.display
+            .handle_event(&mut self.timer, &mut self.control)
+            .is_new_row()
+    }
+}
+
+impl LedMatrix for SimDisplay {
+    fn show<R: Render>(&mut self, image: &R) {
+        SimDisplay::show(self, image);
+    }
+
+    fn brightness(&self, x: usize, y: usize) -> u8 {
+        self.image[y][x]
+    }
+
+    fn hold<D: DelayUs<u32>>(&mut self, delay: &mut D, duration: MillisDurationU32) {
+        delay.delay_us(duration.to_micros());
+    }
+}
+
+impl Default for SimDisplay {
+    /// Returns a new `SimDisplay`, initially blank.
+    fn default() -> Self {
+        SimDisplay::new()
+    }
+}
+
+/// What the display showed over a refresh: the brightness of each LED, by
+/// (y, x) coordinates.
+///
+/// This formats as five lines of text, with `.` for an LED which is off
+/// and its brightness otherwise, or with the alternate flag (`{:#}`) as
+/// blocks in shades of red, with ANSI escape codes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot(pub [[u8; 5]; 5]);
+
+impl Render for Snapshot {
+    fn brightness_at(&self, x: usize, y: usize) -> u8 {
+        self.0[y][x]
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.0 {
+            for &brightness in row {
+                if f.alternate() {
+                    let red = 40 + u32::from(brightness) * 215 / 9;
+                    write!(f, "\x1b[38;2;{};0;0m██", red)?;
+                } else if brightness == 0 {
+                    f.write_str(".")?;
+                } else {
+                    write!(f, "{}", brightness)?;
+                }
+            }
+            if f.alternate() {
+                f.write_str("\x1b[0m")?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+//! Host-side simulation of the display, buttons and serial port.
+//!
+//! # Scope
+//!
+//! With the `sim` feature, the crate builds for `std` targets, and this
+//! module provides software stand-ins for the hardware, to test application
+//! logic on a PC:
+//! - a [`SimDisplay`], which runs the [`display`](crate::display) driver's
+//!   refresh cycle against a simulated matrix, and renders what it shows as
+//!   a [`Snapshot`], as text or with ANSI colours
+//! - a [`Recording`] of the display pin writes and secondary alarms over a
+//!   refresh, giving each LED's duty cycle, to check the brightness shown
+//!   against expected images
+//! - a [`SimButton`], with the polled API of a
+//!   [`Button`](crate::button::Button), pressed from a script
+//! - a [`SimSerial`], a serial port reading scripted input and recording
+//!   its output
+//! - a [`SimDelay`], which adds up the time waited for instead of waiting.
+//!
+//! The peripherals themselves can't be used on the host: only the types and
+//! the logic which doesn't touch registers.
+//!
+//! # Example
+//!
+//! ```
+//! use microbit::{
+//!     button::ButtonEvent,
+//!     display::image::GreyscaleImage,
+//!     sim::{SimButton, SimDisplay},
+//! };
+//!
+//! let mut display = SimDisplay::new();
+//! let mut button_a = SimButton::with_script(&[(10, true), (50, false)]);
+//!
+//! button_a.advance_to(20);
+//! if button_a.poll_event() == Some(ButtonEvent::Pressed) {
+//!     display.show(&GreyscaleImage::new(&[
+//!         [0, 9, 0, 9, 0],
+//!         [9, 5, 9, 5, 9],
+//!         [9, 5, 5, 5, 9],
+//!         [0, 9, 5, 9, 0],
+//!         [0, 0, 9, 0, 0],
+//!     ]));
+//! }
+//!
+//! assert_eq!(
+//!     display.refresh().to_string(),
+//!     ".9.9.\n95959\n95559\n.959.\n..9..\n",
+//! );
+//! ```
+
+mod button;
+mod delay;
+mod display;
+mod record;
+mod serial;
+
+pub use button::SimButton;
+pub use delay::SimDelay;
+pub use display::{SimControl, SimDisplay, SimTimer, Snapshot};
+pub use record::{Alarm, PinWrite, PinWriteKind, Recording};
+pub use serial::SimSerial;
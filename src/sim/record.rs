@@ -0,0 +1,222 @@
+//! Recordings of the display pins and timer, over a refresh.
+
+use tiny_led_matrix::{Frame, Matrix};
+
+use super::Snapshot;
+use crate::display::{MicrobitFrame, MATRIX_COLS};
+
+/// The ticks an LED is lit for, per primary cycle, at each brightness.
+///
+/// These are the greyscale timings of `tiny-led-matrix`.
+const LIT_TICKS: [u32; 10] = [0, 2, 4, 8, 15, 28, 53, 102, 199, 375];
+
+/// How the display driver changed the display pins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PinWriteKind {
+    /// A new matrix row, with only its columns in `cols` lit.
+    Row,
+    /// More columns of the current row lit, for a dimmer brightness.
+    Light,
+}
+
+/// A write to the display pins, by the display driver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PinWrite {
+    /// When the pins were written, in ticks since the recording started.
+    pub at: u32,
+    /// How the pins were changed.
+    pub kind: PinWriteKind,
+    /// The matrix row lit.
+    pub row: usize,
+    /// The matrix columns written, a bit for each.
+    pub cols: u32,
+}
+
+/// A secondary alarm programmed by the display driver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Alarm {
+    /// When the alarm was programmed, in ticks since the recording started.
+    pub at: u32,
+    /// The tick of the primary cycle the alarm was programmed for.
+    pub ticks: u16,
+}
+
+/// What the display driver did over a refresh, from
+/// [`SimDisplay::record()`](super::SimDisplay::record).
+///
+/// The pin writes are replayed to give each LED's duty cycle, and the
+/// brightness it shows.
+///
+/// ```
+/// use microbit::{display::image::GreyscaleImage, sim::SimDisplay};
+///
+/// let mut display = SimDisplay::new();
+/// display.show(&GreyscaleImage::new(&[
+///     [9, 0, 0, 0, 0],
+///     [0, 1, 0, 0, 0],
+///     [0, 0, 5, 0, 0],
+///     [0, 0, 0, 8, 0],
+///     [0, 0, 0, 0, 0],
+/// ]));
+/// let recording = display.record();
+///
+/// recording.assert_brightness(&[
+///     [9, 0, 0, 0, 0],
+///     [0, 1, 0, 0, 0],
+///     [0, 0, 5, 0, 0],
+///     [0, 0, 0, 8, 0],
+///     [0, 0, 0, 0, 0],
+/// ]);
+/// // Lit for the whole of its row's primary cycle
+/// assert_eq!(recording.lit_ticks()[0][0], 375);
+/// // Brightness 5 is lit for the last 28 ticks of its row
+/// assert!(recording.alarms().iter().any(|alarm| alarm.ticks == 375 - 28));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    writes: Vec<PinWrite>,
+    alarms: Vec<Alarm>,
+    ticks: u32,
+}
+
+impl Recording {
+    pub(super) fn new(writes: Vec<PinWrite>, alarms: Vec<Alarm>, ticks: u32) -> Self {
+        Recording {
+            writes,
+            alarms,
+            ticks,
+        }
+    }
+
+    /// Returns the writes to the display pins, in order.
+    pub fn writes(&self) -> &[PinWrite] {
+        &self.writes
+    }
+
+    /// Returns the secondary alarms programmed, in order.
+    pub fn alarms(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    /// Returns the length of the recording, in ticks.
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// Returns the ticks each LED was lit for, by (y, x) coordinates.
+    pub fn lit_ticks(&self) -> [[u32; 5]; 5] {
+        let mut lit_ticks = [[0; 5]; 5];
+        let mut cols = 0;
+        for (i, write) in self.writes.iter().enumerate() {
+            match write.kind {
+                PinWriteKind::Row => cols = write.cols,
+                PinWriteKind::Light => cols |= write.cols,
+            }
+            let until = self.writes.get(i + 1).map_or(self.ticks, |next| next.at);
+            for_each_lit(write.row, cols, |x, y| lit_ticks[y][x] += until - write.at);
+        }
+        lit_ticks
+    }
+
+    /// Returns the fraction of the recording each LED was lit for, by
+    /// (y, x) coordinates.
+    ///
+    /// At full brightness, each LED is lit for its matrix row's share of
+    /// the refresh: a third on the v1, a fifth on the v2.
+    ///
+    /// ```
+    /// use microbit::{display::image::BitImage, sim::SimDisplay};
+    ///
+    /// let mut display = SimDisplay::new();
+    /// display.show(&BitImage::new(&[[1; 5]; 5]));
+    /// let duty_cycles = display.record().duty_cycles();
+    ///
+    /// #[cfg(feature = "v1")]
+    /// let expected = 1.0 / 3.0;
+    /// #[cfg(feature = "v2")]
+    /// let expected = 1.0 / 5.0;
+    /// for &duty in duty_cycles.iter().flatten() {
+    ///     assert!((duty - expected).abs() < 1e-6);
+    /// }
+    /// ```
+    pub fn duty_cycles(&self) -> [[f32; 5]; 5] {
+        let mut duty_cycles = [[0.0; 5]; 5];
+        for (duty_row, ticks_row) in duty_cycles.iter_mut().zip(self.lit_ticks().iter()) {
+            for (duty, &ticks) in duty_row.iter_mut().zip(ticks_row) {
+                *duty = ticks as f32 / self.ticks.max(1) as f32;
+            }
+        }
+        duty_cycles
+    }
+
+    /// Returns the brightness each LED showed: the one whose greyscale
+    /// timing is closest to the time it was lit for.
+    pub fn brightness(&self) -> Snapshot {
+        let mut brightness = [[0; 5]; 5];
+        for (brightness_row, ticks_row) in brightness.iter_mut().zip(self.lit_ticks().iter()) {
+            for (led, &ticks) in brightness_row.iter_mut().zip(ticks_row) {
+                *led = level(ticks);
+            }
+        }
+        Snapshot(brightness)
+    }
+
+    /// Checks that each LED showed its `expected` brightness, by (y, x)
+    /// coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics with both images as text if any LED differs.
+    ///
+    /// # Example
+    ///
+    /// Each LED shows each brightness on its own:
+    ///
+    /// ```
+    /// use microbit::{display::image::GreyscaleImage, sim::SimDisplay};
+    ///
+    /// let mut display = SimDisplay::new();
+    /// for y in 0..5 {
+    ///     for x in 0..5 {
+    ///         for brightness in 0..=9 {
+    ///             let mut image = [[0; 5]; 5];
+    ///             image[y][x] = brightness;
+    ///             display.show(&GreyscaleImage::new(&image));
+    ///             display.record().assert_brightness(&image);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn assert_brightness(&self, expected: &[[u8; 5]; 5]) {
+        let brightness = self.brightness();
+        assert!(
+            brightness.0 == *expected,
+            "the display showed\n{}instead of\n{}",
+            brightness,
+            Snapshot(*expected)
+        );
+    }
+}
+
+/// Calls `f` with the (x, y) coordinates of each LED lit by `cols` on the
+/// matrix row `row`.
+pub(super) fn for_each_lit(row: usize, cols: u32, mut f: impl FnMut(usize, usize)) {
+    for col in 0..MATRIX_COLS {
+        if cols & 1 << col != 0 {
+            if let Some((x, y)) = <MicrobitFrame as Frame>::Mtx::image_coordinates(col, row) {
+                f(x, y);
+            }
+        }
+    }
+}
+
+/// Returns the brightness lit for closest to `ticks` per primary cycle.
+fn level(ticks: u32) -> u8 {
+    let mut best = 0;
+    for (brightness, &lit) in LIT_TICKS.iter().enumerate() {
+        if lit.abs_diff(ticks) < LIT_TICKS[best].abs_diff(ticks) {
+            best = brightness;
+        }
+    }
+    best as u8
+}
@@ -0,0 +1,82 @@
+//! A simulated serial port.
+
+use core::{convert::Infallible, fmt};
+use std::collections::VecDeque;
+
+use embedded_hal::serial;
+
+/// A simulated serial port, reading scripted input and recording its
+/// output.
+///
+/// This implements the `embedded-hal` serial traits and `fmt::Write`, as
+/// the board's `Uart` does.
+///
+/// ```
+/// use core::fmt::Write;
+/// use embedded_hal::serial::Read;
+/// use microbit::sim::SimSerial;
+///
+/// let mut serial = SimSerial::new();
+/// serial.feed(b"a");
+/// assert_eq!(serial.read(), Ok(b'a'));
+/// assert!(serial.read().is_err());
+///
+/// write!(serial, "hello\r\n").unwrap();
+/// assert_eq!(serial.output(), b"hello\r\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct SimSerial {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+impl SimSerial {
+    /// Returns a new `SimSerial`, with no input.
+    pub fn new() -> Self {
+        SimSerial::default()
+    }
+
+    /// Adds bytes to the input.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    /// Returns the bytes written so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns the bytes written so far, and forgets them.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.output)
+    }
+}
+
+impl serial::Read<u8> for SimSerial {
+    type Error = Infallible;
+
+    /// Reads a byte of input, or returns `WouldBlock` while there's none.
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        self.input.pop_front().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl serial::Write<u8> for SimSerial {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.output.push(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl fmt::Write for SimSerial {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.output.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
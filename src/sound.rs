@@ -0,0 +1,448 @@
+//! The v2's microphone and speaker.
+//!
+//! # Scope
+//!
+//! This module provides, like MicroPython's `microphone` and `speaker`:
+//! - a [`Microphone`] driver, which samples the microphone with the `SAADC`
+//!   to measure the [sound level](Microphone::sound_level) in dB
+//! - loud and quiet [events](SoundEvent), such as claps, from a
+//!   [`SoundDetector`] with thresholds in dB
+//! - a [`Speaker`] driver, which plays tones and 8-bit PCM samples with the
+//!   `PWM0` peripheral, reading the samples by EasyDMA.
+//!
+//! The microphone draws about 100µA while powered, and lights the LED next
+//! to the microphone hole.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     board::Board,
+//!     hal::gpio::Level,
+//!     sound::{Microphone, SoundDetector, SoundEvent, Speaker},
+//!     time::RateExtU32,
+//! };
+//!
+//! let board = Board::take().unwrap();
+//! let mut microphone = Microphone::new(
+//!     board.SAADC,
+//!     board.pins.p0_05,
+//!     board.pins.p0_20.into_push_pull_output(Level::Low),
+//! );
+//! let mut speaker = Speaker::new(
+//!     board.PWM0,
+//!     board.pins.p0_00.into_push_pull_output(Level::Low).degrade(),
+//! );
+//! let mut detector = SoundDetector::new();
+//!
+//! loop {
+//!     match detector.update(microphone.sound_level()) {
+//!         Some(SoundEvent::Loud) => speaker.tone(880.Hz()),
+//!         Some(SoundEvent::Quiet) => speaker.stop(),
+//!         None => {}
+//!     }
+//! }
+//! ```
+//!
+//! See a working example at `examples/sound_clap.rs`
+
+use core::sync::atomic::{compiler_fence, AtomicU16, Ordering};
+
+use crate::{
+    gpio::{MIC, RUN_MIC},
+    hal::{
+        gpio::{Disconnected, Output, Pin, Port, PushPull},
+        prelude::*,
+    },
+    pac::{PWM0, SAADC},
+    time::HertzU32,
+};
+
+/// The number of samples in a sound level measurement.
+pub const WINDOW: usize = 128;
+
+/// The microphone's sample rate: the `SAADC`'s 16MHz clock divided by 1375,
+/// about 11.6kHz, so that a measurement takes 11ms.
+pub const SAMPLE_RATE: u32 = 16_000_000 / SAMPLE_PERIOD;
+
+const SAMPLE_PERIOD: u32 = 1_375;
+
+/// The sound level of a signal of 1 count RMS, in dB SPL.
+///
+/// The -38dBV/Pa microphone gives about 340 counts RMS at 94dB SPL (1 Pa),
+/// with a gain of 4, the 0.6V reference and 12 bits.
+const CALIBRATION_DB: u32 = 43;
+
+/// Returns the sound level of `samples`, in dB SPL, from their RMS value
+/// once their DC offset is removed.
+///
+/// ```
+/// use microbit::sound;
+///
+/// assert_eq!(sound::level(&[2048; 16]), 0);
+/// // 1 count RMS
+/// assert_eq!(sound::level(&[2047, 2049, 2047, 2049]), 43);
+/// // 10 times the amplitude is 20dB louder
+/// assert_eq!(sound::level(&[2038, 2058, 2038, 2058]), 63);
+/// ```
+pub fn level(samples: &[i16]) -> u8 {
+    if samples.is_empty() {
+        return 0;
+    }
+    let len = samples.len() as i64;
+    let mean = samples.iter().map(|&s| i64::from(s)).sum::<i64>() / len;
+    let mean_square = samples
+        .iter()
+        .map(|&s| (i64::from(s) - mean).pow(2))
+        .sum::<i64>()
+        / len;
+    if mean_square == 0 {
+        return 0;
+    }
+    // 10 * log10(x) = 3.0103 * log2(x), rounded
+    let db = (log2_q8(mean_square as u64) * 30_103 + (5_000 << 8)) / (10_000 << 8);
+    (db + CALIBRATION_DB).min(255) as u8
+}
+
+/// Returns log2(`x`) in 256ths, rounded down, for `x` > 0.
+fn log2_q8(x: u64) -> u32 {
+    let int = 63 - x.leading_zeros();
+    // The mantissa, from 1 to 2 in 65536ths: each squaring gives the next
+    // bit of the fraction
+    let mut y = if int >= 16 {
+        x >> (int - 16)
+    } else {
+        x << (16 - int)
+    };
+    let mut fraction = 0;
+    for bit in (0..8).rev() {
+        y = (y * y) >> 16;
+        if y >= 2 << 16 {
+            y >>= 1;
+            fraction |= 1 << bit;
+        }
+    }
+    int << 8 | fraction
+}
+
+/// A change in the sound level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SoundEvent {
+    /// The sound level rose above the loud threshold, as for a clap.
+    Loud,
+    /// The sound level fell below the quiet threshold after being loud.
+    Quiet,
+}
+
+/// Turns sound levels into loud and quiet events.
+///
+/// ```
+/// use microbit::sound::{SoundDetector, SoundEvent};
+///
+/// let mut detector = SoundDetector::new();
+/// detector.set_threshold(SoundEvent::Loud, 80);
+/// assert_eq!(detector.update(60), None);
+/// assert_eq!(detector.update(85), Some(SoundEvent::Loud));
+/// assert_eq!(detector.update(70), None);
+/// assert_eq!(detector.update(50), Some(SoundEvent::Quiet));
+/// assert_eq!(detector.current_event(), Some(SoundEvent::Quiet));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SoundDetector {
+    loud: u8,
+    quiet: u8,
+    current: Option<SoundEvent>,
+}
+
+impl SoundDetector {
+    /// Returns a new `SoundDetector`, with a loud threshold of 70dB and a
+    /// quiet threshold of 55dB.
+    pub const fn new() -> Self {
+        SoundDetector {
+            loud: 70,
+            quiet: 55,
+            current: None,
+        }
+    }
+
+    /// Sets the level, in dB, above which the sound is loud or below which
+    /// it is quiet.
+    pub fn set_threshold(&mut self, event: SoundEvent, level: u8) {
+        match event {
+            SoundEvent::Loud => self.loud = level,
+            SoundEvent::Quiet => self.quiet = level,
+        }
+    }
+
+    /// Returns the threshold for `event`, in dB.
+    pub fn threshold(&self, event: SoundEvent) -> u8 {
+        match event {
+            SoundEvent::Loud => self.loud,
+            SoundEvent::Quiet => self.quiet,
+        }
+    }
+
+    /// Takes a new sound level, in dB, and returns the event it causes, if
+    /// any.
+    pub fn update(&mut self, level: u8) -> Option<SoundEvent> {
+        let event = match self.current {
+            Some(SoundEvent::Loud) if level < self.quiet => SoundEvent::Quiet,
+            Some(SoundEvent::Loud) => return None,
+            _ if level > self.loud => SoundEvent::Loud,
+            _ => return None,
+        };
+        self.current = Some(event);
+        Some(event)
+    }
+
+    /// Returns the last event, or `None` if the sound hasn't been loud yet.
+    pub fn current_event(&self) -> Option<SoundEvent> {
+        self.current
+    }
+}
+
+impl Default for SoundDetector {
+    fn default() -> Self {
+        SoundDetector::new()
+    }
+}
+
+/// The microphone, sampled by the `SAADC`.
+pub struct Microphone {
+    saadc: SAADC,
+    mic: MIC<Disconnected>,
+    run: RUN_MIC,
+    samples: [i16; WINDOW],
+}
+
+impl Microphone {
+    /// Powers the microphone and returns a new `Microphone` using the
+    /// `SAADC`.
+    pub fn new(saadc: SAADC, mic: MIC<Disconnected>, mut run: RUN_MIC) -> Self {
+        run.set_high().ok();
+        // The microphone is on AIN3
+        saadc.ch[0].pselp.write(|w| w.pselp().analog_input3());
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+        saadc.ch[0].config.write(|w| {
+            w.gain()
+                .gain4()
+                .refsel()
+                .internal()
+                .tacq()
+                ._3us()
+                .mode()
+                .se()
+                .resp()
+                .bypass()
+                .resn()
+                .bypass()
+                .burst()
+                .disabled()
+        });
+        saadc.resolution.write(|w| w.val()._12bit());
+        saadc.oversample.write(|w| w.oversample().bypass());
+        saadc
+            .samplerate
+            .write(|w| unsafe { w.cc().bits(SAMPLE_PERIOD as u16).mode().timers() });
+        saadc.enable.write(|w| w.enable().enabled());
+
+        saadc.events_calibratedone.reset();
+        saadc.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
+        while saadc.events_calibratedone.read().bits() == 0 {}
+        saadc.events_calibratedone.reset();
+
+        Microphone {
+            saadc,
+            mic,
+            run,
+            samples: [0; WINDOW],
+        }
+    }
+
+    /// Powers the microphone down and releases the `SAADC` and the pins.
+    pub fn free(mut self) -> (SAADC, MIC<Disconnected>, RUN_MIC) {
+        self.saadc.enable.write(|w| w.enable().disabled());
+        self.saadc.ch[0].pselp.write(|w| w.pselp().nc());
+        self.run.set_low().ok();
+        (self.saadc, self.mic, self.run)
+    }
+
+    /// Records [`WINDOW`] samples at [`SAMPLE_RATE`], blocking for about
+    /// 11ms, and returns them.
+    pub fn record(&mut self) -> &[i16; WINDOW] {
+        let ptr = self.samples.as_mut_ptr() as u32;
+        let saadc = &self.saadc;
+        saadc.result.ptr.write(|w| unsafe { w.ptr().bits(ptr) });
+        saadc
+            .result
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(WINDOW as u16) });
+        saadc.events_started.reset();
+        saadc.events_end.reset();
+        // The samples are written by EasyDMA, behind the compiler's back
+        compiler_fence(Ordering::SeqCst);
+        saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+        while saadc.events_started.read().bits() == 0 {}
+        // The local timer takes the samples once started
+        saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+        while saadc.events_end.read().bits() == 0 {}
+        saadc.events_stopped.reset();
+        saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while saadc.events_stopped.read().bits() == 0 {}
+        compiler_fence(Ordering::SeqCst);
+        &self.samples
+    }
+
+    /// Measures the sound level, in dB SPL, over [`WINDOW`] samples.
+    pub fn sound_level(&mut self) -> u8 {
+        level(self.record())
+    }
+}
+
+/// The frequency of the PWM carrier for PCM samples: the 16MHz clock over
+/// 256 steps.
+pub const CARRIER_RATE: u32 = 62_500;
+
+/// The number of samples converted at a time while playing.
+const CHUNK_LEN: usize = 64;
+
+/// The duty cycle of the current tone, read by EasyDMA, which can't read
+/// flash.
+static TONE_DUTY: AtomicU16 = AtomicU16::new(0);
+
+/// A speaker driven by `PWM0`.
+pub struct Speaker {
+    pwm: PWM0,
+    pin: Pin<Output<PushPull>>,
+    buffers: [[u16; CHUNK_LEN]; 2],
+}
+
+impl Speaker {
+    /// Returns a new `Speaker` on `pin`, the on-board speaker on `P0_00` or
+    /// one connected to the edge connector.
+    pub fn new(pwm: PWM0, pin: Pin<Output<PushPull>>) -> Self {
+        pwm.psel.out[0].write(|w| unsafe {
+            w.pin()
+                .bits(pin.pin())
+                .port()
+                .bit(pin.port() == Port::Port1)
+                .connect()
+                .connected()
+        });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.decoder
+            .write(|w| w.load().common().mode().refresh_count());
+        pwm.loop_.write(|w| w.cnt().disabled());
+        pwm.enable.write(|w| w.enable().enabled());
+        Speaker {
+            pwm,
+            pin,
+            buffers: [[0; CHUNK_LEN]; 2],
+        }
+    }
+
+    /// Silences the speaker and releases `PWM0` and the pin.
+    pub fn free(mut self) -> (PWM0, Pin<Output<PushPull>>) {
+        self.stop();
+        self.pwm.enable.write(|w| w.enable().disabled());
+        self.pwm.psel.out[0].write(|w| w.connect().disconnected());
+        (self.pwm, self.pin)
+    }
+
+    /// Plays a square wave of `frequency` until [`stop()`](Speaker::stop)
+    /// or another sound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frequency` isn't in the range 31Hz to 20kHz.
+    pub fn tone(&mut self, frequency: HertzU32) {
+        assert!(
+            (31..=20_000).contains(&frequency.raw()),
+            "unsupported frequency"
+        );
+        self.stop();
+        // 1MHz ticks
+        let top = 1_000_000 / frequency.raw();
+        self.pwm.prescaler.write(|w| w.prescaler().div_16());
+        self.pwm
+            .countertop
+            .write(|w| unsafe { w.countertop().bits(top as u16) });
+        TONE_DUTY.store(top as u16 / 2, Ordering::Relaxed);
+        self.pwm.seq0.refresh.write(|w| unsafe { w.bits(0) });
+        // The last duty cycle of a sequence is held until stopped
+        self.start(TONE_DUTY.as_ptr(), 1);
+    }
+
+    /// Plays 8-bit unsigned PCM `samples` at `rate`, blocking until they
+    /// have all been played.
+    ///
+    /// Each sample lasts a whole number of [`CARRIER_RATE`] periods, so
+    /// `rate` is rounded to 62.5kHz divided by a whole number, such as
+    /// 7812.5Hz or 15625Hz.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` isn't in the range 4Hz to 62.5kHz.
+    pub fn play(&mut self, samples: &[u8], rate: HertzU32) {
+        assert!(
+            (4..=CARRIER_RATE).contains(&rate.raw()),
+            "unsupported sample rate"
+        );
+        self.stop();
+        self.pwm.prescaler.write(|w| w.prescaler().div_1());
+        self.pwm
+            .countertop
+            .write(|w| unsafe { w.countertop().bits(256) });
+        let periods = CARRIER_RATE / rate.raw();
+        self.pwm
+            .seq0
+            .refresh
+            .write(|w| unsafe { w.bits(periods - 1) });
+
+        // Convert a chunk while the previous one plays
+        for (i, chunk) in samples.chunks(CHUNK_LEN).enumerate() {
+            let buffer = &mut self.buffers[i % 2];
+            for (duty, &sample) in buffer.iter_mut().zip(chunk) {
+                *duty = u16::from(sample);
+            }
+            if i > 0 {
+                self.wait();
+            }
+            self.start(self.buffers[i % 2].as_ptr(), chunk.len());
+        }
+        if !samples.is_empty() {
+            self.wait();
+        }
+        self.stop();
+    }
+
+    /// Silences the speaker.
+    pub fn stop(&mut self) {
+        if self.pwm.events_seqstarted[0].read().bits() == 0 {
+            return;
+        }
+        self.pwm.events_stopped.reset();
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_stopped.read().bits() == 0 {}
+        compiler_fence(Ordering::SeqCst);
+        self.pwm.events_seqstarted[0].reset();
+    }
+
+    /// Starts playing `len` duty cycles from `ptr`, in RAM.
+    fn start(&self, ptr: *const u16, len: usize) {
+        self.pwm.seq0.ptr.write(|w| unsafe { w.bits(ptr as u32) });
+        self.pwm.seq0.cnt.write(|w| unsafe { w.bits(len as u32) });
+        self.pwm.events_seqend[0].reset();
+        self.pwm.events_seqstarted[0].reset();
+        // The duty cycles are read by EasyDMA, behind the compiler's back
+        compiler_fence(Ordering::SeqCst);
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_seqstarted[0].read().bits() == 0 {}
+    }
+
+    /// Waits for the current sequence to end.
+    fn wait(&self) {
+        while self.pwm.events_seqend[0].read().bits() == 0 {}
+        compiler_fence(Ordering::SeqCst);
+    }
+}
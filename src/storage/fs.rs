@@ -0,0 +1,649 @@
+//! A flat file system compatible with MicroPython's.
+//!
+//! MicroPython on the micro:bit keeps its files in 128-byte chunks of
+//! flash, numbered from 1. The first byte of a chunk is a marker: `0xff`
+//! if it is unused, `0` once freed, `0xfe` for the first chunk of a file
+//! and, for the others, the number of the previous chunk of the file. The
+//! last byte is the number of the next chunk, or `0xff` for the last one.
+//! The 126 bytes in between hold the data, which in the first chunk comes
+//! after a header: the offset of the end of the file in its last chunk, the
+//! length of the name, and the name.
+//!
+//! Removing a file only frees its chunks. A page is erased, to make its
+//! chunks usable again, once there are no unused chunks left: if it still
+//! holds chunks in use, they are copied to the last page of the region
+//! meanwhile, which isn't used for files.
+//!
+//! No word is written more than twice between erases, as the nRF52833
+//! allows: the first word of a chunk, with its marker, is written with the
+//! first data bytes, or once the file is closed for the first chunk of a
+//! file, and then only when the chunk is freed.
+//!
+//! ```
+//! use microbit::storage::{
+//!     fs::{Error, FileSystem},
+//!     RamFlash, PAGE_SIZE,
+//! };
+//!
+//! let mut memory = [0xffff_ffff; 4 * PAGE_SIZE / 4];
+//! let mut fs = FileSystem::new(RamFlash::new(&mut memory));
+//! fs.create("main.py")
+//!     .unwrap()
+//!     .write(b"from microbit import *\ndisplay.scroll('Hello')\n")
+//!     .unwrap();
+//! fs.create("empty.txt").unwrap();
+//!
+//! let mut file = fs.open("main.py").unwrap();
+//! assert_eq!(file.size(), 47);
+//! let mut buf = [0; 64];
+//! let len = file.read(&mut buf);
+//! assert_eq!(&buf[..23], b"from microbit import *\n");
+//! assert_eq!(len, 47);
+//!
+//! let mut names = fs.list().map(|file| (file.name().len(), file.size()));
+//! assert_eq!(names.next(), Some((7, 47)));
+//! assert_eq!(names.next(), Some((9, 0)));
+//! assert_eq!(names.next(), None);
+//!
+//! // Freed chunks are reclaimed as files are rewritten
+//! for i in 0..100u8 {
+//!     fs.create("data.bin").unwrap().write(&[i; 600]).unwrap();
+//! }
+//! let mut file = fs.open("data.bin").unwrap();
+//! assert_eq!(file.read(&mut buf), 64);
+//! assert_eq!(buf, [99; 64]);
+//!
+//! // A file which wasn't closed when the power was lost is removed
+//! let mut flash = fs.free();
+//! flash.lose_power_after(5);
+//! let mut fs = FileSystem::new(flash);
+//! fs.create("log.txt").unwrap().write(&[b'.'; 200]).unwrap();
+//! let mut fs = FileSystem::new(RamFlash::new(&mut memory));
+//! assert_eq!(fs.open("log.txt").err(), Some(Error::NotFound));
+//! assert!(fs.open("main.py").is_ok());
+//!
+//! fs.remove("main.py").unwrap();
+//! assert_eq!(fs.list().count(), 2);
+//! ```
+
+use core::str;
+
+use super::{Flash, PAGE_SIZE};
+
+/// The longest file name, in bytes.
+pub const MAX_NAME_LEN: usize = 120;
+
+/// The size of a chunk.
+const CHUNK_LEN: usize = 128;
+/// The number of data bytes in a chunk, between the marker and the number
+/// of the next chunk.
+const DATA_LEN: usize = 126;
+/// The number of chunks in a page.
+const PAGE_CHUNKS: usize = PAGE_SIZE / CHUNK_LEN;
+/// The most chunks, as the highest numbers are markers.
+const MAX_CHUNKS: usize = 252;
+
+/// The marker of a freed chunk.
+const FREED: u8 = 0;
+/// The marker of the copy of a page's chunks in the last page, followed by
+/// the number of the page.
+const PERSISTENT_DATA: u8 = 0xfd;
+/// The marker of the first chunk of a file.
+const FILE_START: u8 = 0xfe;
+/// The marker of an unused chunk.
+const UNUSED: u8 = 0xff;
+/// The number of the chunk after the last one of a file.
+const END: u8 = 0xff;
+
+/// The value of an erased word.
+const ERASED: u32 = 0xffff_ffff;
+
+/// An error from a [`FileSystem`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The name is empty or longer than [`MAX_NAME_LEN`].
+    InvalidName,
+    /// There is no file with that name.
+    NotFound,
+    /// There are no chunks left.
+    Full,
+}
+
+/// A file system on a [`Flash`] region of at least two pages.
+///
+/// Up to 252 chunks, in all the pages but the last one, hold files.
+///
+/// # Example
+///
+/// Each word of flash is written at most twice between erases:
+///
+/// ```
+/// use microbit::storage::{fs::FileSystem, Flash, RamFlash, PAGE_SIZE};
+///
+/// /// Flash which checks how many times each word is written.
+/// struct CountingFlash<'a> {
+///     flash: RamFlash<'a>,
+///     writes: Vec<u8>,
+/// }
+///
+/// impl Flash for CountingFlash<'_> {
+///     fn page_count(&self) -> usize {
+///         self.flash.page_count()
+///     }
+///
+///     fn read_word(&self, offset: usize) -> u32 {
+///         self.flash.read_word(offset)
+///     }
+///
+///     fn write_word(&mut self, offset: usize, word: u32) {
+///         self.writes[offset / 4] += 1;
+///         assert!(self.writes[offset / 4] <= 2, "word {:#x} written 3 times", offset);
+///         self.flash.write_word(offset, word);
+///     }
+///
+///     fn erase_page(&mut self, page: usize) {
+///         self.writes[page * PAGE_SIZE / 4..(page + 1) * PAGE_SIZE / 4].fill(0);
+///         self.flash.erase_page(page);
+///     }
+/// }
+///
+/// let mut memory = [0xffff_ffff; 3 * PAGE_SIZE / 4];
+/// let flash = CountingFlash {
+///     flash: RamFlash::new(&mut memory),
+///     writes: vec![0; 3 * PAGE_SIZE / 4],
+/// };
+/// let mut fs = FileSystem::new(flash);
+/// fs.create("config.txt").unwrap().write(b"volume=5").unwrap();
+/// for i in 0..50u8 {
+///     fs.create("data.bin").unwrap().write(&[i; 300]).unwrap();
+///     fs.create("log.txt").unwrap().write(&[i; 150]).unwrap();
+///     fs.remove("log.txt").unwrap();
+/// }
+/// assert!(fs.open("config.txt").is_ok());
+/// ```
+pub struct FileSystem<F: Flash> {
+    flash: F,
+    /// The number of chunks.
+    chunks: u8,
+    /// The chunk to start looking for an unused chunk from.
+    next: u8,
+    /// The first chunk of the file open for writing, which isn't marked
+    /// until the file is closed.
+    open: Option<u8>,
+}
+
+impl<F: Flash> FileSystem<F> {
+    /// Opens the file system on `flash`, completing any operation
+    /// interrupted by a reset, and removing files which weren't closed.
+    ///
+    /// Erased flash is an empty file system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `flash` has fewer than two pages.
+    pub fn new(flash: F) -> Self {
+        assert!(flash.page_count() >= 2, "too few pages");
+        let chunks = ((flash.page_count() - 1) * PAGE_CHUNKS).min(MAX_CHUNKS);
+        let mut fs = FileSystem {
+            flash,
+            chunks: chunks as u8,
+            next: 1,
+            open: None,
+        };
+        fs.mount();
+        fs
+    }
+
+    /// Gives the [`Flash`] back.
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// Opens a file for reading.
+    pub fn open(&self, name: &str) -> Result<ReadFile<'_, F>, Error> {
+        let start = self.find(name)?;
+        Ok(ReadFile {
+            fs: self,
+            start,
+            chunk: start,
+            position: 2 + name.len(),
+            end: self.data(start, 0),
+        })
+    }
+
+    /// Creates a file, replacing any file with the same name, and opens it
+    /// for writing.
+    ///
+    /// The file is closed when the [`WriteFile`] is dropped. Until then,
+    /// it can't be opened, and it is removed if the micro:bit is reset.
+    pub fn create(&mut self, name: &str) -> Result<WriteFile<'_, F>, Error> {
+        match self.remove(name) {
+            Ok(()) | Err(Error::NotFound) => {}
+            Err(error) => return Err(error),
+        }
+        let start = self.allocate()?;
+        self.open = Some(start);
+        // The first word is written with the end of the file, when closing
+        let name = name.as_bytes();
+        let mut file = WriteFile {
+            fs: self,
+            start,
+            chunk: start,
+            position: 4,
+            pending: None,
+            first_word: u32::from_le_bytes([FILE_START, END, name.len() as u8, name[0]]),
+        };
+        for &byte in &name[1..] {
+            file.put(byte);
+        }
+        Ok(file)
+    }
+
+    /// Removes a file.
+    pub fn remove(&mut self, name: &str) -> Result<(), Error> {
+        let start = self.find(name)?;
+        self.free_chunks(start);
+        Ok(())
+    }
+
+    /// Returns an iterator over the files.
+    ///
+    /// Files whose names aren't UTF-8 are skipped.
+    pub fn list(&self) -> Files<'_, F> {
+        Files { fs: self, chunk: 1 }
+    }
+
+    /// Returns the first chunk of a file.
+    fn find(&self, name: &str) -> Result<u8, Error> {
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(Error::InvalidName);
+        }
+        (1..=self.chunks)
+            .find(|&chunk| {
+                self.is_file(chunk)
+                    && usize::from(self.data(chunk, 1)) == name.len()
+                    && name
+                        .bytes()
+                        .enumerate()
+                        .all(|(i, byte)| self.data(chunk, 2 + i) == byte)
+            })
+            .ok_or(Error::NotFound)
+    }
+
+    /// Returns whether `previous` is in use and `chunk` is its next chunk.
+    fn is_linked(&self, previous: u8, chunk: u8) -> bool {
+        previous <= self.chunks
+            && !matches!(self.marker(previous), FREED | UNUSED)
+            && self.next_chunk(previous) == Some(chunk)
+    }
+
+    /// Returns whether `chunk` is the first chunk of a closed file.
+    fn is_file(&self, chunk: u8) -> bool {
+        self.marker(chunk) == FILE_START && self.data(chunk, 0) != END
+    }
+
+    /// Returns the size of the file starting at `start`.
+    fn size_of(&self, start: u8) -> usize {
+        let mut size = 0;
+        let mut chunk = start;
+        while let Some(next) = self.next_chunk(chunk) {
+            size += DATA_LEN;
+            chunk = next;
+        }
+        let header = 2 + usize::from(self.data(start, 1));
+        (size + usize::from(self.data(start, 0)).min(DATA_LEN)).saturating_sub(header)
+    }
+
+    /// Returns the chunk after `chunk` in its file.
+    fn next_chunk(&self, chunk: u8) -> Option<u8> {
+        let next = self.byte(self.offset(chunk) + CHUNK_LEN - 1);
+        if next != END && (1..=self.chunks).contains(&next) && self.marker(next) == chunk {
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    /// Returns an unused chunk, reclaiming freed chunks if there are none.
+    fn allocate(&mut self) -> Result<u8, Error> {
+        let mut reclaimed = false;
+        loop {
+            for i in 0..self.chunks {
+                let chunk = (self.next - 1 + i) % self.chunks + 1;
+                if self.marker(chunk) == UNUSED && self.open != Some(chunk) {
+                    self.next = chunk % self.chunks + 1;
+                    return Ok(chunk);
+                }
+            }
+            // Reclaiming a page leaves unused chunks
+            if reclaimed || !self.reclaim() {
+                return Err(Error::Full);
+            }
+            reclaimed = true;
+        }
+    }
+
+    /// Erases the page with the most freed chunks, keeping its chunks in
+    /// use, and returns whether there was one.
+    fn reclaim(&mut self) -> bool {
+        let pages = usize::from(self.chunks).div_ceil(PAGE_CHUNKS);
+        let freed = |fs: &Self, page: usize| {
+            fs.page_chunks(page)
+                .filter(|&chunk| fs.marker(chunk) == FREED)
+                .count()
+        };
+        let page = match (0..pages).max_by_key(|&page| freed(self, page)) {
+            Some(page) if freed(self, page) > 0 => page,
+            _ => return false,
+        };
+        if self
+            .page_chunks(page)
+            .all(|chunk| matches!(self.marker(chunk), FREED | UNUSED) && self.open != Some(chunk))
+        {
+            self.flash.erase_page(page);
+            return true;
+        }
+
+        // Copy the chunks in use to the last page, marking one of the
+        // freed chunks' places with the number of the page
+        let copy = self.flash.page_count() - 1;
+        self.erase(copy);
+        let mut mark = None;
+        for (slot, chunk) in self.page_chunks(page).enumerate() {
+            let offset = copy * PAGE_SIZE + slot * CHUNK_LEN;
+            match self.marker(chunk) {
+                _ if self.open == Some(chunk) => self.copy_chunk(self.offset(chunk), offset),
+                UNUSED => {}
+                FREED => mark = Some(offset),
+                _ => self.copy_chunk(self.offset(chunk), offset),
+            }
+        }
+        // Once all the chunks in use are copied
+        if let Some(offset) = mark {
+            let word = u32::from(PERSISTENT_DATA) | (page as u32) << 8 | 0xffff_0000;
+            self.flash.write_word(offset, word);
+        }
+        self.restore(page);
+        true
+    }
+
+    /// Erases `page` and copies its chunks in use back from the last page,
+    /// then erases the last page.
+    fn restore(&mut self, page: usize) {
+        let copy = self.flash.page_count() - 1;
+        self.flash.erase_page(page);
+        for (slot, chunk) in self.page_chunks(page).enumerate() {
+            let offset = copy * PAGE_SIZE + slot * CHUNK_LEN;
+            if self.open == Some(chunk) || !matches!(self.byte(offset), UNUSED | PERSISTENT_DATA) {
+                self.copy_chunk(offset, page * PAGE_SIZE + slot * CHUNK_LEN);
+            }
+        }
+        self.flash.erase_page(copy);
+    }
+
+    /// Completes any interrupted page erase, and removes files which
+    /// weren't closed and chunks which aren't part of a file.
+    fn mount(&mut self) {
+        let copy = self.flash.page_count() - 1;
+        let pages = usize::from(self.chunks).div_ceil(PAGE_CHUNKS);
+        let page = (0..PAGE_CHUNKS)
+            .map(|slot| copy * PAGE_SIZE + slot * CHUNK_LEN)
+            .find(|&offset| self.byte(offset) == PERSISTENT_DATA)
+            .map(|offset| usize::from(self.byte(offset + 1)));
+        match page {
+            Some(page) if page < pages => self.restore(page),
+            _ => self.erase(copy),
+        }
+
+        for chunk in 1..=self.chunks {
+            match self.marker(chunk) {
+                FILE_START if !self.is_file(chunk) => self.free_chunks(chunk),
+                // The first chunk of a file which wasn't closed
+                UNUSED if !self.is_erased(chunk) => self.set_marker(chunk, FREED),
+                _ => {}
+            }
+        }
+        // Freeing a chunk can leave the next one without a previous chunk
+        let mut freed = true;
+        while freed {
+            freed = false;
+            for chunk in 1..=self.chunks {
+                let previous = self.marker(chunk);
+                if !matches!(previous, FREED | FILE_START | UNUSED)
+                    && !self.is_linked(previous, chunk)
+                {
+                    self.set_marker(chunk, FREED);
+                    freed = true;
+                }
+            }
+        }
+    }
+
+    /// Frees the chunks of a file, from the first one.
+    fn free_chunks(&mut self, start: u8) {
+        let mut chunk = Some(start);
+        while let Some(current) = chunk {
+            chunk = self.next_chunk(current);
+            self.set_marker(current, FREED);
+        }
+    }
+
+    /// Returns the chunks of a page.
+    fn page_chunks(&self, page: usize) -> impl Iterator<Item = u8> {
+        let first = page * PAGE_CHUNKS + 1;
+        let last = (first + PAGE_CHUNKS - 1).min(usize::from(self.chunks));
+        (first..=last).map(|chunk| chunk as u8)
+    }
+
+    /// Returns whether all the words of a chunk are erased.
+    fn is_erased(&self, chunk: u8) -> bool {
+        let start = self.offset(chunk);
+        (start..start + CHUNK_LEN)
+            .step_by(4)
+            .all(|offset| self.flash.read_word(offset) == ERASED)
+    }
+
+    /// Copies a chunk, whose place is erased.
+    fn copy_chunk(&mut self, from: usize, to: usize) {
+        for i in (0..CHUNK_LEN).step_by(4) {
+            let word = self.flash.read_word(from + i);
+            if word != ERASED {
+                self.flash.write_word(to + i, word);
+            }
+        }
+    }
+
+    /// Erases a page unless it is already erased.
+    fn erase(&mut self, page: usize) {
+        let start = page * PAGE_SIZE;
+        if (0..PAGE_SIZE / 4).any(|i| self.flash.read_word(start + 4 * i) != ERASED) {
+            self.flash.erase_page(page);
+        }
+    }
+
+    fn marker(&self, chunk: u8) -> u8 {
+        self.byte(self.offset(chunk))
+    }
+
+    fn set_marker(&mut self, chunk: u8, marker: u8) {
+        let offset = self.offset(chunk);
+        self.flash.write_word(offset, !u32::from(!marker));
+    }
+
+    /// Returns a data byte of a chunk.
+    fn data(&self, chunk: u8, index: usize) -> u8 {
+        self.byte(self.offset(chunk) + 1 + index)
+    }
+
+    fn byte(&self, offset: usize) -> u8 {
+        (self.flash.read_word(offset & !3) >> (8 * (offset % 4))) as u8
+    }
+
+    fn offset(&self, chunk: u8) -> usize {
+        (usize::from(chunk) - 1) * CHUNK_LEN
+    }
+}
+
+/// A file open for reading.
+pub struct ReadFile<'a, F: Flash> {
+    fs: &'a FileSystem<F>,
+    start: u8,
+    chunk: u8,
+    /// The index of the next byte in the chunk's data.
+    position: usize,
+    /// The end of the file in its last chunk.
+    end: u8,
+}
+
+impl<F: Flash> ReadFile<'_, F> {
+    /// Reads from the file into `buf`, returning the number of bytes read,
+    /// which is less than the length of `buf` only at the end of the file.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        while len < buf.len() {
+            let next = self.fs.next_chunk(self.chunk);
+            let limit = match next {
+                Some(_) => DATA_LEN,
+                None => usize::from(self.end).min(DATA_LEN),
+            };
+            if self.position < limit {
+                buf[len] = self.fs.data(self.chunk, self.position);
+                self.position += 1;
+                len += 1;
+            } else if let Some(next) = next {
+                self.chunk = next;
+                self.position = 0;
+            } else {
+                break;
+            }
+        }
+        len
+    }
+
+    /// Returns the size of the file, in bytes.
+    pub fn size(&self) -> usize {
+        self.fs.size_of(self.start)
+    }
+}
+
+/// A file open for writing, closed when dropped.
+pub struct WriteFile<'a, F: Flash> {
+    fs: &'a mut FileSystem<F>,
+    start: u8,
+    chunk: u8,
+    /// The offset of the next byte in the chunk.
+    position: usize,
+    /// The offset and value of a word yet to be written.
+    pending: Option<(usize, u32)>,
+    /// The first word of the first chunk, written when closing.
+    first_word: u32,
+}
+
+impl<F: Flash> WriteFile<'_, F> {
+    /// Appends `data` to the file.
+    ///
+    /// If the file system is full, the data which fits is written, and
+    /// [`Error::Full`] is returned.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        for &byte in data {
+            if self.position == CHUNK_LEN - 1 {
+                self.flush();
+                let next = self.fs.allocate()?;
+                self.put(next);
+                self.flush();
+                // The marker is written with the first data bytes
+                let previous = self.chunk;
+                self.chunk = next;
+                self.position = 0;
+                self.put(previous);
+            }
+            self.put(byte);
+        }
+        Ok(())
+    }
+
+    /// Writes a byte at the current position in the chunk.
+    fn put(&mut self, byte: u8) {
+        let offset = self.fs.offset(self.chunk) + self.position;
+        let word = offset & !3;
+        if !matches!(self.pending, Some((pending, _)) if pending == word) {
+            self.flush();
+            self.pending = Some((word, ERASED));
+        }
+        if let Some((_, value)) = &mut self.pending {
+            *value &= !(u32::from(!byte) << (8 * (offset % 4)));
+        }
+        self.position += 1;
+    }
+
+    fn flush(&mut self) {
+        if let Some((offset, word)) = self.pending.take() {
+            self.fs.flash.write_word(offset, word);
+        }
+    }
+}
+
+impl<F: Flash> Drop for WriteFile<'_, F> {
+    fn drop(&mut self) {
+        self.flush();
+        // The end of the file, in the chunk's data
+        let end = (self.position - 1) as u8;
+        let first_word = self.first_word & !0xff00 | u32::from(end) << 8;
+        self.fs
+            .flash
+            .write_word(self.fs.offset(self.start), first_word);
+        self.fs.open = None;
+    }
+}
+
+/// An iterator over the files in a [`FileSystem`].
+pub struct Files<'a, F: Flash> {
+    fs: &'a FileSystem<F>,
+    chunk: u8,
+}
+
+impl<F: Flash> Iterator for Files<'_, F> {
+    type Item = Metadata;
+
+    fn next(&mut self) -> Option<Metadata> {
+        while self.chunk <= self.fs.chunks {
+            let chunk = self.chunk;
+            self.chunk += 1;
+            if !self.fs.is_file(chunk) {
+                continue;
+            }
+            let mut metadata = Metadata {
+                name: [0; MAX_NAME_LEN],
+                name_len: usize::from(self.fs.data(chunk, 1)).min(MAX_NAME_LEN),
+                size: self.fs.size_of(chunk),
+            };
+            for (i, byte) in metadata.name[..metadata.name_len].iter_mut().enumerate() {
+                *byte = self.fs.data(chunk, 2 + i);
+            }
+            if str::from_utf8(&metadata.name[..metadata.name_len]).is_ok() {
+                return Some(metadata);
+            }
+        }
+        None
+    }
+}
+
+/// The name and size of a file.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    name: [u8; MAX_NAME_LEN],
+    name_len: usize,
+    size: usize,
+}
+
+impl Metadata {
+    /// Returns the name of the file.
+    pub fn name(&self) -> &str {
+        str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    /// Returns the size of the file, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
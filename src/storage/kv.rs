@@ -0,0 +1,416 @@
+//! A key-value store on flash pages.
+//!
+//! The store is a log of records, appended to the active page. When it is
+//! full, the next page becomes the active page: the values still in use
+//! from the oldest page are copied into it, and the oldest page is erased.
+//! The pages are used in turn, so they wear evenly.
+//!
+//! Each page starts with a header word, holding a magic number and a
+//! sequence number which orders the pages, and a state word which is
+//! cleared once the values from the oldest page have been copied. Each
+//! record is a header word, holding its key, length and kind, the value,
+//! padded to whole words, and a checksum written last: a record whose
+//! checksum doesn't match was interrupted, and is ignored.
+
+use core::iter;
+
+use super::{Flash, PAGE_SIZE};
+
+/// The longest value.
+pub const MAX_VALUE_LEN: usize = 128;
+
+/// The value of an erased word.
+const ERASED: u32 = 0xffff_ffff;
+/// The magic number in the top half of a page header.
+const PAGE_MAGIC: u32 = 0x4b56;
+/// The page state once the values from the oldest page have been copied.
+const PAGE_READY: u32 = 0;
+/// The offset of the first record in a page.
+const FIRST_RECORD: usize = 8;
+/// The key of an erased record header.
+const RESERVED_KEY: u16 = 0xffff;
+
+/// The kind of a record holding a value.
+const KIND_VALUE: u32 = 0x5a;
+/// The kind of a record marking a key as removed.
+const KIND_REMOVED: u32 = 0xa5;
+
+/// An error from a [`KeyValueStore`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The key is `0xffff`, which is reserved.
+    InvalidKey,
+    /// The value is longer than [`MAX_VALUE_LEN`].
+    ValueTooLong,
+    /// There is no room for the value, even after reclaiming the space
+    /// used by old values.
+    Full,
+}
+
+/// A record found in flash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Record {
+    page: usize,
+    offset: usize,
+    key: u16,
+    len: usize,
+    removed: bool,
+}
+
+/// Returns the number of words in a record with a value of `len` bytes.
+fn record_words(len: usize) -> usize {
+    2 + len.div_ceil(4)
+}
+
+/// Returns the checksum of a record's header and value words, which is
+/// never [`ERASED`].
+fn checksum(words: impl Iterator<Item = u32>) -> u32 {
+    // FNV-1a
+    let mut hash: u32 = 0x811c_9dc5;
+    for word in words {
+        for byte in word.to_le_bytes().iter() {
+            hash ^= u32::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    if hash == ERASED {
+        0
+    } else {
+        hash
+    }
+}
+
+/// A key-value store on a [`Flash`] region of at least two pages.
+///
+/// Keys are 16-bit numbers, except `0xffff`, and values are up to
+/// [`MAX_VALUE_LEN`] bytes. All the values must fit in one page, less some
+/// room for new values.
+///
+/// Writing a value never erases or overwrites the previous one: if a reset
+/// or power loss interrupts it, the store keeps the previous value.
+///
+/// ```
+/// use microbit::storage::{KeyValueStore, RamFlash, PAGE_SIZE};
+///
+/// let mut memory = [0xffff_ffff; 2 * PAGE_SIZE / 4];
+/// let mut store = KeyValueStore::new(RamFlash::new(&mut memory));
+/// store.set(1, b"calibration").unwrap();
+///
+/// // A power loss while setting a new value keeps the old one
+/// let mut flash = store.free();
+/// flash.lose_power_after(2);
+/// let mut store = KeyValueStore::new(flash);
+/// store.set(1, b"new calibration").unwrap();
+///
+/// let mut store = KeyValueStore::new(RamFlash::new(&mut memory));
+/// let mut buf = [0; 32];
+/// let len = store.get(1, &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"calibration");
+///
+/// // The pages are reused as values change
+/// for i in 0..1000u32 {
+///     store.set(2, &i.to_le_bytes()).unwrap();
+/// }
+/// assert_eq!(store.get(2, &mut buf), Some(4));
+/// assert_eq!(buf[..4], 999u32.to_le_bytes());
+/// assert_eq!(store.get(1, &mut buf), Some(11));
+///
+/// store.remove(1).unwrap();
+/// assert_eq!(store.get(1, &mut buf), None);
+/// ```
+pub struct KeyValueStore<F: Flash> {
+    flash: F,
+    /// The page new records are written to.
+    active: usize,
+    /// The sequence number of the active page.
+    sequence: u16,
+    /// The offset of the next record in the active page.
+    write: usize,
+}
+
+impl<F: Flash> KeyValueStore<F> {
+    /// Opens the store on `flash`, formatting it if it doesn't hold a
+    /// store, and completing any operation interrupted by a reset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `flash` has fewer than two pages.
+    pub fn new(flash: F) -> Self {
+        assert!(flash.page_count() >= 2, "too few pages");
+        let mut store = KeyValueStore {
+            flash,
+            active: 0,
+            sequence: 0,
+            write: FIRST_RECORD,
+        };
+        store.mount();
+        store
+    }
+
+    /// Gives the [`Flash`] back.
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// Copies the value of `key` into `buf`, returning its length, or
+    /// `None` if the key has no value.
+    ///
+    /// The value is truncated if `buf` is too short.
+    pub fn get(&self, key: u16, buf: &mut [u8]) -> Option<usize> {
+        let record = self.latest(key).filter(|record| !record.removed)?;
+        let start = self.address(record.page, record.offset + 4);
+        let len = record.len.min(buf.len());
+        for (i, chunk) in buf[..len].chunks_mut(4).enumerate() {
+            let word = self.flash.read_word(start + 4 * i).to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+        Some(record.len)
+    }
+
+    /// Returns whether `key` has a value.
+    pub fn contains(&self, key: u16) -> bool {
+        self.latest(key).is_some_and(|record| !record.removed)
+    }
+
+    /// Sets the value of `key`.
+    ///
+    /// Nothing is written if the value is unchanged.
+    pub fn set(&mut self, key: u16, value: &[u8]) -> Result<(), Error> {
+        if key == RESERVED_KEY {
+            return Err(Error::InvalidKey);
+        }
+        if value.len() > MAX_VALUE_LEN {
+            return Err(Error::ValueTooLong);
+        }
+        let mut words = [ERASED; MAX_VALUE_LEN / 4];
+        for (word, chunk) in words.iter_mut().zip(value.chunks(4)) {
+            let mut bytes = [0xff; 4];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            *word = u32::from_le_bytes(bytes);
+        }
+        let words = &words[..value.len().div_ceil(4)];
+        if let Some(record) = self.latest(key).filter(|record| !record.removed) {
+            let start = self.address(record.page, record.offset + 4);
+            let unchanged = record.len == value.len()
+                && (0..words.len()).all(|i| self.flash.read_word(start + 4 * i) == words[i]);
+            if unchanged {
+                return Ok(());
+            }
+        }
+        self.append(key, KIND_VALUE, value.len(), words)
+    }
+
+    /// Removes the value of `key`, if it has one.
+    pub fn remove(&mut self, key: u16) -> Result<(), Error> {
+        if !self.contains(key) {
+            return Ok(());
+        }
+        self.append(key, KIND_REMOVED, 0, &[])
+    }
+
+    /// Appends a record to the active page, moving on to the next pages if
+    /// there isn't room.
+    fn append(&mut self, key: u16, kind: u32, len: usize, words: &[u32]) -> Result<(), Error> {
+        let mut rotations = 0;
+        while self.write + 4 * record_words(len) > PAGE_SIZE {
+            // By then, the values from every other page have been copied
+            if rotations == self.flash.page_count() - 1 {
+                return Err(Error::Full);
+            }
+            self.rotate();
+            rotations += 1;
+        }
+        let header = u32::from(key) | (len as u32) << 16 | kind << 24;
+        let start = self.address(self.active, self.write);
+        self.flash.write_word(start, header);
+        for (i, word) in words.iter().enumerate() {
+            self.flash.write_word(start + 4 * (i + 1), *word);
+        }
+        let sum = checksum(iter::once(header).chain(words.iter().copied()));
+        self.flash.write_word(start + 4 * (words.len() + 1), sum);
+        self.write += 4 * record_words(len);
+        Ok(())
+    }
+
+    /// Makes the next page the active page, copying the values in use from
+    /// the oldest page into it, then erasing the oldest page.
+    fn rotate(&mut self) {
+        let pages = self.flash.page_count();
+        let next = (self.active + 1) % pages;
+        self.erase(next);
+        self.sequence = self.sequence.wrapping_add(1);
+        self.flash.write_word(
+            self.address(next, 0),
+            PAGE_MAGIC << 16 | u32::from(self.sequence),
+        );
+        self.active = next;
+        self.write = FIRST_RECORD;
+
+        let oldest = (next + 1) % pages;
+        if self.sequence_of(oldest).is_some() {
+            let mut offset = FIRST_RECORD;
+            while let Some((record, next_offset)) = self.record_at(oldest, offset) {
+                if let Some(record) = record.filter(|record| !record.removed) {
+                    if self.latest(record.key) == Some(record) {
+                        self.copy(record);
+                    }
+                }
+                offset = next_offset;
+            }
+        }
+        self.flash.write_word(self.address(next, 4), PAGE_READY);
+        self.erase(oldest);
+    }
+
+    /// Copies a record to the active page, which has room for it.
+    fn copy(&mut self, record: Record) {
+        let mut words = [ERASED; MAX_VALUE_LEN / 4];
+        let words = &mut words[..record.len.div_ceil(4)];
+        let start = self.address(record.page, record.offset + 4);
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = self.flash.read_word(start + 4 * i);
+        }
+        // There is always room for the values from one page
+        let _ = self.append(record.key, KIND_VALUE, record.len, words);
+    }
+
+    /// Finds the active page and completes any interrupted operation.
+    fn mount(&mut self) {
+        let pages = self.flash.page_count();
+        for page in 0..pages {
+            let header = self.flash.read_word(self.address(page, 0));
+            if header != ERASED && self.sequence_of(page).is_none() {
+                self.flash.erase_page(page);
+            }
+        }
+        let active = match self.newest() {
+            Some(active) => active,
+            None => {
+                // Format the store
+                self.flash.write_word(self.address(0, 0), PAGE_MAGIC << 16);
+                self.flash.write_word(self.address(0, 4), PAGE_READY);
+                0
+            }
+        };
+        let ready = self.flash.read_word(self.address(active, 4)) == PAGE_READY;
+        let previous = (active + pages - 1) % pages;
+        let active = if ready {
+            active
+        } else if self.sequence_of(previous).is_some() {
+            // Copying the values from the oldest page was interrupted, but
+            // they are all still there: start again from the previous page
+            self.flash.erase_page(active);
+            previous
+        } else {
+            self.flash.write_word(self.address(active, 4), PAGE_READY);
+            active
+        };
+        self.active = active;
+        self.sequence = self.sequence_of(active).unwrap_or(0);
+        self.write = FIRST_RECORD;
+        while let Some((_, next_offset)) = self.record_at(active, self.write) {
+            self.write = next_offset;
+        }
+        if self.write < PAGE_SIZE
+            && self.flash.read_word(self.address(active, self.write)) != ERASED
+        {
+            // Don't write over a damaged header
+            self.write = PAGE_SIZE;
+        }
+        // Finish erasing the oldest page, if that was interrupted
+        self.erase((active + 1) % pages);
+    }
+
+    /// Returns the latest record for `key`.
+    fn latest(&self, key: u16) -> Option<Record> {
+        let pages = self.flash.page_count();
+        let mut latest = None;
+        // From the oldest page to the active one
+        for page in (1..=pages).map(|i| (self.active + i) % pages) {
+            if self.sequence_of(page).is_none() {
+                continue;
+            }
+            let mut offset = FIRST_RECORD;
+            while let Some((record, next_offset)) = self.record_at(page, offset) {
+                if let Some(record) = record.filter(|record| record.key == key) {
+                    latest = Some(record);
+                }
+                offset = next_offset;
+            }
+        }
+        latest
+    }
+
+    /// Reads the record at `offset` in `page`, returning it if it is
+    /// complete, and the offset of the next record.
+    ///
+    /// Returns `None` at the end of the records.
+    fn record_at(&self, page: usize, offset: usize) -> Option<(Option<Record>, usize)> {
+        if offset + 4 > PAGE_SIZE {
+            return None;
+        }
+        let start = self.address(page, offset);
+        let header = self.flash.read_word(start);
+        let kind = header >> 24;
+        let len = (header >> 16 & 0xff) as usize;
+        let next_offset = offset + 4 * record_words(len);
+        if header == ERASED
+            || !(kind == KIND_VALUE || kind == KIND_REMOVED)
+            || len > MAX_VALUE_LEN
+            || next_offset > PAGE_SIZE
+        {
+            // Nothing is written after a damaged header
+            return None;
+        }
+        let value_words = len.div_ceil(4);
+        let words = (0..=value_words).map(|i| self.flash.read_word(start + 4 * i));
+        let complete = checksum(words) == self.flash.read_word(start + 4 * (value_words + 1));
+        let record = Record {
+            page,
+            offset,
+            key: header as u16,
+            len,
+            removed: kind == KIND_REMOVED,
+        };
+        Some((Some(record).filter(|_| complete), next_offset))
+    }
+
+    /// Returns the page with the newest sequence number.
+    fn newest(&self) -> Option<usize> {
+        let mut newest: Option<(usize, u16)> = None;
+        for page in 0..self.flash.page_count() {
+            if let Some(sequence) = self.sequence_of(page) {
+                match newest {
+                    // Sequence numbers wrap around
+                    Some((_, newest_sequence))
+                        if (sequence.wrapping_sub(newest_sequence) as i16) <= 0 => {}
+                    _ => newest = Some((page, sequence)),
+                }
+            }
+        }
+        newest.map(|(page, _)| page)
+    }
+
+    /// Returns the sequence number of a page, or `None` if it doesn't have
+    /// a valid header.
+    fn sequence_of(&self, page: usize) -> Option<u16> {
+        let header = self.flash.read_word(self.address(page, 0));
+        if header >> 16 == PAGE_MAGIC {
+            Some(header as u16)
+        } else {
+            None
+        }
+    }
+
+    /// Erases a page unless it is already erased.
+    fn erase(&mut self, page: usize) {
+        let start = self.address(page, 0);
+        if (0..PAGE_SIZE / 4).any(|i| self.flash.read_word(start + 4 * i) != ERASED) {
+            self.flash.erase_page(page);
+        }
+    }
+
+    fn address(&self, page: usize, offset: usize) -> usize {
+        page * PAGE_SIZE + offset
+    }
+}
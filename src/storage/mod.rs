@@ -0,0 +1,76 @@
+//! Persistent storage in the micro:bit's flash.
+//!
+//! # Scope
+//!
+//! This module provides:
+//! - a [`KeyValueStore`], which keeps small values in a few flash pages,
+//!   spreading the erases over all of them and surviving a reset or power
+//!   loss at any point
+//! - a flat [file system](fs), compatible with MicroPython's
+//! - the [`Flash`] trait over the flash memory, with an implementation on
+//!   the [`NVMC`](Nvmc) and one in [RAM](RamFlash) for host tests.
+//!
+//! `memory.x` keeps the program out of the last [`STORAGE_PAGES`] pages of
+//! flash, from [`STORAGE_START`], for the store, and out of the
+//! [`FS_PAGES`] pages before them, from [`FS_START`], for the file system.
+//! The numbers of pages are set by the `MICROBIT_STORAGE_PAGES` and
+//! `MICROBIT_FS_PAGES` environment variables when building, 0 by default
+//! so that the flash is left to programs which don't use storage: set them
+//! to at least 2, such as 4 and 8.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::storage::{KeyValueStore, Nvmc, STORAGE_PAGES, STORAGE_START};
+//!
+//! const BOOT_COUNT: u16 = 1;
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let mut store = KeyValueStore::new(Nvmc::new(&p.NVMC, STORAGE_START, STORAGE_PAGES));
+//!
+//! let mut buf = [0; 4];
+//! let count = match store.get(BOOT_COUNT, &mut buf) {
+//!     Some(4) => u32::from_le_bytes(buf) + 1,
+//!     _ => 1,
+//! };
+//! store.set(BOOT_COUNT, &count.to_le_bytes()).unwrap();
+//! ```
+//!
+//! See a working example at `examples/storage_boot_count.rs`
+
+pub mod fs;
+mod kv;
+mod nvmc;
+mod ram;
+
+pub use kv::{Error, KeyValueStore, MAX_VALUE_LEN};
+pub use nvmc::{Nvmc, FS_PAGES, FS_START, STORAGE_PAGES, STORAGE_START};
+pub use ram::RamFlash;
+
+/// The size of a flash page, the unit of erasure, in bytes.
+#[cfg(feature = "v1")]
+pub const PAGE_SIZE: usize = 1024;
+/// The size of a flash page, the unit of erasure, in bytes.
+#[cfg(feature = "v2")]
+pub const PAGE_SIZE: usize = 4096;
+
+/// A region of flash memory, made of [`PAGE_SIZE`] pages of 32-bit words.
+///
+/// Offsets are in bytes from the start of the region, and must be multiples
+/// of 4.
+pub trait Flash {
+    /// Returns the number of pages in the region.
+    fn page_count(&self) -> usize;
+
+    /// Reads the word at `offset`.
+    fn read_word(&self, offset: usize) -> u32;
+
+    /// Writes the word at `offset`.
+    ///
+    /// Writing can only clear bits, so the word ends up as the bitwise AND
+    /// of its old value and `word`.
+    fn write_word(&mut self, offset: usize, word: u32);
+
+    /// Erases a page, setting all its bits.
+    fn erase_page(&mut self, page: usize);
+}
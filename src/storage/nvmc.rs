@@ -0,0 +1,90 @@
+//! Flash written and erased through the `NVMC`.
+
+use core::ptr;
+
+use crate::pac::NVMC;
+
+use super::{Flash, PAGE_SIZE};
+
+// Set by the `MICROBIT_STORAGE_PAGES` and `MICROBIT_FS_PAGES` environment
+// variables when building
+include!(concat!(env!("OUT_DIR"), "/storage.rs"));
+
+/// The end of the flash.
+#[cfg(feature = "v1")]
+const FLASH_END: usize = 0x4_0000;
+/// The end of the flash.
+#[cfg(feature = "v2")]
+const FLASH_END: usize = 0x8_0000;
+
+/// The start of the flash reserved for storage by `memory.x`.
+pub const STORAGE_START: usize = FLASH_END - STORAGE_PAGES * PAGE_SIZE;
+
+/// The start of the flash reserved for the file system by `memory.x`.
+pub const FS_START: usize = STORAGE_START - FS_PAGES * PAGE_SIZE;
+
+/// A region of the micro:bit's flash, written and erased through the `NVMC`.
+///
+/// The CPU stalls while the flash is written or erased: about 45µs for a
+/// word, and 22ms for a page on the v1 or 85ms on the v2. Interrupts are delayed meanwhile, which can
+/// upset time-critical peripherals such as the radio.
+pub struct Nvmc<'a> {
+    nvmc: &'a NVMC,
+    start: usize,
+    pages: usize,
+}
+
+impl<'a> Nvmc<'a> {
+    /// Returns the region of `pages` flash pages from address `start`.
+    ///
+    /// The region mustn't overlap the program: use [`STORAGE_START`] and
+    /// [`STORAGE_PAGES`], or [`FS_START`] and [`FS_PAGES`], unless
+    /// `memory.x` reserves other pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` isn't the start of a page.
+    pub fn new(nvmc: &'a NVMC, start: usize, pages: usize) -> Self {
+        assert!(start.is_multiple_of(PAGE_SIZE), "unaligned flash region");
+        Nvmc { nvmc, start, pages }
+    }
+
+    fn address(&self, offset: usize) -> usize {
+        assert!(offset.is_multiple_of(4) && offset < self.pages * PAGE_SIZE);
+        self.start + offset
+    }
+
+    fn wait_ready(&self) {
+        while self.nvmc.ready.read().ready().is_busy() {}
+    }
+}
+
+impl Flash for Nvmc<'_> {
+    fn page_count(&self) -> usize {
+        self.pages
+    }
+
+    fn read_word(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile(self.address(offset) as *const u32) }
+    }
+
+    fn write_word(&mut self, offset: usize, word: u32) {
+        let address = self.address(offset);
+        self.nvmc.config.write(|w| w.wen().wen());
+        self.wait_ready();
+        unsafe { ptr::write_volatile(address as *mut u32, word) };
+        self.wait_ready();
+        self.nvmc.config.write(|w| w.wen().ren());
+    }
+
+    fn erase_page(&mut self, page: usize) {
+        let address = self.address(page * PAGE_SIZE);
+        self.nvmc.config.write(|w| w.wen().een());
+        self.wait_ready();
+        self.nvmc
+            .erasepage()
+            .write(|w| unsafe { w.bits(address as u32) });
+        self.wait_ready();
+        self.nvmc.config.write(|w| w.wen().ren());
+    }
+}
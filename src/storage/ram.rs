@@ -0,0 +1,90 @@
+//! Flash emulated in RAM.
+
+use super::{Flash, PAGE_SIZE};
+
+/// The number of words in a page.
+const PAGE_WORDS: usize = PAGE_SIZE / 4;
+
+/// Flash emulated in RAM, for testing on the host.
+///
+/// Like real flash, writes can only clear bits, and only erasing sets them
+/// again. The memory outlives the `RamFlash`, so a reset can be emulated by
+/// using the same memory again.
+///
+/// A power loss can be emulated with
+/// [`lose_power_after()`](RamFlash::lose_power_after):
+///
+/// ```
+/// use microbit::storage::{Flash, RamFlash, PAGE_SIZE};
+///
+/// let mut memory = [0xffff_ffff; PAGE_SIZE / 4];
+/// let mut flash = RamFlash::new(&mut memory);
+/// flash.lose_power_after(1);
+/// flash.write_word(0, 0x1234_5678);
+/// flash.write_word(4, 0x1234_5678);
+/// assert_eq!(memory[..2], [0x1234_5678, 0xffff_ffff]);
+/// ```
+pub struct RamFlash<'a> {
+    memory: &'a mut [u32],
+    /// The number of writes and erases left before the power is lost.
+    power: Option<usize>,
+}
+
+impl<'a> RamFlash<'a> {
+    /// Returns flash emulated in `memory`, as it is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memory` isn't a whole number of pages.
+    pub fn new(memory: &'a mut [u32]) -> Self {
+        assert!(memory.len().is_multiple_of(PAGE_WORDS), "partial page");
+        RamFlash {
+            memory,
+            power: None,
+        }
+    }
+
+    /// Makes writes and erases have no effect after the next `operations`,
+    /// as if the power had been lost.
+    pub fn lose_power_after(&mut self, operations: usize) {
+        self.power = Some(operations);
+    }
+
+    /// Returns whether the next write or erase should happen, counting it.
+    fn powered(&mut self) -> bool {
+        match &mut self.power {
+            Some(0) => false,
+            Some(operations) => {
+                *operations -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+impl Flash for RamFlash<'_> {
+    fn page_count(&self) -> usize {
+        self.memory.len() / PAGE_WORDS
+    }
+
+    fn read_word(&self, offset: usize) -> u32 {
+        assert!(offset.is_multiple_of(4));
+        self.memory[offset / 4]
+    }
+
+    fn write_word(&mut self, offset: usize, word: u32) {
+        assert!(offset.is_multiple_of(4));
+        if self.powered() {
+            self.memory[offset / 4] &= word;
+        }
+    }
+
+    fn erase_page(&mut self, page: usize) {
+        if self.powered() {
+            for word in &mut self.memory[page * PAGE_WORDS..(page + 1) * PAGE_WORDS] {
+                *word = 0xffff_ffff;
+            }
+        }
+    }
+}
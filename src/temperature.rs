@@ -0,0 +1,256 @@
+//! The nRF51822's or nRF52833's die temperature sensor.
+//!
+//! # Scope
+//!
+//! This module provides a [`Thermometer`] driver for the `TEMP`
+//! peripheral, like MicroPython's `microbit.temperature()`, with:
+//! - blocking ([`temperature()`](Thermometer::temperature)) and async
+//!   ([`measure()`](Thermometer::measure)) measurements, with a 0.25°C
+//!   resolution
+//! - a calibration offset, which can be kept in a
+//!   [`KeyValueStore`](crate::storage::KeyValueStore) with the other
+//!   settings.
+//!
+//! The sensor measures the chip's temperature, which is a few degrees above
+//! the air's while the CPU or radio are busy.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::temperature::Thermometer;
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! let mut thermometer = Thermometer::new(p.TEMP);
+//! let temperature = thermometer.temperature();
+//!
+//! // in the TEMP interrupt handler, for async measurements
+//! microbit::temperature::handle_temp_interrupt();
+//! ```
+//!
+//! See a working example at `examples/temperature_serial.rs`
+
+use core::{
+    fmt,
+    future::Future,
+    ops::{Add, Sub},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    executor::WakerCell,
+    pac::TEMP,
+    storage::{self, Flash, KeyValueStore},
+};
+
+/// The key of the calibration offset in a [`KeyValueStore`].
+pub const OFFSET_KEY: u16 = 0x7e00;
+
+static WAKER: WakerCell = WakerCell::new();
+
+/// A temperature, in steps of 0.25°C.
+///
+/// ```
+/// use microbit::temperature::Temperature;
+///
+/// let temperature = Temperature::from_quarter_degrees(85);
+/// assert_eq!(temperature.degrees(), 21);
+/// assert_eq!(temperature.to_string(), "21.25");
+///
+/// let temperature = temperature - Temperature::from_degrees(23);
+/// assert_eq!(temperature.quarter_degrees(), -7);
+/// assert_eq!(temperature.degrees(), -1);
+/// assert_eq!(temperature.to_string(), "-1.75");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Temperature(i32);
+
+impl Temperature {
+    /// Returns a temperature of `quarter_degrees` quarters of a degree
+    /// Celsius.
+    pub const fn from_quarter_degrees(quarter_degrees: i32) -> Self {
+        Temperature(quarter_degrees)
+    }
+
+    /// Returns a temperature of `degrees` degrees Celsius.
+    pub const fn from_degrees(degrees: i32) -> Self {
+        Temperature(degrees * 4)
+    }
+
+    /// Returns the temperature in quarters of a degree Celsius.
+    pub fn quarter_degrees(self) -> i32 {
+        self.0
+    }
+
+    /// Returns the temperature in whole degrees Celsius, truncated like
+    /// MicroPython's `microbit.temperature()`.
+    pub fn degrees(self) -> i32 {
+        self.0 / 4
+    }
+
+    /// Returns the temperature in degrees Celsius.
+    pub fn celsius(self) -> f32 {
+        self.0 as f32 / 4.0
+    }
+}
+
+impl Add for Temperature {
+    type Output = Temperature;
+
+    fn add(self, other: Temperature) -> Temperature {
+        Temperature(self.0 + other.0)
+    }
+}
+
+impl Sub for Temperature {
+    type Output = Temperature;
+
+    fn sub(self, other: Temperature) -> Temperature {
+        Temperature(self.0 - other.0)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let quarters = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, quarters / 4, quarters % 4 * 25)
+    }
+}
+
+/// The die temperature sensor.
+pub struct Thermometer {
+    temp: TEMP,
+    offset: Temperature,
+}
+
+impl Thermometer {
+    /// Returns a new `Thermometer` using the `TEMP` peripheral, without
+    /// calibration offset.
+    pub fn new(temp: TEMP) -> Self {
+        // nRF51 anomaly 31: the sensor's offset has to be loaded by hand
+        #[cfg(feature = "v1")]
+        unsafe {
+            (0x4000_c504 as *mut u32).write_volatile(0)
+        };
+        Thermometer {
+            temp,
+            offset: Temperature::default(),
+        }
+    }
+
+    /// Releases the `TEMP` peripheral.
+    pub fn free(self) -> TEMP {
+        self.temp
+    }
+
+    /// Returns the calibration offset, added to each measurement.
+    pub fn offset(&self) -> Temperature {
+        self.offset
+    }
+
+    /// Sets the calibration offset, added to each measurement.
+    pub fn set_offset(&mut self, offset: Temperature) {
+        self.offset = offset;
+    }
+
+    /// Sets the calibration offset so that the temperature measured now is
+    /// `actual`.
+    pub fn calibrate(&mut self, actual: Temperature) {
+        self.offset = Temperature::default();
+        self.offset = actual - self.temperature();
+    }
+
+    /// Sets the calibration offset to the one kept in `store`, if any,
+    /// returning whether there was one.
+    pub fn load_offset<F: Flash>(&mut self, store: &KeyValueStore<F>) -> bool {
+        let mut buf = [0; 4];
+        match store.get(OFFSET_KEY, &mut buf) {
+            Some(4) => {
+                self.offset = Temperature(i32::from_le_bytes(buf));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Keeps the calibration offset in `store`, under [`OFFSET_KEY`].
+    pub fn save_offset<F: Flash>(
+        &self,
+        store: &mut KeyValueStore<F>,
+    ) -> Result<(), storage::Error> {
+        store.set(OFFSET_KEY, &self.offset.0.to_le_bytes())
+    }
+
+    /// Measures the temperature, blocking for about 36µs.
+    pub fn temperature(&mut self) -> Temperature {
+        self.start();
+        while self.temp.events_datardy.read().bits() == 0 {}
+        self.result()
+    }
+
+    /// Measures the temperature, waiting for the `TEMP` interrupt.
+    ///
+    /// The `TEMP` interrupt must be unmasked in the NVIC, with
+    /// [`handle_temp_interrupt()`] called from its handler.
+    pub async fn measure(&mut self) -> Temperature {
+        self.start();
+        DataReady.await;
+        self.result()
+    }
+
+    fn start(&mut self) {
+        self.temp.events_datardy.reset();
+        self.temp.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn result(&mut self) -> Temperature {
+        self.temp.events_datardy.reset();
+        let raw = self.temp.temp.read().bits();
+        self.temp.tasks_stop.write(|w| unsafe { w.bits(1) });
+        // nRF51 anomaly 28: negative values have to be sign-extended from
+        // 10 bits
+        #[cfg(feature = "v1")]
+        let raw = if raw & 0x200 != 0 {
+            raw | 0xffff_fc00
+        } else {
+            raw
+        };
+        Temperature(raw as i32) + self.offset
+    }
+}
+
+fn temp() -> &'static crate::pac::temp::RegisterBlock {
+    unsafe { &*TEMP::ptr() }
+}
+
+/// A future which completes once a measurement is ready.
+struct DataReady;
+
+impl Future for DataReady {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let temp = temp();
+        if temp.events_datardy.read().bits() != 0 {
+            return Poll::Ready(());
+        }
+        WAKER.register(cx.waker());
+        temp.intenset.write(|w| w.datardy().set());
+        Poll::Pending
+    }
+}
+
+/// Wakes any [`Thermometer`] waiting for a measurement.
+///
+/// Call this in the interrupt handler for `TEMP`.
+///
+/// Disables the interrupt; the event is cleared when the measurement is
+/// read.
+pub fn handle_temp_interrupt() {
+    let temp = temp();
+    if temp.events_datardy.read().bits() != 0 {
+        temp.intenclr.write(|w| w.datardy().clear());
+        WAKER.wake();
+    }
+}
@@ -0,0 +1,155 @@
+//! Typed durations and rates.
+//!
+//! The crate uses the [`fugit`] types for all durations and rates, so that
+//! units can't be mixed up. The extension traits make them easy to write:
+//!
+//! ```
+//! use microbit::time::{ExtU32, MillisDurationU32, RateExtU32, HertzU32};
+//!
+//! let duration: MillisDurationU32 = 250.millis();
+//! let rate: HertzU32 = 60.Hz();
+//! ```
+//!
+//! # Timers
+//!
+//! [`Timer`] is a future which completes after a given time, for use with
+//! the [`executor`](crate::executor). It uses a time driver on `RTC1`, which
+//! is started with [`init()`].
+
+pub use fugit::{ExtU32, ExtU64, HertzU32, MicrosDurationU32, MillisDurationU32, RateExtU32};
+
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use cortex_m::interrupt::{CriticalSection, Mutex};
+
+use crate::{
+    clock::{RtcClock, COUNTER_MASK, MIN_COMPARE_TICKS, TICKS_PER_SECOND},
+    executor::WakerCell,
+    hal::rtc::{RtcCompareReg, RtcInterrupt},
+    pac::RTC1,
+};
+
+/// A duration measured in [`RtcClock`] ticks.
+pub type RtcDuration = fugit::TimerDurationU64<TICKS_PER_SECOND>;
+
+/// An instant measured in [`RtcClock`] ticks.
+pub type RtcInstant = fugit::TimerInstantU64<TICKS_PER_SECOND>;
+
+/// Time driver for [`Timer`], running an [`RtcClock`]
+/// on `RTC1`.
+static CLOCK: Mutex<RefCell<Option<RtcClock<RTC1>>>> = Mutex::new(RefCell::new(None));
+
+/// The earliest deadline of the pending [`Timer`]s, which `COMPARE0` is set
+/// to, until it fires.
+static ALARM: Mutex<Cell<Option<RtcInstant>>> = Mutex::new(Cell::new(None));
+
+static WAKER: WakerCell = WakerCell::new();
+
+/// Starts the time driver used by [`Timer`] and [`now()`].
+///
+/// The LFCLK must be running (see
+/// [`start_lfclk()`](crate::clock::start_lfclk)), and the `RTC1` interrupt
+/// must be unmasked in the NVIC with [`handle_rtc_interrupt()`] called from
+/// its handler.
+pub fn init(rtc: RTC1) {
+    let mut clock = RtcClock::new(rtc);
+    clock.rtc().enable_event(RtcInterrupt::Compare0);
+    cortex_m::interrupt::free(|cs| *CLOCK.borrow(cs).borrow_mut() = Some(clock));
+}
+
+/// Returns the current instant of the time driver.
+///
+/// # Panics
+///
+/// Panics if [`init()`] hasn't been called.
+pub fn now() -> RtcInstant {
+    cortex_m::interrupt::free(|cs| with_clock(cs, |clock| clock.now()))
+}
+
+/// Updates the time driver and wakes any waiting [`Timer`].
+///
+/// Call this in the interrupt handler for `RTC1`.
+pub fn handle_rtc_interrupt() {
+    cortex_m::interrupt::free(|cs| {
+        with_clock(cs, |clock| {
+            clock.handle_interrupt();
+            let rtc = clock.rtc();
+            if rtc.is_event_triggered(RtcInterrupt::Compare0) {
+                rtc.reset_event(RtcInterrupt::Compare0);
+                rtc.disable_interrupt(RtcInterrupt::Compare0, None);
+                // The pending timers set the next alarm when polled again
+                ALARM.borrow(cs).set(None);
+            }
+        })
+    });
+    WAKER.wake();
+}
+
+fn with_clock<R>(cs: &CriticalSection, f: impl FnOnce(&mut RtcClock<RTC1>) -> R) -> R {
+    f(CLOCK
+        .borrow(cs)
+        .borrow_mut()
+        .as_mut()
+        .expect("time driver not initialised"))
+}
+
+/// A future which completes at a given instant.
+///
+/// Uses the time driver started by [`init()`]. Any number of timers can be
+/// awaited at once from one [`block_on()`](crate::executor::block_on): the
+/// alarm is set for the earliest.
+pub struct Timer {
+    deadline: RtcInstant,
+}
+
+impl Timer {
+    /// Returns a timer which completes at `deadline`.
+    pub fn at(deadline: RtcInstant) -> Self {
+        Timer { deadline }
+    }
+
+    /// Returns a timer which completes after `duration`.
+    pub fn after(duration: MillisDurationU32) -> Self {
+        Timer::at(now() + RtcDuration::millis(u64::from(duration.to_millis())))
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let deadline = self.deadline;
+        cortex_m::interrupt::free(|cs| {
+            with_clock(cs, |clock| {
+                let now = clock.now();
+                if now >= deadline {
+                    return Poll::Ready(());
+                }
+                let alarm = ALARM.borrow(cs);
+                let earliest = match alarm.get() {
+                    Some(alarm) => deadline < alarm,
+                    None => true,
+                };
+                if earliest {
+                    // A deadline more than one counter period away makes the
+                    // alarm fire early, and the timer is then polled again.
+                    let ticks = deadline
+                        .ticks()
+                        .max(now.ticks() + u64::from(MIN_COMPARE_TICKS));
+                    let rtc = clock.rtc();
+                    rtc.set_compare(RtcCompareReg::Compare0, ticks as u32 & COUNTER_MASK)
+                        .ok();
+                    rtc.enable_interrupt(RtcInterrupt::Compare0, None);
+                    alarm.set(Some(deadline));
+                }
+                WAKER.register(cx.waker());
+                Poll::Pending
+            })
+        })
+    }
+}
@@ -0,0 +1,211 @@
+//! Touch sensing on the pads and the v2's logo.
+//!
+//! # Scope
+//!
+//! This module provides a [`TouchPad`] driver, like MicroPython's
+//! `pin.is_touched()`, with:
+//! - polled state ([`is_touched()`](TouchPad::is_touched))
+//! - touch and release events, as [`ButtonEvent`]s
+//!   ([`poll_event()`](TouchPad::poll_event))
+//! - a [`TouchMode`] for each pad, resistive or capacitive.
+//!
+//! # Touch modes
+//!
+//! The big pads have 10MΩ pull-up resistors. In
+//! [resistive](TouchMode::Resistive) mode, a pad reads as touched when the
+//! finger pulls it down, which needs a finger on the GND pad as well.
+//!
+//! In [capacitive](TouchMode::Capacitive) mode, the pad is discharged then
+//! timed while its pull-up charges it again: a finger adds capacitance, which
+//! slows the charge. The pad is touched when the charge takes longer than a
+//! threshold, [calibrated](TouchPad::calibrate) from the untouched pad. This
+//! is the mode of the v2's logo, from `TouchPad::logo()`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     board::Board,
+//!     button::ButtonEvent,
+//!     touch::{TouchMode, TouchPad},
+//! };
+//!
+//! let board = Board::take().unwrap();
+//! // Don't touch the pad while it is calibrated
+//! let mut pad = TouchPad::new(board.pins.pad1.degrade(), TouchMode::Capacitive);
+//!
+//! loop {
+//!     if pad.poll_event() == Some(ButtonEvent::Pressed) {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! See a working example at `examples/touch_logo.rs`
+
+use crate::{
+    button::ButtonEvent,
+    hal::gpio::{Disconnected, Pin},
+    pac,
+};
+#[cfg(feature = "v2")]
+use crate::{gpio::LOGO, hal::gpio::Port};
+
+/// The number of charges timed for a capacitive measurement, of which the
+/// shortest is kept, as interrupts can lengthen the others.
+const CHARGES: usize = 4;
+
+/// The longest charge timed, in loop iterations.
+pub const MAX_CHARGE_TIME: u32 = 20_000;
+
+/// How a pad senses a touch.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TouchMode {
+    /// The pad is touched when a finger pulls it down to GND.
+    Resistive,
+    /// The pad is touched when a finger slows its charge.
+    Capacitive,
+}
+
+/// A touch-sensitive pad, with a pull-up resistor.
+pub struct TouchPad {
+    pin: Pin<Disconnected>,
+    mode: TouchMode,
+    threshold: u32,
+    touched: bool,
+}
+
+impl TouchPad {
+    /// Returns a new `TouchPad` on `pin`, in `mode`.
+    ///
+    /// In capacitive mode, the pad is [calibrated](TouchPad::calibrate), so
+    /// it mustn't be touched meanwhile.
+    pub fn new(pin: Pin<Disconnected>, mode: TouchMode) -> Self {
+        let mut pad = TouchPad {
+            pin,
+            mode: TouchMode::Resistive,
+            threshold: MAX_CHARGE_TIME,
+            touched: false,
+        };
+        pad.set_mode(mode);
+        pad
+    }
+
+    /// Returns a new capacitive `TouchPad` on the v2's logo.
+    ///
+    /// The logo is [calibrated](TouchPad::calibrate), so it mustn't be
+    /// touched meanwhile.
+    #[cfg(feature = "v2")]
+    pub fn logo(pin: LOGO<Disconnected>) -> Self {
+        TouchPad::new(pin.degrade(), TouchMode::Capacitive)
+    }
+
+    /// Disconnects the pin and gives it back.
+    pub fn free(self) -> Pin<Disconnected> {
+        self.port().pin_cnf[self.index()].reset();
+        self.pin
+    }
+
+    /// Returns the touch mode.
+    pub fn mode(&self) -> TouchMode {
+        self.mode
+    }
+
+    /// Sets the touch mode, calibrating the pad when switching to
+    /// capacitive mode.
+    pub fn set_mode(&mut self, mode: TouchMode) {
+        self.mode = mode;
+        self.port().pin_cnf[self.index()].write(|w| w.dir().input().input().connect());
+        if mode == TouchMode::Capacitive {
+            self.calibrate();
+        }
+        self.touched = self.is_touched();
+    }
+
+    /// Sets the capacitive threshold to a quarter above the charge time of
+    /// the untouched pad.
+    pub fn calibrate(&mut self) {
+        let untouched = self.charge_time();
+        self.threshold = (untouched + untouched / 4 + 1).min(MAX_CHARGE_TIME);
+    }
+
+    /// Returns the capacitive threshold, in loop iterations.
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Sets the capacitive threshold, in loop iterations, rather than
+    /// [calibrating](TouchPad::calibrate) it.
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.threshold = threshold;
+    }
+
+    /// Times the pad's charge through its pull-up, in loop iterations, up to
+    /// [`MAX_CHARGE_TIME`].
+    ///
+    /// This takes up to about 1ms per charge.
+    pub fn charge_time(&mut self) -> u32 {
+        let port = self.port();
+        let index = self.index();
+        let bit = 1 << index;
+        (0..CHARGES)
+            .map(|_| {
+                // Discharge the pad, then let it charge
+                port.outclr.write(|w| unsafe { w.bits(bit) });
+                port.pin_cnf[index].write(|w| w.dir().output().input().connect());
+                cortex_m::asm::delay(64);
+                port.pin_cnf[index].write(|w| w.dir().input().input().connect());
+                let mut time = 0;
+                while port.in_.read().bits() & bit == 0 && time < MAX_CHARGE_TIME {
+                    time += 1;
+                }
+                time
+            })
+            .min()
+            .unwrap_or(MAX_CHARGE_TIME)
+    }
+
+    /// Returns whether the pad is currently touched.
+    pub fn is_touched(&mut self) -> bool {
+        match self.mode {
+            TouchMode::Resistive => self.port().in_.read().bits() & 1 << self.index() == 0,
+            TouchMode::Capacitive => self.charge_time() > self.threshold,
+        }
+    }
+
+    /// Returns an event if the pad has been touched or released since the
+    /// last call: [`Pressed`](ButtonEvent::Pressed) when touched.
+    ///
+    /// Changes shorter than the interval between calls may be missed.
+    pub fn poll_event(&mut self) -> Option<ButtonEvent> {
+        let touched = self.is_touched();
+        if touched == self.touched {
+            return None;
+        }
+        self.touched = touched;
+        Some(if touched {
+            ButtonEvent::Pressed
+        } else {
+            ButtonEvent::Released
+        })
+    }
+
+    fn index(&self) -> usize {
+        usize::from(self.pin.pin())
+    }
+
+    #[cfg(feature = "v1")]
+    fn port(&self) -> &'static pac::gpio::RegisterBlock {
+        // Only this pin's registers are used
+        unsafe { &*pac::GPIO::ptr() }
+    }
+
+    #[cfg(feature = "v2")]
+    fn port(&self) -> &'static pac::p0::RegisterBlock {
+        // Only this pin's registers are used
+        match self.pin.port() {
+            Port::Port0 => unsafe { &*pac::P0::ptr() },
+            Port::Port1 => unsafe { &*pac::P1::ptr() },
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Watchdog timer.
+//!
+//! # Scope
+//!
+//! This module provides a [`Watchdog`] driver for the `WDT`, which resets
+//! the micro:bit unless it is fed in time. Each of up to 8 [`Feeder`]s has
+//! its own reload register, and all of them must be fed within the timeout,
+//! so that a task can't keep the micro:bit running while another is stuck.
+//!
+//! Once started, the watchdog can't be stopped or reconfigured until the
+//! next reset. After a watchdog reset,
+//! [`reset_reason()`](crate::power::reset_reason) reports it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use microbit::{
+//!     power,
+//!     time::ExtU32,
+//!     watchdog::{Config, Watchdog},
+//! };
+//!
+//! let p = microbit::Peripherals::take().unwrap();
+//! if power::reset_reason(&p.POWER).watchdog {
+//!     // the program got stuck last time
+//! }
+//!
+//! let config = Config {
+//!     timeout: 2_000.millis(),
+//!     ..Config::default()
+//! };
+//! let mut watchdog = Watchdog::start(p.WDT, config, 2).ok().unwrap();
+//! let mut sensor_task = watchdog.feeder(0).unwrap();
+//! let mut radio_task = watchdog.feeder(1).unwrap();
+//!
+//! loop {
+//!     sensor_task.feed();
+//!     radio_task.feed();
+//! }
+//! ```
+//!
+//! See a working example at `examples/watchdog.rs`
+
+use crate::{
+    pac::WDT,
+    time::{MillisDurationU32, RtcDuration},
+};
+
+/// The number of reload registers.
+pub const MAX_FEEDERS: usize = 8;
+
+/// The watchdog's settings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// The time after which the micro:bit is reset unless every feeder has
+    /// been fed, from about 0.5ms to 36 hours. Defaults to 1 second.
+    pub timeout: MillisDurationU32,
+    /// Whether the watchdog keeps counting while the CPU sleeps. Defaults to
+    /// `true`.
+    pub run_while_sleeping: bool,
+    /// Whether the watchdog keeps counting while a debugger halts the CPU.
+    /// Defaults to `false`.
+    pub run_while_halted: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            timeout: MillisDurationU32::millis(1_000),
+            run_while_sleeping: true,
+            run_while_halted: false,
+        }
+    }
+}
+
+/// The running watchdog timer, which hands out the [`Feeder`]s.
+pub struct Watchdog {
+    wdt: WDT,
+    feeders: usize,
+    /// The feeders handed out, one bit each.
+    taken: u8,
+}
+
+impl Watchdog {
+    /// Starts the watchdog, with `feeders` reload registers, from 1 to
+    /// [`MAX_FEEDERS`].
+    ///
+    /// Gives the `WDT` back if it is already running, as after a reset by a
+    /// bootloader which started it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `feeders` isn't in the range 1 to [`MAX_FEEDERS`].
+    pub fn start(wdt: WDT, config: Config, feeders: usize) -> Result<Self, WDT> {
+        assert!((1..=MAX_FEEDERS).contains(&feeders), "invalid feeder count");
+        if wdt.runstatus.read().runstatus().bit_is_set() {
+            return Err(wdt);
+        }
+        // The counter runs from the LFCLK, as the RTCs do
+        let ticks = RtcDuration::millis(u64::from(config.timeout.to_millis())).ticks();
+        wdt.crv
+            .write(|w| unsafe { w.bits(ticks.clamp(0xf, 0xffff_ffff) as u32) });
+        wdt.config.write(|w| {
+            let w = if config.run_while_sleeping {
+                w.sleep().run()
+            } else {
+                w.sleep().pause()
+            };
+            if config.run_while_halted {
+                w.halt().run()
+            } else {
+                w.halt().pause()
+            }
+        });
+        wdt.rren
+            .write(|w| unsafe { w.bits((1 << feeders as u32) - 1) });
+        wdt.tasks_start.write(|w| unsafe { w.bits(1) });
+        Ok(Watchdog {
+            wdt,
+            feeders,
+            taken: 0,
+        })
+    }
+
+    /// Returns the feeder for reload register `index`, or `None` if it is
+    /// out of range or was already taken.
+    pub fn feeder(&mut self, index: usize) -> Option<Feeder> {
+        if index >= self.feeders || self.taken & 1 << index != 0 {
+            return None;
+        }
+        self.taken |= 1 << index;
+        Some(Feeder { index: index as u8 })
+    }
+
+    /// Returns whether every feeder has been fed since the watchdog last
+    /// restarted its count.
+    pub fn is_fed(&self) -> bool {
+        self.wdt.reqstatus.read().bits() & self.wdt.rren.read().bits() == 0
+    }
+}
+
+/// A reload register of the [`Watchdog`], to be fed by one task.
+pub struct Feeder {
+    index: u8,
+}
+
+impl Feeder {
+    /// Feeds the watchdog: once all the feeders are fed, it restarts its
+    /// count.
+    pub fn feed(&mut self) {
+        // Each feeder only writes its own reload register
+        let wdt = unsafe { &*WDT::ptr() };
+        wdt.rr[usize::from(self.index)].write(|w| w.rr().reload());
+    }
+
+    /// Returns whether this feeder has been fed since the watchdog last
+    /// restarted its count.
+    pub fn is_fed(&self) -> bool {
+        let wdt = unsafe { &*WDT::ptr() };
+        wdt.reqstatus.read().bits() & 1 << self.index == 0
+    }
+}